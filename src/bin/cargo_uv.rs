@@ -1,20 +1,29 @@
 use std::env::args;
 
-use cargo_uv::{Cli, FOOTER, Packages, Result, Tasks, exit, setup_tracing};
-use rusty_viking::MietteDefaultConfig;
+use cargo_uv::{Cli, ErrorFormat, Result, exit, setup_error_hook, setup_tracing};
 
 fn main() -> Result<()> {
-    MietteDefaultConfig::init_set_panic_hook(Some(FOOTER.into()))?;
-    let args = args().collect();
-    let mut cli_args = Cli::cli_args(args, Some("cargo uv"), Some("uv"))?;
-    setup_tracing(&cli_args)?;
-
-    let packages = Packages::from(cli_args.get_metadata()?);
-    let mut tasks = Tasks::generate_tasks(&cli_args, packages)?;
-
-    tasks = tasks.run_all(&cli_args)?.join_all()?;
-    tracing::info!("Completed run, starting cleanup");
-    tasks.run_cleanup_tasks(&cli_args)?;
-
+    let args: Vec<String> = args().collect();
+    let error_format = ErrorFormat::from_args(&args);
+    setup_error_hook(error_format)?;
+    if let Err(report) = run(args) {
+        // Only the report is printed, so stderr is valid JSON. The `Debug` output of a
+        // [miette::Report] is rendered by the installed handler, the `JSONReportHandler` here.
+        if error_format.is_json() {
+            eprintln!("{report:?}");
+            exit!(1);
+        }
+        return Err(report);
+    }
     exit!();
 }
+
+fn run(args: Vec<String>) -> Result<()> {
+    let cli_args = Cli::cli_args(args, Some("cargo uv"), Some("uv"))?;
+    if let Some(shell) = cli_args.completions() {
+        Cli::write_completions(shell, &mut std::io::stdout());
+        exit!();
+    }
+    setup_tracing(&cli_args)?;
+    cargo_uv::run(cli_args).map(|_| ())
+}
@@ -1,48 +1,140 @@
 use std::{
-    path::PathBuf,
+    path::{Path, PathBuf},
     process::{Child, Command, Stdio},
+    time::{Duration, Instant},
 };
 
-use miette::IntoDiagnostic;
-use tracing::{debug, instrument};
+use miette::{IntoDiagnostic, bail};
+use semver::Version;
+use tracing::{debug, info, instrument};
 
-use crate::{Process, cli::Suppress};
+use crate::{OutputExt, PackageName, Process, cli::Suppress};
+
+/// Registry token for `cargo publish`, redacted from [`Debug`] so it isn't logged.
+#[derive(Clone, PartialEq, Eq)]
+pub struct RegistryToken(String);
+
+impl std::fmt::Debug for RegistryToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "RegistryToken(***)")
+    }
+}
+
+impl std::str::FromStr for RegistryToken {
+    type Err = String;
+
+    fn from_str(token: &str) -> Result<Self, Self::Err> {
+        if token.trim().is_empty() {
+            return Err("token can't be empty".to_string());
+        }
+        Ok(Self(token.to_string()))
+    }
+}
 
 #[derive(Debug, Default)]
 pub struct Cargo {
     manifest_path: Option<PathBuf>,
+    registry: Option<String>,
+    token: Option<RegistryToken>,
+    locked: bool,
+    offline: bool,
 }
 impl Cargo {
     pub fn new(manifest_path: Option<PathBuf>) -> Self {
-        Self { manifest_path }
+        Self {
+            manifest_path,
+            ..Default::default()
+        }
     }
 
+    /// Use the workspace root manifest, so the lockfile is generated at the root.
+    pub fn with_manifest_path(mut self, manifest_path: PathBuf) -> Self {
+        self.manifest_path = Some(manifest_path);
+        self
+    }
+
+    /// Publish to the registry from `.cargo/config.toml` instead of crates.io.
+    pub fn with_registry(mut self, registry: Option<String>) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    /// Token for the registry, only passed when not a dry run.
+    pub fn with_token(mut self, token: Option<RegistryToken>) -> Self {
+        self.token = token;
+        self
+    }
+
+    /// Require `Cargo.lock` to stay unchanged, passing `--locked`.
+    pub fn with_locked(mut self, locked: bool) -> Self {
+        self.locked = locked;
+        self
+    }
+
+    /// Run without accessing the network, passing `--offline`.
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Base cargo command for `subcommand`.
+    ///
+    /// `--manifest-path` is a subcommand argument so must come after it.
+    ///
+    /// Suppressed commands are quiet and uncoloured so they don't interleave with the task output.
     #[instrument(name = "Cargo::command")]
-    pub fn command(&self, supress_stdout: bool) -> Command {
+    pub fn command(&self, subcommand: &str, supress_stdout: bool) -> Command {
         let mut cargo = Command::new("cargo");
+        cargo.arg(subcommand);
         if !supress_stdout {
             debug!("Inherit");
             cargo.stdout(Stdio::inherit());
         } else {
             cargo.stdout(Stdio::piped());
+            cargo.args(["--quiet", "--color", "never"]);
         }
 
         if let Some(manifest_path) = self.manifest_path.as_ref() {
             cargo.arg("--manifest-path").arg(manifest_path);
         }
+        if self.offline {
+            cargo.arg("--offline");
+        }
 
         cargo
     }
 
+    /// Publishes the package.
     pub fn publish(
         &self,
+        package: &PackageName,
         suppress: Suppress,
         dry_run: bool,
         no_verify: bool,
         allow_dirty: bool,
     ) -> miette::Result<Child> {
-        let mut cargo = self.command(suppress.includes_cargo());
-        cargo.arg("publish");
+        let cargo = self.publish_command(package, suppress, dry_run, no_verify, allow_dirty);
+        Process::Spawn.run(cargo)?.try_into_child()
+    }
+
+    /// Builds the command for [`Cargo::publish`].
+    ///
+    /// Equivilent to: `cargo publish --package <package> [--locked] [--offline] [--dry-run] [--no-verify] [--allow-dirty] [--registry <registry>]`
+    ///
+    /// The token is passed in [`Cargo::token_env`] so it isn't logged or shown by `ps`.
+    pub fn publish_command(
+        &self,
+        package: &PackageName,
+        suppress: Suppress,
+        dry_run: bool,
+        no_verify: bool,
+        allow_dirty: bool,
+    ) -> Command {
+        let mut cargo = self.command("publish", suppress.includes_cargo());
+        cargo.args(["--package", package.as_str()]);
+        if self.locked {
+            cargo.arg("--locked");
+        }
         if dry_run {
             cargo.arg("--dry-run");
         }
@@ -51,25 +143,334 @@ impl Cargo {
             cargo.arg("--no-verify");
         }
 
-        // BUG: Be able to remove --allow-dirty #1
-        // cargo.args(["--allow-dirty"]);
+        // The version bump is committed before publishing so the tree is only dirty if allowed.
         if allow_dirty {
             cargo.args(["--allow-dirty"]);
         }
-        Process::Spawn.run(cargo)?.try_into_child()
+
+        if let Some(registry) = &self.registry {
+            cargo.args(["--registry", registry]);
+        }
+        // A dry run doesn't upload so doesn't need authenticating.
+        if let Some(RegistryToken(token)) = &self.token
+            && !dry_run
+        {
+            cargo.env(self.token_env(), token);
+        }
+        cargo
+    }
+
+    /// Environment variable cargo reads the token from, `CARGO_REGISTRY_TOKEN` for crates.io or
+    /// `CARGO_REGISTRIES_<NAME>_TOKEN` for `--registry`.
+    pub fn token_env(&self) -> String {
+        match &self.registry {
+            Some(registry) => format!(
+                "CARGO_REGISTRIES_{}_TOKEN",
+                registry.to_uppercase().replace('-', "_")
+            ),
+            None => String::from("CARGO_REGISTRY_TOKEN"),
+        }
+    }
+
+    /// Polls [`Cargo::is_published`] until `version` of the package is on the registry.
+    ///
+    /// Errors if the version isn't found within `timeout`.
+    #[instrument(skip(self))]
+    pub fn wait_for_publish(
+        &self,
+        package: &PackageName,
+        version: &Version,
+        timeout: Duration,
+        interval: Duration,
+    ) -> miette::Result<()> {
+        let start = Instant::now();
+        loop {
+            if self.is_published(package, version)? {
+                info!("{package} {version} is available on the registry");
+                return Ok(());
+            }
+            let elapsed = start.elapsed();
+            if elapsed >= timeout {
+                bail!(
+                    help = "Increase '--publish-wait' or check the publish succeeded.",
+                    "Timed out after {}s waiting for {package} {version} to appear on the registry",
+                    timeout.as_secs()
+                );
+            }
+            debug!("{package} {version} not on the registry yet, retrying in {interval:?}");
+            std::thread::sleep(interval.min(timeout - elapsed));
+        }
+    }
+
+    /// Returns `true` if exactly `version` of the package is on the registry, not only the newest
+    /// so a backport is found too.
+    ///
+    /// Equivilent to: `cargo info <package>@<version> [--registry <registry>]`
+    ///
+    /// Run outside the workspace as `cargo info` reports a workspace member's local version,
+    /// the workspace's `.cargo/config.toml` files are passed with `--config` for `--registry`.
+    pub fn is_published(&self, package: &PackageName, version: &Version) -> miette::Result<bool> {
+        let output = Process::Output
+            .run(self.info_command(package, version))?
+            .try_into_output()?;
+        if output.status.success() {
+            return Ok(Self::parse_info(&output.stdout()).as_ref() == Some(version));
+        }
+        let stderr = output.stderr();
+        if stderr.contains("could not find") {
+            return Ok(false);
+        }
+        bail!(
+            help = format!("Failed to run `cargo info {package}@{version}`"),
+            "{}",
+            stderr.trim_end()
+        )
+    }
+
+    /// Builds the command for [`Cargo::is_published`].
+    fn info_command(&self, package: &PackageName, version: &Version) -> Command {
+        let mut cargo = Command::new("cargo");
+        cargo.current_dir(std::env::temp_dir());
+        let manifest_dir = self.manifest_path.as_deref().and_then(Path::parent);
+        for config in manifest_dir.into_iter().flat_map(Path::ancestors) {
+            let config = config.join(".cargo/config.toml");
+            if config.is_file() {
+                cargo.arg("--config").arg(config);
+            }
+        }
+        cargo.args(["info", "--quiet", &format!("{package}@{version}")]);
+        if let Some(registry) = &self.registry {
+            cargo.args(["--registry", registry]);
+        }
+        cargo
     }
 
+    /// Parses the version from `cargo info` output, e.g. `version: 1.2.3 (latest 1.3.0)`.
+    fn parse_info(stdout: &str) -> Option<Version> {
+        stdout.lines().find_map(|line| {
+            let version = line.strip_prefix("version: ")?.split_whitespace().next()?;
+            Version::parse(version).ok()
+        })
+    }
+
+    /// Generates the lockfile, erroring if it isn't next to the manifest afterwards.
+    ///
+    /// Not run with '--locked', which only verifies the lockfile with [`Cargo::verify_lockfile`].
     pub fn generate_lockfile(&self) -> miette::Result<()> {
-        let mut cargo = self.command(true);
-        cargo.arg("generate-lockfile");
+        let cargo = self.lockfile_command();
+        let cmd = Process::display_command(&cargo);
 
         let output = Process::Output.run(cargo)?.try_into_output()?;
         if !output.status.success() {
             Err(
                 miette::miette!("{}", String::from_utf8(output.stderr).into_diagnostic()?)
-                    .context("While running `cargo generate-lockfile`"),
+                    .context(format!("While running `{cmd}`")),
             )?;
         }
+        if let Some(lockfile) = self.lockfile_path()
+            && !lockfile.exists()
+        {
+            bail!(
+                help = "Set '--manifest-path' to the workspace root Cargo.toml.",
+                "Cargo.lock was not generated at {}",
+                lockfile.display()
+            );
+        }
         Ok(())
     }
+
+    /// Builds the command for [`Cargo::generate_lockfile`].
+    ///
+    /// Equivilent to: `cargo generate-lockfile [--offline]`
+    pub fn lockfile_command(&self) -> Command {
+        self.command("generate-lockfile", true)
+    }
+
+    /// Checks `Cargo.lock` is up to date with the manifests.
+    ///
+    /// Equivilent to: `cargo metadata --locked --format-version 1 [--offline]`
+    pub fn verify_lockfile(&self) -> miette::Result<()> {
+        let mut cargo = self.command("metadata", true);
+        cargo.args(["--locked", "--format-version", "1"]);
+        let output = Process::Output.run(cargo)?.try_into_output()?;
+        if !output.status.success() {
+            bail!(
+                help = "Run `cargo generate-lockfile` and commit Cargo.lock, or drop '--locked'.",
+                "Cargo.lock is out of date: {}",
+                output.stderr().trim_end()
+            );
+        }
+        Ok(())
+    }
+
+    /// `Cargo.lock` next to the manifest, [`None`] if the manifest path isn't set.
+    pub fn lockfile_path(&self) -> Option<PathBuf> {
+        self.manifest_path
+            .as_ref()
+            .map(|manifest| manifest.with_file_name("Cargo.lock"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(cargo: &Command) -> Vec<String> {
+        cargo
+            .get_args()
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn publish_targets_package() {
+        let cargo = Cargo::new(Some(PathBuf::from("ws/Cargo.toml")));
+        let package = PackageName::from("b".to_string());
+
+        let publish = args(&cargo.publish_command(&package, Suppress::ALL, true, false, false));
+        assert_eq!(publish[0], "publish");
+        assert!(publish.windows(2).any(|a| a == ["--package", "b"]));
+        assert!(
+            publish
+                .windows(2)
+                .any(|a| a == ["--manifest-path", "ws/Cargo.toml"])
+        );
+    }
+
+    #[test]
+    fn publish_no_verify_only_when_set() {
+        let cargo = Cargo::default();
+        let package = PackageName::from("a".to_string());
+
+        let publish = args(&cargo.publish_command(&package, Suppress::NONE, false, true, false));
+        assert!(publish.contains(&"--no-verify".to_string()));
+        let publish = args(&cargo.publish_command(&package, Suppress::NONE, false, false, false));
+        assert!(!publish.contains(&"--no-verify".to_string()));
+    }
+
+    #[test]
+    fn suppressed_commands_are_quiet() {
+        let cargo = Cargo::default();
+        let package = PackageName::from("a".to_string());
+
+        for suppressed in [
+            args(&cargo.publish_command(&package, Suppress::CARGO, false, false, false)),
+            args(&cargo.publish_command(&package, Suppress::ALL, false, false, false)),
+            args(&cargo.publish_command(
+                &package,
+                Suppress::GIT | Suppress::CARGO,
+                false,
+                false,
+                false,
+            )),
+            args(&cargo.lockfile_command()),
+        ] {
+            assert!(
+                suppressed.contains(&"--quiet".to_string()),
+                "{suppressed:?}"
+            );
+            assert!(
+                suppressed.windows(2).any(|a| a == ["--color", "never"]),
+                "{suppressed:?}"
+            );
+        }
+
+        let publish = args(&cargo.publish_command(&package, Suppress::GIT, false, false, false));
+        assert!(!publish.contains(&"--quiet".to_string()));
+        assert!(!publish.contains(&"--color".to_string()));
+    }
+
+    #[test]
+    fn locked_and_offline() {
+        let cargo = Cargo::default().with_locked(true).with_offline(true);
+        let package = PackageName::from("a".to_string());
+
+        let publish = args(&cargo.publish_command(&package, Suppress::ALL, false, false, false));
+        assert!(publish.contains(&"--locked".to_string()));
+        assert!(publish.contains(&"--offline".to_string()));
+
+        let lockfile = args(&cargo.lockfile_command());
+        assert_eq!(lockfile[0], "generate-lockfile");
+        assert!(!lockfile.contains(&"update".to_string()));
+        assert!(!lockfile.contains(&"--workspace".to_string()));
+        assert!(!lockfile.contains(&"--locked".to_string()));
+        assert!(lockfile.contains(&"--offline".to_string()));
+
+        let cargo = Cargo::default();
+        let publish = args(&cargo.publish_command(&package, Suppress::ALL, false, false, false));
+        assert!(!publish.contains(&"--locked".to_string()));
+        assert!(!publish.contains(&"--offline".to_string()));
+        assert_eq!(args(&cargo.lockfile_command())[0], "generate-lockfile");
+    }
+
+    #[test]
+    fn publish_registry_and_token() {
+        let cargo = Cargo::default()
+            .with_registry(Some("internal".into()))
+            .with_token(Some("secret".parse().unwrap()));
+        let package = PackageName::from("a".to_string());
+
+        let token_env = |cmd: &Command| {
+            cmd.get_envs()
+                .find(|(key, _)| *key == "CARGO_REGISTRIES_INTERNAL_TOKEN")
+                .and_then(|(_, value)| value)
+                .map(|value| value.to_str().unwrap().to_string())
+        };
+
+        let command = cargo.publish_command(&package, Suppress::ALL, false, false, false);
+        let publish = args(&command);
+        assert!(publish.windows(2).any(|a| a == ["--registry", "internal"]));
+        assert!(!publish.contains(&"--token".to_string()));
+        assert!(!publish.contains(&"secret".to_string()));
+        assert_eq!(token_env(&command).as_deref(), Some("secret"));
+
+        let command = cargo.publish_command(&package, Suppress::ALL, true, false, false);
+        assert!(
+            args(&command)
+                .windows(2)
+                .any(|a| a == ["--registry", "internal"])
+        );
+        assert_eq!(token_env(&command), None);
+
+        let crates_io = Cargo::default().with_token(Some("secret".parse().unwrap()));
+        assert_eq!(crates_io.token_env(), "CARGO_REGISTRY_TOKEN");
+        let dashed = Cargo::default().with_registry(Some("my-registry".into()));
+        assert_eq!(dashed.token_env(), "CARGO_REGISTRIES_MY_REGISTRY_TOKEN");
+    }
+
+    #[test]
+    fn parse_info_version() {
+        let stdout = "a #cli\nA crate\nversion: 0.1.1 (latest 0.2.0)\nlicense: MIT\n";
+        assert_eq!(Cargo::parse_info(stdout), Some(Version::new(0, 1, 1)));
+        assert_eq!(
+            Cargo::parse_info("version: 1.0.0-rc.2\n"),
+            Some("1.0.0-rc.2".parse().unwrap())
+        );
+        assert_eq!(Cargo::parse_info("a\nlicense: MIT\n"), None);
+    }
+
+    #[test]
+    fn info_command_runs_outside_the_workspace() {
+        let manifest = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+        let cargo = Cargo::new(Some(manifest)).with_registry(Some("internal".into()));
+        let command = cargo.info_command(&"a".into(), &Version::new(0, 1, 1));
+        assert_eq!(
+            command.get_current_dir(),
+            Some(std::env::temp_dir().as_path())
+        );
+        let info = args(&command);
+        assert!(
+            info.windows(2).any(|a| a == ["info", "--quiet"]),
+            "{info:?}"
+        );
+        assert!(info.contains(&"a@0.1.1".to_string()));
+        assert!(info.windows(2).any(|a| a == ["--registry", "internal"]));
+    }
+
+    #[test]
+    fn registry_token_is_redacted() {
+        let token: RegistryToken = "secret".parse().unwrap();
+        assert!(!format!("{token:?}").contains("secret"));
+        assert!("".parse::<RegistryToken>().is_err());
+    }
 }
@@ -5,6 +5,8 @@ use rusty_viking::EnumDisplay;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, clap::ValueEnum, Default, EnumDisplay, Hash)]
 #[Lower]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum Action {
     #[value(help = "Bump the version 1 prerelease level.")]
     Pre,
@@ -19,6 +21,8 @@ pub enum Action {
     #[value(help = "Print the current version of the package.")]
     #[default]
     Print,
+    #[value(help = "Verify the package versions match their manifests and the lockfile.")]
+    Verify,
     /// Display the layout of the members in the workspace.
     Tree,
 }
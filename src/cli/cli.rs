@@ -1,16 +1,31 @@
-use std::{ops::Deref, path::PathBuf};
+use std::{
+    io::BufRead,
+    ops::Deref,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-#[cfg(feature = "unstable")]
-use crate::Branch;
 use crate::current_span;
 use crate::{
-    Action, Cargo, Git, GitBuilder, Result,
-    cli::{CARGO_HEADER, GitOps, Manifest, Suppress, Workspace},
+    Action, Branch, Cargo, Git, GitBuilder, RegistryToken, Result,
+    cli::{
+        CARGO_HEADER, Config, ErrorFormat, GitOps, LogFormat, Manifest, MetadataMode, OutputFormat,
+        PrintFormat, Suppress, Workspace,
+    },
+    error::{InvalidArguments, InvalidVersionInput, ManifestNotFoundError, UvError},
+    normalize_path,
+    version::pre_release::{InvalidPrerelease, parse_prerelease},
 };
 use cargo_metadata::Metadata;
-use clap::{CommandFactory, FromArgMatches};
+use clap::{
+    ArgMatches, CommandFactory, FromArgMatches,
+    error::{ContextKind, ContextValue, ErrorKind},
+    parser::ValueSource,
+};
+use clap_complete::Shell;
 use miette::IntoDiagnostic;
 use semver::Version;
+use std::error::Error;
 use tracing::{Level, debug, instrument};
 // use clap::ValueHint;
 
@@ -27,11 +42,11 @@ pub const CLAP_STYLING: clap::builder::styling::Styles = clap::builder::styling:
 #[command(about, long_about=None, version)]
 #[command(styles=CLAP_STYLING)]
 pub struct Cli {
-    /// Action to affect the package version.
+    /// Action to affect the package version, a version e.g. `cargo uv 1.2.3` implies `set`.
     #[arg(default_value_t = Action::default())]
     pub action: Action,
 
-    #[arg(long, help="Sets the pre-release segment for the new version.", value_parser = semver::Prerelease::new)]
+    #[arg(long, help="Sets the pre-release segment for the new version.", value_parser = parse_prerelease)]
     pub pre: Option<semver::Prerelease>,
 
     #[arg(long, help = "Sets the build metadata for the new version.")]
@@ -41,17 +56,85 @@ pub struct Cli {
     #[arg(short, long, help_heading = CARGO_HEADER)]
     pub cargo_publish: bool,
 
-    /// What to suppress from stdout
-    #[arg(short = 'Q', long, default_value = Suppress::default())]
-    pub suppress: Suppress,
+    /// What to suppress from stdout, combine with `-Q git,cargo` or `-Q git -Q cargo`.
+    #[arg(short = 'Q', long, value_delimiter = ',', default_value = Suppress::default())]
+    pub suppress: Vec<Suppress>,
+
+    /// Format of the output, `json` prints a summary of the release for CI.
+    #[arg(long, value_name = "FORMAT", default_value = OutputFormat::default())]
+    pub output_format: OutputFormat,
+
+    /// Format of the errors, `json` prints the code, message and help for CI.
+    #[arg(long, value_name = "FORMAT", default_value = ErrorFormat::default())]
+    pub error_format: ErrorFormat,
+
+    /// Format of the logs, `json` prints an object per line for log aggregators.
+    #[arg(long, value_name = "FORMAT", default_value = LogFormat::default())]
+    pub log_format: LogFormat,
+
+    /// Format for `print`, `json` or a template with '{name}' and '{version}'.
+    #[arg(long, value_name = "FORMAT", value_parser = PrintFormat::parse)]
+    pub format: Option<PrintFormat>,
+
+    /// Only print the version for `print`, without a trailing newline.
+    #[arg(long, conflicts_with = "format")]
+    pub bare: bool,
+
+    /// Skip publishing members with `publish = false`, the default.
+    #[arg(long, requires = "cargo_publish", overrides_with = "include_private", help_heading = CARGO_HEADER)]
+    pub exclude_private: bool,
+
+    /// Publish members with `publish = false` too, they are always bumped and tagged.
+    #[arg(long, requires = "cargo_publish", overrides_with = "exclude_private", help_heading = CARGO_HEADER)]
+    pub include_private: bool,
 
     /// adds 'no_verify' to cargo publish command.
     #[arg(long, help_heading = CARGO_HEADER)]
     pub no_verify: bool,
 
+    /// Registry from `.cargo/config.toml` to publish to instead of crates.io.
+    #[arg(long, value_name = "NAME", value_parser = parse_registry, help_heading = CARGO_HEADER)]
+    pub registry: Option<String>,
+
+    /// Token for the registry, not passed on a dry run.
+    #[arg(long, value_name = "TOKEN", help_heading = CARGO_HEADER)]
+    pub token: Option<RegistryToken>,
+
+    /// Require Cargo.lock to be up to date, it is verified and never regenerated.
+    #[arg(long, help_heading = CARGO_HEADER)]
+    pub locked: bool,
+
+    /// Run cargo without accessing the network.
+    #[arg(long, help_heading = CARGO_HEADER)]
+    pub offline: bool,
+
+    /// Wait up to SECS for each published version to appear on the registry.
+    #[arg(
+        long,
+        value_name = "SECS",
+        requires = "cargo_publish",
+        conflicts_with = "offline",
+        help_heading = CARGO_HEADER
+    )]
+    pub publish_wait: Option<u64>,
+
+    /// Seconds between registry checks for '--publish-wait'.
+    #[arg(
+        long,
+        value_name = "SECS",
+        default_value_t = 5,
+        help_heading = CARGO_HEADER
+    )]
+    pub publish_poll_interval: u64,
+
     #[arg(short = 'n', long, help = "Allows program to work in a dirty repo.")]
     pub allow_dirty: bool,
 
+    /// Allow files matching GLOB to be dirty, can be repeated. Paths are relative to the git root
+    /// and `*` also matches `/`.
+    #[arg(long, value_name = "GLOB")]
+    pub dirty_allow: Vec<String>,
+
     #[command(flatten)]
     pub git_ops: GitOps,
 
@@ -65,9 +148,50 @@ pub struct Cli {
     #[arg(short, long, help = "Bypass version bump checks.")]
     pub force_version: bool,
 
+    #[arg(long, help = "Allow setting a version lower than the current version.")]
+    pub allow_downgrade: bool,
+
+    #[arg(
+        long,
+        help = "Release a crate that has never been released, the repository must have no tags.",
+        long_help = "Release a crate that has never been released, the repository must have no tags. Setting the current version still releases it, e.g. `cargo uv set 0.1.0 --first-release --git-tag` for a crate already at 0.1.0. The changelog lists every commit and '--require-up-to-date' skips a branch that hasn't been pushed."
+    )]
+    pub first_release: bool,
+
+    #[arg(
+        long,
+        help = "Update the version requirement of workspace members depending on a changed package."
+    )]
+    pub update_dependents: bool,
+
+    #[arg(
+        long,
+        help = "Change every selected member and workspace.package.version to the same version."
+    )]
+    pub lockstep: bool,
+
     #[arg(short, long, help = "Allows git tag to occur in a dirty repo.")]
     pub dry_run: bool,
 
+    /// Print the tasks without running them or git, only `cargo metadata` is run.
+    #[arg(long)]
+    pub plan: bool,
+
+    #[arg(long, help = "Keep the written manifests if a later task fails.")]
+    pub no_rollback: bool,
+
+    /// Shell command to run before the versions change, can be repeated.
+    #[arg(long, value_name = "CMD")]
+    pub pre_hook: Vec<String>,
+
+    /// Shell command to run after the release tasks, can be repeated.
+    #[arg(long, value_name = "CMD")]
+    pub post_hook: Vec<String>,
+
+    /// Kill a spawned `cargo publish` or `git push` still running after SECS.
+    #[arg(long, value_name = "SECS")]
+    pub process_timeout: Option<u64>,
+
     #[command(flatten)]
     pub color: colorchoice_clap::Color,
 
@@ -75,23 +199,47 @@ pub struct Cli {
     pub verbosity: clap_verbosity_flag::Verbosity,
 
     /// New version to set. Ignored if action isn't set.
-    #[arg(value_parser = Version::parse)]
+    ///
+    /// Can be omitted when using `--pre` to only change the pre-release. Missing components are
+    /// zero-filled, e.g. `1.2` is `1.2.0`.
+    #[arg(value_parser = parse_set_version)]
     pub set_version: Option<Version>,
 
+    /// New version to set instead of the positional, `-` reads one line from stdin.
+    #[arg(
+        long = "set-version",
+        value_name = "VERSION",
+        conflicts_with = "set_version"
+    )]
+    pub set_version_input: Option<String>,
+
+    /// Environment variable holding the new version to set, e.g. `RELEASE_VERSION`.
+    #[arg(
+        long,
+        value_name = "VAR",
+        conflicts_with_all = ["set_version", "set_version_input"]
+    )]
+    pub set_version_from_env: Option<String>,
+
     #[arg(skip)]
     metadata: Option<Metadata>,
 
+    #[arg(skip)]
+    metadata_mode: MetadataMode,
+
     /// Display the tasks that will be run.
     #[arg(long)]
     display_tasks: bool,
+
+    /// Print the completion script for the shell then exit.
+    #[arg(long, value_name = "SHELL", hide = true)]
+    completions: Option<Shell>,
 }
 
 impl Cli {
     pub fn root_dir(&self) -> Result<PathBuf> {
         let root = match self.manifest.manifest_path.clone() {
-            Some(p) => p
-                .canonicalize()
-                .into_diagnostic()?
+            Some(p) => normalize_path(&p.canonicalize().into_diagnostic()?)
                 .parent()
                 .map(|p| p.to_path_buf())
                 .ok_or_else(|| {
@@ -126,17 +274,63 @@ impl Cli {
 
     #[instrument(skip_all, fields(root_cargo_file), name = "Cli::refresh_metadata")]
     pub fn refresh_metadata(&mut self) -> Result<()> {
-        let mut cmd = self.manifest.metadata();
-        cmd.no_deps(); // Confirmed does have an impact on performance.
-        self.metadata = Some(cmd.exec().into_diagnostic()?);
+        if let Some(manifest_path) = &self.manifest.manifest_path
+            && !manifest_path.exists()
+        {
+            Err(ManifestNotFoundError {
+                help: Some("Check the '--manifest-path' points to a Cargo.toml that exists."),
+                source_code: Some(manifest_path.display().to_string()),
+                label: Some((0, manifest_path.display().to_string().len())),
+                label_msg: "Does not exist.",
+                msg: "The manifest path does not exist.".into(),
+            })?;
+        }
+        self.metadata = Some(self.read_metadata()?);
         Ok(())
     }
 
+    /// Runs `cargo metadata` without caching the result, e.g. after switching branch.
+    pub fn read_metadata(&self) -> Result<Metadata> {
+        self.manifest
+            .metadata_for(self.metadata_mode)
+            .exec()
+            .into_diagnostic()
+    }
+
+    /// Caches the metadata for [`Cli::get_metadata`], read once per run.
+    pub(crate) fn cache_metadata(&mut self, metadata: Metadata) -> &Metadata {
+        self.metadata.insert(metadata)
+    }
+
+    pub fn metadata_mode(&self) -> MetadataMode {
+        self.metadata_mode
+    }
+
+    /// Sets how `cargo metadata` is run, dropping the cached metadata if the mode changes.
+    pub fn set_metadata_mode(&mut self, mode: MetadataMode) {
+        if self.metadata_mode != mode {
+            self.metadata_mode = mode;
+            self.metadata = None;
+        }
+    }
+
     #[instrument(skip_all, fields(self.verbosity), name ="Cli::tracing_level")]
     pub fn tracing_level(&self) -> Option<Level> {
         self.verbosity.tracing_level()
     }
 
+    /// Whether to color stdout, from `--color` then `NO_COLOR`, `CLICOLOR_FORCE` and if stdout
+    /// is a terminal.
+    pub fn color(&self) -> bool {
+        self.color.write_global();
+        anstream::AutoStream::choice(&std::io::stdout()) != anstream::ColorChoice::Never
+    }
+
+    /// `-q` was passed, only the result of the action is printed.
+    pub fn quiet(&self) -> bool {
+        self.verbosity.is_silent()
+    }
+
     #[instrument(skip_all, fields(self.action), name ="Cli::action")]
     pub fn action(&self) -> Action {
         let action = self.action;
@@ -156,19 +350,34 @@ impl Cli {
             return Ok(());
         }
         let git = self.git()?;
-        let files: crate::GitFiles = git.dirty_files()?;
-        let count = files.len();
+        let files: crate::GitFiles = git.dirty_files()?.without_matching(&self.dirty_allow);
+        let (staged, unstaged) = (files.staged(), files.unstaged());
 
-        if count != 0 {
-            miette::bail!(
-                help = "Use '--allow-dirty' to avoid this check.",
+        let mut problems = Vec::new();
+        if !staged.is_empty() {
+            problems.push(format!(
+                "{} file/s have staged changes that would be included in the release commit.{}",
+                staged.len(),
+                staged
+            ));
+        }
+        if !unstaged.is_empty() {
+            problems.push(format!(
                 "{} file/s in the working directory contain changes that were not yet committed into git.{}",
-                count,
-                files
-            )
-        } else {
-            Ok(())
+                unstaged.len(),
+                unstaged
+            ));
         }
+        if problems.is_empty() {
+            return Ok(());
+        }
+        let help = match staged.is_empty() {
+            true => "Use '--allow-dirty' to avoid this check.",
+            false => {
+                "Unstage the files with 'git restore --staged <file>', or use '--allow-dirty' to avoid this check."
+            }
+        };
+        miette::bail!(help = help, "{}", problems.join("\n"))
     }
 
     #[instrument(skip_all, fields(self.dry_run), name ="Cli::dry_run")]
@@ -176,6 +385,15 @@ impl Cli {
         self.dry_run
     }
 
+    /// Only the tasks are printed, git isn't required.
+    pub fn plan(&self) -> bool {
+        self.plan
+    }
+
+    pub fn no_rollback(&self) -> bool {
+        self.no_rollback
+    }
+
     #[instrument(skip_all, fields(message), name = "Cli::git_message")]
     pub fn git_message(&self) -> Option<String> {
         let msg = self.git_ops.message.clone();
@@ -184,12 +402,63 @@ impl Cli {
         msg
     }
 
+    #[instrument(skip_all, fields(template), name = "Cli::message_template")]
+    pub fn message_template(&self) -> Option<&str> {
+        let template = self.git_ops.message_template.as_deref();
+        current_span!().record("template", template);
+        tracing::debug!("Fetching the git message template if available.");
+        template
+    }
+
+    pub fn completions(&self) -> Option<Shell> {
+        self.completions
+    }
+
+    /// Writes the completion script for `cargo-uv`.
+    ///
+    /// Shells complete the `cargo-uv` binary, `cargo uv` is completed by cargo's own
+    /// completions which call `cargo-uv`.
+    pub fn write_completions(shell: Shell, buf: &mut dyn std::io::Write) {
+        let mut cmd = Cli::command();
+        clap_complete::generate(shell, &mut cmd, "cargo-uv", buf);
+    }
+
+    pub fn changelog(&self) -> Option<&Path> {
+        self.git_ops.changelog.as_deref()
+    }
+
     #[instrument(skip_all, fields(self.force_version), name ="Cli::force_version")]
     pub fn force_version(&self) -> bool {
         tracing::debug!("Checking if forcing version.");
         self.force_version
     }
 
+    #[instrument(skip_all, fields(self.allow_downgrade), name ="Cli::allow_downgrade")]
+    pub fn allow_downgrade(&self) -> bool {
+        tracing::debug!("Checking if allowing downgrade.");
+        self.allow_downgrade
+    }
+
+    pub fn first_release(&self) -> bool {
+        self.first_release
+    }
+
+    pub fn update_dependents(&self) -> bool {
+        self.update_dependents
+    }
+
+    pub fn lockstep(&self) -> bool {
+        self.lockstep
+    }
+
+    pub fn pre_hooks(&self) -> &[String] {
+        &self.pre_hook
+    }
+
+    pub fn post_hooks(&self) -> &[String] {
+        &self.post_hook
+    }
+
     #[instrument(skip_all, fields(git_tag), name = "Cli::git_tag")]
     pub fn git_tag(&self) -> bool {
         let tag = self.git_ops.git_tag;
@@ -198,6 +467,75 @@ impl Cli {
         tag
     }
 
+    #[instrument(skip_all, fields(annotate), name = "Cli::git_annotate")]
+    pub fn git_annotate(&self) -> bool {
+        let annotate = self.git_ops.annotate;
+        current_span!().record("annotate", annotate);
+        debug!("Checking for annotate tag flag...");
+        annotate
+    }
+
+    #[instrument(skip_all, fields(sign), name = "Cli::git_sign")]
+    pub fn git_sign(&self) -> bool {
+        let sign = self.git_ops.sign;
+        current_span!().record("sign", sign);
+        debug!("Checking for sign tag flag...");
+        sign
+    }
+
+    pub fn sign_commit(&self) -> bool {
+        self.git_ops.sign_commit
+    }
+
+    pub fn commit_author(&self) -> Option<&str> {
+        self.git_ops.commit_author.as_deref()
+    }
+
+    pub fn git_amend(&self) -> bool {
+        self.git_ops.amend
+    }
+
+    pub fn commit_paths(&self) -> bool {
+        self.git_ops.commit_paths
+    }
+
+    /// Whether `Cargo.lock` is regenerated and committed with '--git-tag'.
+    ///
+    /// Without '--include-lockfile' or '--no-lockfile' it is only skipped when the root package
+    /// has no binary targets, which needs the metadata from [`Cli::get_metadata`].
+    pub fn include_lockfile(&self) -> bool {
+        if self.git_ops.include_lockfile || self.git_ops.no_lockfile {
+            return self.git_ops.include_lockfile;
+        }
+        self.metadata()
+            .and_then(Metadata::root_package)
+            .is_none_or(|package| package.targets.iter().any(|target| target.is_bin()))
+    }
+
+    pub fn git_force(&self) -> bool {
+        self.git_ops.force
+    }
+
+    /// Template for the git tag built from `--tag-format` or `--tag-prefix`.
+    #[instrument(skip_all, fields(tag_format), name = "Cli::tag_format")]
+    pub fn tag_format(&self) -> String {
+        let tag_format = match (&self.git_ops.tag_format, &self.git_ops.tag_prefix) {
+            (Some(format), _) => format.clone(),
+            (None, Some(prefix)) => format!("{prefix}{{version}}"),
+            (None, None) => String::from("{version}"),
+        };
+        current_span!().record("tag_format", &tag_format);
+        tag_format
+    }
+
+    /// Refspec to push `tag` with, from `--tag-refspec` or `tags/<tag>`.
+    pub fn tag_refspec(&self, tag: &str) -> String {
+        match &self.git_ops.tag_refspec {
+            Some(refspec) => refspec.replace("{tag}", tag),
+            None => format!("tags/{tag}"),
+        }
+    }
+
     #[instrument(skip_all, fields(git_push), name = "Cli::git_push")]
     pub fn git_push(&self) -> bool {
         let push = self.git_ops.git_push;
@@ -206,6 +544,36 @@ impl Cli {
         push
     }
 
+    #[instrument(skip_all, fields(push_branch), name = "Cli::push_branch")]
+    pub fn push_branch(&self) -> bool {
+        let push_branch = self.git_ops.push_branch;
+        current_span!().record("push_branch", push_branch);
+        debug!("Checking for push branch flag...");
+        push_branch
+    }
+
+    pub fn no_tag_push(&self) -> bool {
+        self.git_ops.no_tag_push
+    }
+
+    pub fn require_up_to_date(&self) -> bool {
+        self.git_ops.require_up_to_date
+    }
+
+    pub fn git_remote(&self) -> Option<&str> {
+        self.git_ops.remote.as_deref()
+    }
+
+    pub fn push_retries(&self) -> u32 {
+        self.git_ops.push_retries
+    }
+
+    /// Delay before the push retry `attempt`, starting at 1.
+    pub fn push_retry_delay(&self, attempt: u32) -> Duration {
+        let delay = Duration::from_millis(self.git_ops.push_retry_delay);
+        delay.saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+    }
+
     #[instrument(skip_all, fields(cargo_publish), name = "Cli::cargo_publish")]
     pub fn cargo_publish(&self) -> bool {
         let publish = self.cargo_publish;
@@ -218,18 +586,47 @@ impl Cli {
         self.no_verify
     }
 
-    #[cfg(feature = "unstable")]
+    pub fn locked(&self) -> bool {
+        self.locked
+    }
+
+    pub fn offline(&self) -> bool {
+        self.offline
+    }
+
+    /// `false` with `--include-private`, the last of it and `--exclude-private` wins.
+    pub fn exclude_private(&self) -> bool {
+        !self.include_private
+    }
+
+    /// Timeout for [`Task::WaitForPublish`].
+    ///
+    /// [`Task::WaitForPublish`]: crate::Task::WaitForPublish
+    pub fn publish_wait(&self) -> Option<Duration> {
+        self.publish_wait.map(Duration::from_secs)
+    }
+
+    pub fn publish_poll_interval(&self) -> Duration {
+        Duration::from_secs(self.publish_poll_interval)
+    }
+
+    /// Longest a spawned task may run for in [`Tasks::join_all`].
+    ///
+    /// [`Tasks::join_all`]: crate::Tasks::join_all
+    pub fn process_timeout(&self) -> Option<Duration> {
+        self.process_timeout.map(Duration::from_secs)
+    }
+
     pub fn git_branch(&self) -> Branch {
         self.git_ops.branch()
     }
 
-    #[cfg(feature = "unstable")]
     pub fn is_current_branch(&self) -> bool {
         self.git_branch().is_current()
     }
 
     pub fn display_tasks(&self) -> bool {
-        self.display_tasks
+        (self.display_tasks || self.plan) && self.output_format.is_human() && !self.quiet()
     }
 
     pub fn workspace_package(&self) -> bool {
@@ -240,8 +637,13 @@ impl Cli {
         &self.workspace
     }
 
+    /// [Git] run in `--git-root`, or the directory of the manifest.
     pub fn git(&self) -> Result<Git<PathBuf>> {
-        Ok(GitBuilder::new().root_directory(self.root_dir()?).build())
+        let root = match &self.git_ops.git_root {
+            Some(git_root) => git_root.clone(),
+            None => self.root_dir()?,
+        };
+        Ok(GitBuilder::new().root_directory(root).build())
     }
 
     pub fn cargo(&self) -> Result<Cargo> {
@@ -255,7 +657,11 @@ impl Cli {
         } else {
             cargo = Cargo::default();
         }
-        Ok(cargo)
+        Ok(cargo
+            .with_registry(self.registry.clone())
+            .with_token(self.token.clone())
+            .with_locked(self.locked)
+            .with_offline(self.offline))
     }
 
     pub fn pre(&self) -> Option<&semver::Prerelease> {
@@ -270,8 +676,30 @@ impl Cli {
         self.set_version.clone()
     }
 
+    /// Everything is suppressed for [`OutputFormat::Json`] so stdout is only the JSON, and
+    /// with `-q`.
     pub fn suppress(&self) -> Suppress {
-        self.suppress
+        match self.output_format {
+            OutputFormat::Json => Suppress::ALL,
+            OutputFormat::Human if self.quiet() => Suppress::ALL,
+            OutputFormat::Human => self.suppress.iter().copied().collect(),
+        }
+    }
+
+    /// `--bare` or `--format`, defaulting to [`PrintFormat::Human`].
+    pub fn print_format(&self) -> PrintFormat {
+        match (self.bare, &self.format) {
+            (true, _) => PrintFormat::Bare,
+            (false, format) => format.clone().unwrap_or_default(),
+        }
+    }
+
+    pub fn log_format(&self) -> LogFormat {
+        self.log_format
+    }
+
+    pub fn output_format(&self) -> OutputFormat {
+        self.output_format
     }
 
     // /// Partition workspace members into those selected and those excluded.
@@ -324,9 +752,373 @@ impl Cli {
         if let Some(bin) = bin_name {
             cli.set_bin_name(bin);
         }
-        cli = cli.mut_arg("set_version", |a| a.required_if_eq("action", Action::Set));
         cli = cli.next_line_help(false);
 
-        Cli::from_arg_matches(&cli.get_matches_from(&input)).into_diagnostic()
+        let matches = match cli.clone().try_get_matches_from(&input) {
+            Ok(m) => m,
+            Err(e) if let Some(m) = Cli::imply_set(&cli, &input, &e) => m,
+            Err(e) => {
+                if let Some(invalid_pre) = e
+                    .source()
+                    .and_then(|s| s.downcast_ref::<InvalidPrerelease>())
+                {
+                    return Err(invalid_pre.clone().into());
+                }
+                if let Some(invalid_version) = e
+                    .source()
+                    .and_then(|s| s.downcast_ref::<InvalidVersionInput>())
+                {
+                    return Err(invalid_version.clone().into());
+                }
+                if matches!(e.kind(), ErrorKind::DisplayHelp | ErrorKind::DisplayVersion) {
+                    e.exit()
+                }
+                return Err(UvError::clap(&e, &input).into());
+            }
+        };
+        let mut cli = Cli::from_arg_matches(&matches).into_diagnostic()?;
+        let config = Config::load(&cli.config_dir())?;
+        cli.apply_config(config, &matches);
+        cli.read_set_version(std::io::stdin().lock())?;
+        cli.validate()?;
+        Ok(cli)
+    }
+}
+
+impl Cli {
+    /// `cargo uv 1.2.3` is `cargo uv set 1.2.3`, so a version given as the action implies
+    /// [`Action::Set`].
+    ///
+    /// Returns the matches with `set` inserted before the version if they parse.
+    fn imply_set(cli: &clap::Command, input: &[String], error: &clap::Error) -> Option<ArgMatches> {
+        if error.kind() != ErrorKind::InvalidValue {
+            return None;
+        }
+        match error.get(ContextKind::InvalidArg) {
+            Some(ContextValue::String(arg)) if arg == "[ACTION]" => {}
+            _ => return None,
+        }
+        let Some(ContextValue::String(value)) = error.get(ContextKind::InvalidValue) else {
+            return None;
+        };
+        Version::parse(value).ok()?;
+        // The same text may be the value of an option, so the first that parses is the action.
+        input
+            .iter()
+            .enumerate()
+            .skip(1)
+            .filter(|(_, arg)| *arg == value)
+            .find_map(|(idx, _)| {
+                let mut input = input.to_vec();
+                input.insert(idx, Action::Set.to_string());
+                cli.clone().try_get_matches_from(input).ok()
+            })
+    }
+
+    /// Directory of the `--manifest-path`, or the current directory, to read the [`Config`] from.
+    fn config_dir(&self) -> PathBuf {
+        self.manifest
+            .manifest_path
+            .as_deref()
+            .and_then(Path::parent)
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| PathBuf::from("."))
+    }
+
+    /// Rejects an action with flags that can't apply to it, e.g. `print --git-tag`, before
+    /// anything is run.
+    pub fn validate(&self) -> std::result::Result<(), InvalidArguments> {
+        let action = self.action;
+        if action == Action::Set && self.set_version.is_none() && self.pre.is_none() {
+            return Err(InvalidArguments::new(
+                action,
+                "",
+                "Requires a version or '--pre'.",
+                "The `set` action requires a version or '--pre'.",
+                "Provide a version i.e. `cargo uv set 1.2.3` or a pre-release i.e. `cargo uv set --pre rc.1`",
+            ));
+        }
+        if matches!(action, Action::Print | Action::Verify | Action::Tree) {
+            let release_flags = [
+                ("--git-tag", self.git_ops.git_tag),
+                ("--git-push", self.git_ops.git_push),
+                ("--changelog", self.git_ops.changelog.is_some()),
+                ("--cargo-publish", self.cargo_publish),
+                ("--first-release", self.first_release),
+            ];
+            if let Some((flag, _)) = release_flags.iter().find(|(_, set)| *set) {
+                return Err(InvalidArguments::new(
+                    action,
+                    flag,
+                    format!("Can't be used with `{action}`."),
+                    format!("'{flag}' can't be used with the `{action}` action."),
+                    format!(
+                        "`{action}` doesn't change the version, so there is nothing to release. Use a bump action, e.g. `cargo uv patch {flag}`."
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets `set_version` from `--set-version` or the variable named by
+    /// `--set-version-from-env`, `stdin` is read for `--set-version -`.
+    pub fn read_set_version(&mut self, mut stdin: impl BufRead) -> Result<()> {
+        let (input, origin) = match (&self.set_version_input, &self.set_version_from_env) {
+            (Some(input), _) if input == "-" => {
+                let mut line = String::new();
+                stdin.read_line(&mut line).into_diagnostic()?;
+                if line.trim().is_empty() {
+                    miette::bail!(
+                        help = "Pipe the version into cargo-uv, e.g. `echo 1.2.3 | cargo uv set --set-version -`",
+                        "No version was read from stdin for '--set-version -'."
+                    )
+                }
+                (line, "stdin".to_string())
+            }
+            (Some(input), _) => (input.clone(), "'--set-version'".to_string()),
+            (None, Some(var)) => match std::env::var(var) {
+                Ok(value) => (value, format!("${var}")),
+                Err(e) => miette::bail!(
+                    help = "Set the variable or pass the version as an argument, e.g. `cargo uv set 1.2.3`",
+                    "Can't read the version from ${var}: {e}."
+                ),
+            },
+            (None, None) => return Ok(()),
+        };
+        self.set_version = Some(InvalidVersionInput::parse(&input, origin)?);
+        Ok(())
+    }
+
+    /// Sets the flags from the [`Config`] unless they, or a flag they conflict with, were
+    /// passed on the command line.
+    fn apply_config(&mut self, config: Config, matches: &ArgMatches) {
+        let passed = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+        if let Some(tag_prefix) = config.tag_prefix
+            && !passed("tag_prefix")
+            && !passed("tag_format")
+        {
+            self.git_ops.tag_prefix = Some(tag_prefix);
+        }
+        if let Some(message_template) = config.message_template
+            && !passed("message_template")
+            && !passed("message")
+        {
+            self.git_ops.message_template = Some(message_template);
+        }
+        if let Some(sign) = config.sign
+            && !passed("sign")
+        {
+            self.git_ops.sign = sign;
+        }
+        if let Some(push_branch) = config.push_branch
+            && !passed("push_branch")
+        {
+            self.git_ops.push_branch = push_branch;
+        }
+        if let Some(suppress) = config.suppress
+            && !passed("suppress")
+        {
+            self.suppress = vec![suppress];
+        }
+    }
+}
+
+/// Parses the `SET_VERSION` positional, allowing `1` and `1.2`.
+fn parse_set_version(version: &str) -> std::result::Result<Version, InvalidVersionInput> {
+    InvalidVersionInput::parse(version, "'SET_VERSION'")
+}
+
+/// Validates the registry name is alphanumeric, `-` or `_`, like cargo's registry names.
+fn parse_registry(registry: &str) -> std::result::Result<String, String> {
+    let valid = !registry.is_empty()
+        && registry
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(registry.to_string())
+    } else {
+        Err(format!(
+            "'{registry}' must only contain alphanumeric characters, '-' or '_'"
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static TEST_BIN_NAME: &str = "cargo-uv";
+
+    fn cli(args: &[&str]) -> Result<Cli> {
+        let args = [TEST_BIN_NAME]
+            .iter()
+            .chain(args)
+            .map(|s| s.to_string())
+            .collect();
+        Cli::cli_args(args, Some(TEST_BIN_NAME), None)
+    }
+
+    #[test]
+    fn set_requires_version_or_pre() {
+        assert!(cli(&["set"]).is_err());
+        assert!(cli(&["set", "1.2.3"]).is_ok());
+        assert!(cli(&["set", "--pre", "rc.1"]).is_ok());
+    }
+
+    #[test]
+    fn invalid_pre_is_labeled() {
+        let err = cli(&["set", "--pre", "rc.1@"]).unwrap_err();
+        assert!(err.downcast_ref::<InvalidPrerelease>().is_some());
+        assert!(err.labels().is_some());
+    }
+
+    #[test]
+    fn validate_rejects_release_flags_without_a_version_change() {
+        let err = cli(&["print", "--git-tag"]).unwrap_err();
+        let invalid = err.downcast_ref::<InvalidArguments>().unwrap();
+        assert_eq!(
+            invalid.msg,
+            "'--git-tag' can't be used with the `print` action."
+        );
+        assert_eq!(invalid.source_code, "cargo uv print --git-tag");
+        assert_eq!(invalid.label, (15, 9));
+
+        let err = cli(&["set"]).unwrap_err();
+        let invalid = err.downcast_ref::<InvalidArguments>().unwrap();
+        assert_eq!(invalid.label, (9, 3));
+
+        assert!(cli(&["tree", "--cargo-publish"]).is_err());
+        assert!(cli(&["print", "--first-release"]).is_err());
+        assert!(cli(&["patch", "--git-tag", "--git-push", "--cargo-publish"]).is_ok());
+    }
+
+    #[test]
+    fn publish_wait_refused_offline() {
+        let err = cli(&["patch", "-c", "--publish-wait", "5", "--offline"]).unwrap_err();
+        let Some(UvError::Clap { kind, label, .. }) = err.downcast_ref::<UvError>() else {
+            panic!("{err:?}");
+        };
+        assert_eq!(*kind, ErrorKind::ArgumentConflict);
+        assert!(label.is_some());
+    }
+
+    #[test]
+    fn last_private_flag_wins() {
+        let exclude = |args: &[&str]| {
+            cli(&[&["patch", "--cargo-publish"][..], args].concat())
+                .unwrap()
+                .exclude_private()
+        };
+        assert!(exclude(&[]));
+        assert!(exclude(&["--exclude-private"]));
+        assert!(!exclude(&["--include-private"]));
+        assert!(exclude(&["--include-private", "--exclude-private"]));
+        assert!(!exclude(&["--exclude-private", "--include-private"]));
+    }
+
+    #[test]
+    fn bare_version_implies_set() {
+        for args in [
+            &["1.2.3"][..],
+            &["set", "1.2.3"],
+            &["--dry-run", "1.2.3", "--git-tag"],
+            &["--tag-format", "1.2.3", "1.2.3"],
+        ] {
+            let cli = cli(args).unwrap();
+            assert_eq!(cli.action(), Action::Set, "{args:?}");
+            assert_eq!(cli.set_version(), Some(Version::new(1, 2, 3)), "{args:?}");
+        }
+    }
+
+    #[test]
+    fn set_version_zero_fills() {
+        let set = |version: &str| cli(&["set", version]).map(|cli| cli.set_version());
+        assert_eq!(set("2").unwrap(), Some(Version::new(2, 0, 0)));
+        assert_eq!(set("2.3").unwrap(), Some(Version::new(2, 3, 0)));
+        assert_eq!(set("2.3.4").unwrap(), Some(Version::new(2, 3, 4)));
+        assert_eq!(
+            set("2.3.4-rc.1").unwrap(),
+            Some("2.3.4-rc.1".parse().unwrap())
+        );
+        let err = set("2.x").unwrap_err();
+        assert!(err.downcast_ref::<InvalidVersionInput>().is_some());
+        assert!(err.labels().is_some());
+    }
+
+    #[test]
+    fn set_version_read_from_stdin() {
+        let mut cli = cli(&["set", "--set-version", "1.2.3"]).unwrap();
+        assert_eq!(cli.set_version(), Some(Version::new(1, 2, 3)));
+
+        cli.set_version_input = Some("-".into());
+        cli.read_set_version("2.0.0-rc.1\n".as_bytes()).unwrap();
+        assert_eq!(cli.set_version(), Some("2.0.0-rc.1".parse().unwrap()));
+
+        assert!(cli.read_set_version("".as_bytes()).is_err());
+        let err = cli.read_set_version("2.x\n".as_bytes()).unwrap_err();
+        assert!(err.downcast_ref::<InvalidVersionInput>().is_some());
+        assert_eq!(err.to_string(), "The version from stdin is invalid.");
+        assert!(err.labels().is_some());
+    }
+
+    #[test]
+    fn tag_refspec_substitutes_tag() {
+        let default = cli(&["patch", "--git-push"]).unwrap();
+        assert_eq!(default.tag_refspec("v1.2.3"), "tags/v1.2.3");
+        let refspec = "refs/tags/{tag}:refs/tags/{tag}";
+        let custom = cli(&["patch", "--git-push", "--tag-refspec", refspec]).unwrap();
+        assert_eq!(
+            custom.tag_refspec("v1.2.3"),
+            "refs/tags/v1.2.3:refs/tags/v1.2.3"
+        );
+    }
+
+    #[test]
+    fn push_retry_delay_doubles() {
+        let cli = cli(&[
+            "patch",
+            "--git-push",
+            "--push-retries",
+            "3",
+            "--push-retry-delay",
+            "100",
+        ])
+        .unwrap();
+        assert_eq!(cli.push_retries(), 3);
+        assert_eq!(cli.push_retry_delay(1), Duration::from_millis(100));
+        assert_eq!(cli.push_retry_delay(2), Duration::from_millis(200));
+        assert_eq!(cli.push_retry_delay(3), Duration::from_millis(400));
+    }
+
+    #[test]
+    fn bash_completions_list_actions() {
+        let shell = cli(&["--completions", "bash"])
+            .unwrap()
+            .completions()
+            .unwrap();
+        let mut buf = Vec::new();
+        Cli::write_completions(shell, &mut buf);
+        let script = String::from_utf8(buf).unwrap();
+        assert!(script.contains("cargo-uv"), "{script}");
+        for action in ["patch", "minor", "major", "set", "print", "tree"] {
+            assert!(script.contains(action), "{action} missing from {script}");
+        }
+    }
+
+    #[test]
+    fn suppress_values_combine() {
+        let both = Suppress::GIT | Suppress::CARGO;
+        assert_eq!(cli(&["-Q", "git,cargo"]).unwrap().suppress(), both);
+        assert_eq!(cli(&["-Q", "git", "-Q", "cargo"]).unwrap().suppress(), both);
+        assert_eq!(cli(&[]).unwrap().suppress(), Suppress::NONE);
+    }
+
+    #[test]
+    fn parse_registry_names() {
+        assert_eq!(parse_registry("my-registry_2").unwrap(), "my-registry_2");
+        assert!(parse_registry("").is_err());
+        assert!(parse_registry("my registry").is_err());
+        assert!(parse_registry("https://example.com").is_err());
     }
 }
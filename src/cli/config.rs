@@ -0,0 +1,168 @@
+use std::path::Path;
+
+use clap::ValueEnum;
+use miette::{IntoDiagnostic, bail};
+use toml_edit::{DocumentMut, Item, Table};
+use tracing::instrument;
+
+use crate::{CargoFile, Result, cli::Suppress};
+
+/// File name of the standalone config, next to the root `Cargo.toml`.
+pub static CONFIG_FILE: &str = "uv.toml";
+
+/// Defaults for flags read from `[package.metadata.uv]` and `uv.toml`.
+///
+/// `uv.toml` takes precedence over `[package.metadata.uv]`, flags passed on the command line
+/// take precedence over both.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Config {
+    pub tag_prefix: Option<String>,
+    pub message_template: Option<String>,
+    pub sign: Option<bool>,
+    pub push_branch: Option<bool>,
+    pub suppress: Option<Suppress>,
+}
+
+impl Config {
+    /// Reads the config for the `Cargo.toml` and `uv.toml` in `dir`, either may be missing.
+    #[instrument]
+    pub fn load(dir: &Path) -> Result<Config> {
+        let mut config = Config::default();
+        let manifest = dir.join("Cargo.toml");
+        if manifest.exists() {
+            let cargo_file = CargoFile::new(&manifest)?;
+            let table = cargo_file
+                .contents()
+                .and_then(|doc| doc.get("package"))
+                .and_then(|package| package.get("metadata"))
+                .and_then(|metadata| metadata.get("uv"))
+                .and_then(Item::as_table);
+            if let Some(table) = table {
+                config = config.merge(Config::from_table(table, "[package.metadata.uv]")?);
+            }
+        }
+        let config_file = dir.join(CONFIG_FILE);
+        if config_file.exists() {
+            let doc: DocumentMut = std::fs::read_to_string(&config_file)
+                .into_diagnostic()?
+                .parse()
+                .into_diagnostic()?;
+            config = config.merge(Config::from_table(doc.as_table(), CONFIG_FILE)?);
+        }
+        tracing::debug!("Config: {config:?}");
+        Ok(config)
+    }
+
+    /// Parses the known keys of the table, `source` names the table in errors.
+    ///
+    /// Unknown keys are ignored.
+    pub fn from_table(table: &Table, source: &str) -> Result<Config> {
+        let string = |key: &str| -> Result<Option<String>> {
+            match table.get(key) {
+                None => Ok(None),
+                Some(item) => match item.as_str() {
+                    Some(s) => Ok(Some(s.to_string())),
+                    None => bail!(
+                        help = "Use a string, e.g. {key} = \"...\".",
+                        "'{key}' in {source} must be a string."
+                    ),
+                },
+            }
+        };
+        let boolean = |key: &str| -> Result<Option<bool>> {
+            match table.get(key) {
+                None => Ok(None),
+                Some(item) => match item.as_bool() {
+                    Some(b) => Ok(Some(b)),
+                    None => bail!(
+                        help = "Use true or false, e.g. {key} = true.",
+                        "'{key}' in {source} must be a boolean."
+                    ),
+                },
+            }
+        };
+        let suppress = match string("suppress")? {
+            None => None,
+            Some(s) => match s
+                .split(',')
+                .map(|value| Suppress::from_str(value.trim(), true))
+                .collect::<std::result::Result<Suppress, _>>()
+            {
+                Ok(suppress) => Some(suppress),
+                Err(_) => bail!(
+                    help = "Use one or more of: none, git, cargo or all, e.g. \"git,cargo\".",
+                    "'suppress' in {source} has an invalid value '{s}'."
+                ),
+            },
+        };
+        Ok(Config {
+            tag_prefix: string("tag_prefix")?,
+            message_template: string("message_template")?,
+            sign: boolean("sign")?,
+            push_branch: boolean("push_branch")?,
+            suppress,
+        })
+    }
+
+    /// Fields set in `other` replace those in `self`.
+    pub fn merge(self, other: Config) -> Config {
+        Config {
+            tag_prefix: other.tag_prefix.or(self.tag_prefix),
+            message_template: other.message_template.or(self.message_template),
+            sign: other.sign.or(self.sign),
+            push_branch: other.push_branch.or(self.push_branch),
+            suppress: other.suppress.or(self.suppress),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(contents: &str) -> DocumentMut {
+        contents.parse().unwrap()
+    }
+
+    #[test]
+    fn from_table_reads_known_keys() {
+        let doc = table(
+            "tag_prefix = \"v\"\nmessage_template = \"release {version}\"\nsign = true\npush_branch = false\nsuppress = \"git,cargo\"\nother = 1\n",
+        );
+        assert_eq!(
+            Config::from_table(doc.as_table(), CONFIG_FILE).unwrap(),
+            Config {
+                tag_prefix: Some("v".into()),
+                message_template: Some("release {version}".into()),
+                sign: Some(true),
+                push_branch: Some(false),
+                suppress: Some(Suppress::GIT | Suppress::CARGO),
+            }
+        );
+    }
+
+    #[test]
+    fn from_table_rejects_wrong_types() {
+        let doc = table("sign = \"yes\"\n");
+        let err = Config::from_table(doc.as_table(), CONFIG_FILE).unwrap_err();
+        assert_eq!(err.to_string(), "'sign' in uv.toml must be a boolean.");
+        let doc = table("suppress = \"loud\"\n");
+        assert!(Config::from_table(doc.as_table(), CONFIG_FILE).is_err());
+    }
+
+    #[test]
+    fn merge_prefers_other() {
+        let metadata = Config {
+            tag_prefix: Some("v".into()),
+            sign: Some(true),
+            ..Default::default()
+        };
+        let file = Config {
+            tag_prefix: Some("release-".into()),
+            ..Default::default()
+        };
+        let config = metadata.merge(file);
+        assert_eq!(config.tag_prefix.as_deref(), Some("release-"));
+        assert_eq!(config.sign, Some(true));
+    }
+}
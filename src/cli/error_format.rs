@@ -0,0 +1,83 @@
+use clap::ValueEnum;
+use clap::builder::OsStr;
+use rusty_viking::EnumDisplay;
+use std::ffi::OsString;
+
+/// Format of the errors written to stderr.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, ValueEnum, EnumDisplay)]
+#[Lower]
+pub enum ErrorFormat {
+    #[default]
+    #[value(help = "Human readable errors with a footer.")]
+    Human,
+    #[value(help = "Errors as a JSON object with the code, message and help.")]
+    Json,
+}
+
+impl ErrorFormat {
+    /// Returns `true` if the error format is [`Json`].
+    ///
+    /// [`Json`]: ErrorFormat::Json
+    #[must_use]
+    pub fn is_json(&self) -> bool {
+        matches!(self, Self::Json)
+    }
+
+    /// Finds `--error-format` in the raw arguments, so the report handler is set before the
+    /// arguments are parsed.
+    ///
+    /// Falls back to [`ErrorFormat::Human`] for an invalid value, which clap then reports.
+    pub fn from_args(args: &[String]) -> ErrorFormat {
+        let mut args = args.iter();
+        let mut format = None;
+        while let Some(arg) = args.next() {
+            if arg == "--" {
+                break;
+            }
+            if arg == "--error-format" {
+                format = args.next().map(String::as_str);
+            } else if let Some(value) = arg.strip_prefix("--error-format=") {
+                format = Some(value);
+            }
+        }
+        format
+            .and_then(|format| ErrorFormat::from_str(format, true).ok())
+            .unwrap_or_default()
+    }
+}
+
+impl From<ErrorFormat> for OsStr {
+    fn from(format: ErrorFormat) -> Self {
+        let string_rep = OsString::from(format.to_string());
+        Self::from(string_rep)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn from_args_finds_the_flag() {
+        assert_eq!(
+            ErrorFormat::from_args(&args(&["cargo-uv", "patch"])),
+            ErrorFormat::Human
+        );
+        assert_eq!(
+            ErrorFormat::from_args(&args(&["cargo-uv", "--error-format", "json"])),
+            ErrorFormat::Json
+        );
+        assert_eq!(
+            ErrorFormat::from_args(&args(&["cargo-uv", "--error-format=json", "patch"])),
+            ErrorFormat::Json
+        );
+        assert_eq!(
+            ErrorFormat::from_args(&args(&["cargo-uv", "--error-format", "xml"])),
+            ErrorFormat::Human
+        );
+    }
+}
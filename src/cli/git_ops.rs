@@ -1,4 +1,5 @@
-#[cfg(feature = "unstable")]
+use std::path::PathBuf;
+
 use crate::Branch;
 use crate::cli::GIT_HEADER;
 #[derive(Debug, clap::Args)]
@@ -11,6 +12,38 @@ pub struct GitOps {
         help_heading = GIT_HEADER
     )]
     pub git_tag: bool,
+    #[arg(
+        short = 'a',
+        long,
+        help = "Create an annotated git tag.",
+        long_help = "Create an annotated git tag. The tag message is the same as the commit message.",
+        help_heading = GIT_HEADER
+    )]
+    pub annotate: bool,
+    #[arg(
+        short = 's',
+        long,
+        help = "Create a GPG-signed git tag.",
+        long_help = "Create a GPG-signed git tag. Signed tags are always annotated and use the same message as the commit.",
+        help_heading = GIT_HEADER
+    )]
+    pub sign: bool,
+    #[arg(
+        long,
+        value_name = "STRING",
+        conflicts_with = "tag_format",
+        help = "Prefix for the git tag, e.g. 'v' for 'v1.2.3'.",
+        help_heading = GIT_HEADER
+    )]
+    pub tag_prefix: Option<String>,
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        help = "Template for the git tag. Supports '{name}' and '{version}'.",
+        long_help = "Template for the git tag. Supports '{name}' for the root package name and '{version}' for the new version, e.g. '{name}-v{version}'. Defaults to '{version}'.",
+        help_heading = GIT_HEADER
+    )]
+    pub tag_format: Option<String>,
     #[arg(
         long,
         help = "Push tag to the branch's remote repositries.",
@@ -18,23 +51,236 @@ pub struct GitOps {
         help_heading = GIT_HEADER
     )]
     pub git_push: bool,
+    #[arg(
+        long,
+        requires = "git_push",
+        help = "Push the current branch along with the tag.",
+        long_help = "Push the current branch along with the tag. Runs 'git push <remote> <branch> tags/<tag>' for each remote, setting the upstream if the branch has none.",
+        help_heading = GIT_HEADER
+    )]
+    pub push_branch: bool,
+    #[arg(
+        long,
+        requires = "push_branch",
+        help = "Don't push the tag, only the branch.",
+        long_help = "Don't push the tag, only the branch. Use with '--push-branch' to push the release commit while keeping the tag local.",
+        help_heading = GIT_HEADER
+    )]
+    pub no_tag_push: bool,
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        requires = "git_push",
+        value_parser = parse_tag_refspec,
+        help = "Refspec to push the tag with, '{tag}' is the tag. Defaults to 'tags/{tag}'.",
+        long_help = "Refspec to push the tag with, '{tag}' is replaced by the tag, e.g. 'refs/tags/{tag}:refs/tags/{tag}' for mirrors that need the full refspec. Defaults to 'tags/{tag}'.",
+        help_heading = GIT_HEADER
+    )]
+    pub tag_refspec: Option<String>,
+    #[arg(
+        long,
+        value_name = "NAME",
+        requires = "git_push",
+        help = "Only push to this remote instead of all the branch's remotes.",
+        help_heading = GIT_HEADER
+    )]
+    pub remote: Option<String>,
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 0,
+        requires = "git_push",
+        help = "Retry a failed git push up to N times.",
+        long_help = "Retry a failed git push up to N times. Only transient network failures are retried, authentication failures fail immediately.",
+        help_heading = GIT_HEADER
+    )]
+    pub push_retries: u32,
+    #[arg(
+        long,
+        value_name = "MS",
+        default_value_t = 1000,
+        help = "Delay in milliseconds before the first push retry, doubled for each retry.",
+        help_heading = GIT_HEADER
+    )]
+    pub push_retry_delay: u64,
+    #[arg(
+        long,
+        help = "Fail if the branch is behind the remote.",
+        long_help = "Fail if the branch is behind the remote. Runs 'git fetch <remote>' before any changes and checks 'git rev-list --count HEAD..<remote>/<branch>' is 0. The fetch is skipped on a dry run.",
+        help_heading = GIT_HEADER
+    )]
+    pub require_up_to_date: bool,
     #[arg(short, long, help="Message for git commit. Default to git tag.",
         help_heading = GIT_HEADER
     )]
     pub message: Option<String>,
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        conflicts_with = "message",
+        help = "Template for the commit and tag message, e.g. 'release {name} {version}'.",
+        long_help = "Template for the commit and annotated tag message. Supports '{name}' for the root package name, '{version}' for the new version and '{previous}' for the version before bumping, e.g. 'chore(release): {name} {version} (was {previous})'. Any other '{...}' is left as written.",
+        help_heading = GIT_HEADER
+    )]
+    pub message_template: Option<String>,
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Add a section for the release to the changelog.",
+        long_help = "Add a '## [<version>] - <date>' section to the top of the changelog listing the commit subjects since the last tag, from 'git log <tag>..HEAD --pretty=%s'. Relative paths are relative to the workspace root. The file is committed with '--git-tag'.",
+        help_heading = GIT_HEADER
+    )]
+    pub changelog: Option<PathBuf>,
+    #[arg(
+        short = 'S',
+        long,
+        help = "GPG-sign the release commit.",
+        help_heading = GIT_HEADER
+    )]
+    pub sign_commit: bool,
+    #[arg(
+        long,
+        value_name = "AUTHOR",
+        value_parser = parse_author,
+        help = "Author of the release commit, e.g. 'Name <email>'.",
+        help_heading = GIT_HEADER
+    )]
+    pub commit_author: Option<String>,
+    #[arg(
+        long,
+        help = "Only commit the files cargo-uv changed, not everything staged.",
+        long_help = "Only commit the files cargo-uv changed, not everything staged. Runs 'git commit -- <paths>' so files staged before the run stay staged and out of the release commit.",
+        help_heading = GIT_HEADER
+    )]
+    pub commit_paths: bool,
+    #[arg(
+        long,
+        conflicts_with = "no_lockfile",
+        help = "Regenerate and commit Cargo.lock, the default for binaries.",
+        help_heading = GIT_HEADER
+    )]
+    pub include_lockfile: bool,
+    #[arg(
+        long,
+        help = "Don't regenerate or commit Cargo.lock, the default for libraries.",
+        long_help = "Don't regenerate or commit Cargo.lock, the default when the root package has no binary targets as libraries often ignore their lockfile.",
+        help_heading = GIT_HEADER
+    )]
+    pub no_lockfile: bool,
+    #[arg(
+        long,
+        requires = "git_tag",
+        help = "Amend the previous version bump commit instead of creating a new one.",
+        long_help = "Amend the previous version bump commit instead of creating a new one. Useful for re-running a release that failed after the commit. Fails if the previous commit has been pushed or changes files other than Cargo.toml and Cargo.lock.",
+        help_heading = GIT_HEADER
+    )]
+    pub amend: bool,
+    #[arg(
+        long,
+        value_name = "PATH",
+        value_parser = parse_git_root,
+        help = "Root of the git repository, if not the directory of the manifest.",
+        long_help = "Root of the git repository, i.e. the directory containing '.git'. Use when the Cargo workspace is in a subdirectory of the repository, defaults to the directory of the manifest.",
+        help_heading = GIT_HEADER
+    )]
+    pub git_root: Option<PathBuf>,
     #[arg(long = "force-git", help = "Pass force into all git operations.",
         help_heading = GIT_HEADER)]
     pub force: bool,
 
-    #[cfg(feature = "unstable")]
-    /// Used to change branch for the execution of the program. Defaults to current branch.
-    #[arg(long, default_value = Branch::default(), hide_default_value(true), help_heading = GIT_HEADER)]
+    #[arg(
+        long,
+        value_name = "BRANCH",
+        default_value = Branch::default(),
+        value_parser = parse_branch,
+        hide_default_value(true),
+        help = "Make the release on this branch, then switch back.",
+        long_help = "Make the release on this branch, then switch back. Changes, including untracked files, are stashed before switching and restored after switching back, other stashes are left alone. Defaults to the current branch.",
+        help_heading = GIT_HEADER
+    )]
     branch: Branch,
 }
 
-#[cfg(feature = "unstable")]
 impl GitOps {
     pub fn branch(&self) -> Branch {
         self.branch.clone()
     }
 }
+
+/// Validates the branch is a valid git ref name, `.` is the current branch.
+fn parse_branch(branch: &str) -> Result<Branch, String> {
+    Branch::local(branch).map_err(|e| e.to_string())
+}
+
+/// Validates the directory contains a `.git`, which is a file for worktrees and submodules.
+fn parse_git_root(path: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(path);
+    if path.join(".git").exists() {
+        Ok(path)
+    } else {
+        Err(format!("'{}' doesn't contain a '.git'", path.display()))
+    }
+}
+
+/// Validates the refspec template contains `{tag}`.
+fn parse_tag_refspec(refspec: &str) -> Result<String, String> {
+    match refspec.contains("{tag}") {
+        true => Ok(refspec.to_string()),
+        false => Err(format!("'{refspec}' must contain '{{tag}}'")),
+    }
+}
+
+/// Validates the author is in the form `Name <email>`.
+fn parse_author(author: &str) -> Result<String, String> {
+    let author = author.trim();
+    match author.split_once('<') {
+        Some((name, email))
+            if !name.trim().is_empty() && email.ends_with('>') && email.len() > 1 =>
+        {
+            Ok(author.to_string())
+        }
+        _ => Err(format!("'{author}' must be in the form 'Name <email>'")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    #[test]
+    fn parse_author_valid() {
+        assert_eq!(
+            parse_author("Release Bot <bot@example.com>").unwrap(),
+            "Release Bot <bot@example.com>"
+        );
+    }
+
+    #[test]
+    fn parse_git_root_requires_dot_git() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        assert!(parse_git_root(root.join("src").to_str().unwrap()).is_err());
+        let repo = std::env::temp_dir().join(format!("cargo-uv-git-root-{}", std::process::id()));
+        std::fs::create_dir_all(repo.join(".git")).unwrap();
+        assert_eq!(parse_git_root(repo.to_str().unwrap()).unwrap(), repo);
+        std::fs::remove_dir_all(repo).unwrap();
+    }
+
+    #[test]
+    fn parse_tag_refspec_requires_tag() {
+        assert_eq!(
+            parse_tag_refspec("refs/tags/{tag}:refs/tags/{tag}").unwrap(),
+            "refs/tags/{tag}:refs/tags/{tag}"
+        );
+        assert!(parse_tag_refspec("refs/tags/v1").is_err());
+    }
+
+    #[test]
+    fn parse_author_invalid() {
+        assert!(parse_author("Release Bot").is_err());
+        assert!(parse_author("<bot@example.com>").is_err());
+        assert!(parse_author("Release Bot <bot@example.com").is_err());
+        assert!(parse_author("Release Bot <>").is_err());
+    }
+}
@@ -0,0 +1,32 @@
+use clap::ValueEnum;
+use clap::builder::OsStr;
+use rusty_viking::EnumDisplay;
+use std::ffi::OsString;
+
+/// Format of the tracing logs.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, ValueEnum, EnumDisplay)]
+#[Lower]
+pub enum LogFormat {
+    #[default]
+    #[value(help = "Human readable log lines.")]
+    Human,
+    #[value(help = "A JSON object per line, for log aggregators.")]
+    Json,
+}
+
+impl LogFormat {
+    /// Returns `true` if the log format is [`Json`].
+    ///
+    /// [`Json`]: LogFormat::Json
+    #[must_use]
+    pub fn is_json(&self) -> bool {
+        matches!(self, Self::Json)
+    }
+}
+
+impl From<LogFormat> for OsStr {
+    fn from(format: LogFormat) -> Self {
+        let string_rep = OsString::from(format.to_string());
+        Self::from(string_rep)
+    }
+}
@@ -15,6 +15,21 @@ pub struct Manifest {
     pub manifest_path: Option<path::PathBuf>,
 }
 
+/// Whether `cargo metadata` resolves the dependency graph.
+///
+/// [`Packages`] only needs the dependencies declared in each manifest, which are included
+/// either way, so [`MetadataMode::NoDeps`] is the default as it is faster.
+///
+/// [`Packages`]: crate::Packages
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MetadataMode {
+    /// `cargo metadata --no-deps`, only the workspace members.
+    #[default]
+    NoDeps,
+    /// Also resolves the dependencies, filling `Metadata::resolve`.
+    WithDeps,
+}
+
 impl Manifest {
     /// Create a `cargo_metadata::MetadataCommand`
     ///
@@ -26,6 +41,15 @@ impl Manifest {
         }
         c
     }
+
+    /// [`Manifest::metadata`] with `--no-deps` for [`MetadataMode::NoDeps`].
+    pub fn metadata_for(&self, mode: MetadataMode) -> cargo_metadata::MetadataCommand {
+        let mut c = self.metadata();
+        if mode == MetadataMode::NoDeps {
+            c.no_deps();
+        }
+        c
+    }
 }
 
 #[cfg(test)]
@@ -1,15 +1,25 @@
 mod action;
 #[allow(clippy::module_inception)]
 mod cli;
+mod config;
+mod error_format;
 mod git_ops;
+mod log_format;
 mod manifest;
+mod output_format;
+mod print_format;
 mod suppress;
 mod workspace;
 
 pub use action::Action;
 pub use cli::Cli;
+pub use config::Config;
+pub use error_format::ErrorFormat;
 pub use git_ops::GitOps;
-pub use manifest::Manifest;
+pub use log_format::LogFormat;
+pub use manifest::{Manifest, MetadataMode};
+pub use output_format::OutputFormat;
+pub use print_format::PrintFormat;
 pub use suppress::Suppress;
 pub use workspace::Workspace;
 
@@ -0,0 +1,40 @@
+use clap::ValueEnum;
+use clap::builder::OsStr;
+use rusty_viking::EnumDisplay;
+use std::ffi::OsString;
+
+/// Format of the output written to stdout.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, ValueEnum, EnumDisplay)]
+#[Lower]
+pub enum OutputFormat {
+    #[default]
+    #[value(help = "Human readable output.")]
+    Human,
+    #[value(help = "A JSON summary of the release on success, suppressing all other output.")]
+    Json,
+}
+
+impl OutputFormat {
+    /// Returns `true` if the output format is [`Human`].
+    ///
+    /// [`Human`]: OutputFormat::Human
+    #[must_use]
+    pub fn is_human(&self) -> bool {
+        matches!(self, Self::Human)
+    }
+
+    /// Returns `true` if the output format is [`Json`].
+    ///
+    /// [`Json`]: OutputFormat::Json
+    #[must_use]
+    pub fn is_json(&self) -> bool {
+        matches!(self, Self::Json)
+    }
+}
+
+impl From<OutputFormat> for OsStr {
+    fn from(format: OutputFormat) -> Self {
+        let string_rep = OsString::from(format.to_string());
+        Self::from(string_rep)
+    }
+}
@@ -0,0 +1,124 @@
+use miette::IntoDiagnostic;
+use semver::Version;
+
+use crate::{PackageName, Result};
+
+/// How `print` formats the versions, from `--format` and `--bare`.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub enum PrintFormat {
+    /// `<name> <version>` per line.
+    #[default]
+    Human,
+    /// Only the versions, one per line without a trailing newline, for shell capture.
+    Bare,
+    /// An object with the name and version, or an array of them for more than one package.
+    Json,
+    /// A template with `{name}` and `{version}`, e.g. `{name}@{version}`, per line.
+    Template(String),
+}
+
+impl PrintFormat {
+    /// Parses `--format`, either `json` or a template containing `{version}` or `{name}`.
+    pub fn parse(format: &str) -> std::result::Result<PrintFormat, String> {
+        match format {
+            "json" => Ok(PrintFormat::Json),
+            template if template.contains("{version}") || template.contains("{name}") => {
+                Ok(PrintFormat::Template(template.to_string()))
+            }
+            _ => Err(format!(
+                "'{format}' must be 'json' or a template with '{{name}}' or '{{version}}'"
+            )),
+        }
+    }
+
+    /// Formats the versions of the packages, in order.
+    pub fn render(&self, versions: &[(PackageName, Version)]) -> Result<String> {
+        let lines = |line: &dyn Fn(&PackageName, &Version) -> String| -> String {
+            versions
+                .iter()
+                .map(|(name, version)| line(name, version) + "\n")
+                .collect()
+        };
+        Ok(match self {
+            PrintFormat::Human => lines(&|name, version| format!("{name} {version}")),
+            PrintFormat::Template(template) => lines(&|name, version| {
+                template
+                    .replace("{name}", name.as_ref())
+                    .replace("{version}", &version.to_string())
+            }),
+            PrintFormat::Bare => versions
+                .iter()
+                .map(|(_, version)| version.to_string())
+                .collect::<Vec<_>>()
+                .join("\n"),
+            PrintFormat::Json => {
+                let mut objects: Vec<_> = versions
+                    .iter()
+                    .map(|(name, version)| {
+                        serde_json::json!({ "name": name.to_string(), "version": version.to_string() })
+                    })
+                    .collect();
+                let json = match objects.len() {
+                    1 => objects.remove(0),
+                    _ => serde_json::Value::Array(objects),
+                };
+                serde_json::to_string(&json).into_diagnostic()? + "\n"
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn versions() -> Vec<(PackageName, Version)> {
+        vec![
+            ("a".into(), Version::new(1, 2, 3)),
+            ("b".into(), Version::parse("0.1.0-rc.1").unwrap()),
+        ]
+    }
+
+    #[test]
+    fn parse_format() {
+        assert_eq!(PrintFormat::parse("json").unwrap(), PrintFormat::Json);
+        assert_eq!(
+            PrintFormat::parse("{name}@{version}").unwrap(),
+            PrintFormat::Template("{name}@{version}".into())
+        );
+        assert!(PrintFormat::parse("yaml").is_err());
+    }
+
+    #[test]
+    fn render_each_format() {
+        let one = &versions()[..1];
+        assert_eq!(PrintFormat::Human.render(one).unwrap(), "a 1.2.3\n");
+        assert_eq!(PrintFormat::Bare.render(one).unwrap(), "1.2.3");
+        assert_eq!(
+            PrintFormat::Template("{name}@{version}".into())
+                .render(one)
+                .unwrap(),
+            "a@1.2.3\n"
+        );
+        assert_eq!(
+            PrintFormat::Json.render(one).unwrap(),
+            "{\"name\":\"a\",\"version\":\"1.2.3\"}\n"
+        );
+    }
+
+    #[test]
+    fn render_multiple_packages() {
+        assert_eq!(
+            PrintFormat::Human.render(&versions()).unwrap(),
+            "a 1.2.3\nb 0.1.0-rc.1\n"
+        );
+        assert_eq!(
+            PrintFormat::Bare.render(&versions()).unwrap(),
+            "1.2.3\n0.1.0-rc.1"
+        );
+        assert_eq!(
+            PrintFormat::Json.render(&versions()).unwrap(),
+            "[{\"name\":\"a\",\"version\":\"1.2.3\"},{\"name\":\"b\",\"version\":\"0.1.0-rc.1\"}]\n"
+        );
+    }
+}
@@ -1,68 +1,117 @@
 use clap::ValueEnum;
-use clap::builder::OsStr;
-use rusty_viking::EnumDisplay;
+use clap::builder::{OsStr, PossibleValue};
 use std::ffi::OsString;
+use std::fmt::Display;
+use std::ops::{BitOr, BitOrAssign};
 
 /// What to suppress from stdout
-#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, ValueEnum, EnumDisplay)]
-#[Lower]
-pub enum Suppress {
-    #[default]
-    None,
-    Git,
-    Cargo,
-    All,
-}
+///
+/// A set of the outputs, e.g. `-Q git,cargo` silences git and cargo but keeps the output of
+/// cargo-uv, which is only silenced by `all`.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct Suppress(u8);
 
 impl Suppress {
-    /// Returns `true` if the suppress is [`None`].
-    ///
-    /// [`None`]: Suppress::None
+    pub const NONE: Suppress = Suppress(0);
+    pub const GIT: Suppress = Suppress(1);
+    pub const CARGO: Suppress = Suppress(1 << 1);
+    /// Git, cargo and the output of cargo-uv itself.
+    pub const ALL: Suppress = Suppress(0b111);
+
+    /// Returns `true` if every output in `other` is suppressed.
+    #[must_use]
+    pub fn contains(&self, other: Suppress) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns `true` if nothing is suppressed.
     #[must_use]
     pub fn is_none(&self) -> bool {
-        matches!(self, Self::None)
+        *self == Self::NONE
     }
 
-    /// Returns `true` if the suppress is [`Git`].
-    ///
-    /// [`Git`]: Suppress::Git
+    /// Returns `true` if only git is suppressed.
     #[must_use]
     pub fn is_git(&self) -> bool {
-        matches!(self, Self::Git)
+        *self == Self::GIT
     }
 
-    /// Returns `true` if the suppress is [`Git`] or [`All`].
-    ///
-    /// [`Git`]: Suppress::Git
-    /// [`All`]: Suppress::All
+    /// Returns `true` if git is suppressed.
     #[must_use]
     pub fn includes_git(&self) -> bool {
-        matches!(self, Self::Git | Self::All)
+        self.contains(Self::GIT)
     }
 
-    /// Returns `true` if the suppress is [`Cargo`].
-    ///
-    /// [`Cargo`]: Suppress::Cargo
+    /// Returns `true` if only cargo is suppressed.
     #[must_use]
     pub fn is_cargo(&self) -> bool {
-        matches!(self, Self::Cargo)
+        *self == Self::CARGO
     }
 
-    /// Returns `true` if the suppress is [`Cargo`] or [`All`].
-    ///
-    /// [`Cargo`]: Suppress::Cargo
-    /// [`All`]: Suppress::All
+    /// Returns `true` if cargo is suppressed.
     #[must_use]
     pub fn includes_cargo(&self) -> bool {
-        matches!(self, Self::Cargo | Self::All)
+        self.contains(Self::CARGO)
     }
 
-    /// Returns `true` if the suppress is [`All`].
-    ///
-    /// [`All`]: Suppress::All
+    /// Returns `true` if everything is suppressed, including the output of cargo-uv.
     #[must_use]
     pub fn is_all(&self) -> bool {
-        matches!(self, Self::All)
+        *self == Self::ALL
+    }
+}
+
+impl BitOr for Suppress {
+    type Output = Suppress;
+
+    fn bitor(self, rhs: Suppress) -> Suppress {
+        Suppress(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for Suppress {
+    fn bitor_assign(&mut self, rhs: Suppress) {
+        self.0 |= rhs.0
+    }
+}
+
+impl FromIterator<Suppress> for Suppress {
+    fn from_iter<I: IntoIterator<Item = Suppress>>(iter: I) -> Self {
+        iter.into_iter().fold(Suppress::NONE, BitOr::bitor)
+    }
+}
+
+/// Each value is a single flag, combine them with `-Q git,cargo` or `-Q git -Q cargo`.
+impl ValueEnum for Suppress {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Self::NONE, Self::GIT, Self::CARGO, Self::ALL]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        match *self {
+            Self::NONE => Some(PossibleValue::new("none")),
+            Self::GIT => Some(PossibleValue::new("git")),
+            Self::CARGO => Some(PossibleValue::new("cargo")),
+            Self::ALL => Some(PossibleValue::new("all")),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Suppress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::NONE => write!(f, "none"),
+            Self::ALL => write!(f, "all"),
+            _ => {
+                let names: Vec<&str> = [(Self::GIT, "git"), (Self::CARGO, "cargo")]
+                    .into_iter()
+                    .filter(|(flag, _)| self.contains(*flag))
+                    .map(|(_, name)| name)
+                    .collect();
+                write!(f, "{}", names.join(","))
+            }
+        }
     }
 }
 
@@ -72,3 +121,26 @@ impl From<Suppress> for OsStr {
         Self::from(string_rep)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn git_and_cargo_keep_tool_output() {
+        let suppress: Suppress = [Suppress::GIT, Suppress::CARGO].into_iter().collect();
+        assert!(suppress.includes_git());
+        assert!(suppress.includes_cargo());
+        assert!(!suppress.is_all());
+        assert_eq!(suppress.to_string(), "git,cargo");
+    }
+
+    #[test]
+    fn all_includes_everything() {
+        assert!(Suppress::ALL.includes_git());
+        assert!(Suppress::ALL.includes_cargo());
+        assert!((Suppress::ALL | Suppress::GIT).is_all());
+        assert!(!Suppress::GIT.includes_cargo());
+        assert!(Suppress::default().is_none());
+    }
+}
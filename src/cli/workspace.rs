@@ -5,18 +5,20 @@ use std::collections::HashSet;
 
 use tracing::{instrument, trace};
 
-use crate::{Package, PackageName, Packages, ReadToml, Result, SplitVec, cli::WORKSPACE_HEADER};
+use crate::{
+    Package, PackageError, PackageName, Packages, ReadToml, Result, SplitVec, cli::WORKSPACE_HEADER,
+};
 
 /// Cargo flags for selecting crates in a workspace.
 #[derive(Default, Clone, Debug, PartialEq, Eq, clap::Args)]
 #[command(about = None, long_about = None)]
 #[non_exhaustive]
 pub struct Workspace {
-    #[arg(short, long, value_name = "SPEC", help_heading = WORKSPACE_HEADER)]
+    #[arg(short, long, value_name = "SPEC", value_parser = parse_package_spec, help_heading = WORKSPACE_HEADER)]
     /// Package to process (see `cargo help pkgid`)
     pub package: Vec<String>,
 
-    #[arg(short = 'x', long, value_name = "SPEC", help_heading = WORKSPACE_HEADER)]
+    #[arg(short = 'x', long, value_name = "SPEC", value_parser = parse_package_spec, help_heading = WORKSPACE_HEADER)]
     /// Exclude packages from being processed
     pub exclude: Vec<String>,
 
@@ -33,24 +35,40 @@ pub struct Workspace {
     pub default_members: bool,
 }
 
+/// Validates a `-p` or `-x` spec is a package name, which may contain the globs `*` and `?`.
+fn parse_package_spec(spec: &str) -> std::result::Result<String, PackageError> {
+    if spec.contains(['*', '?']) {
+        return match PackageName::check(spec, |c| c == '*' || c == '?') {
+            Some(reason) => Err(PackageError::InvalidPackageName {
+                name: spec.to_string(),
+                reason,
+            }),
+            None => Ok(spec.to_string()),
+        };
+    }
+    PackageName::new(spec).map(|name| name.0)
+}
+
 impl Workspace {
     #[instrument(skip(packages))]
     /// Partition workspace members into those selected and those excluded.
     ///
-    /// Notes:
-    /// - Requires the features `cargo_metadata`.
-    /// - Requires not calling `MetadataCommand::no_deps`
+    /// The dependencies between members are those declared in the manifests, so the packages
+    /// can come from either [`MetadataMode`].
+    ///
+    /// [`MetadataMode`]: crate::cli::MetadataMode
     pub fn partition_packages<'m>(
         &self,
         packages: &'m Packages,
     ) -> Result<SplitVec<&'m Package<ReadToml>>> {
+        let root_package = packages.root_package_name_unchecked();
         let selection = PackagesCli::from_flags(
             self.workspace,
             self.default_members,
             &self.exclude,
             &self.package,
+            root_package.is_some(),
         );
-        let root_package = packages.root_package_name_unchecked();
         let modifications: &PackagesCliModifier<'_> = selection.as_ref();
         let workspace_members: HashSet<&PackageName> = packages.workspace_members();
         let workspace_default_members: HashSet<&PackageName> = packages.workspace_default_members();
@@ -64,11 +82,25 @@ impl Workspace {
             PackagesCli::DefaultMembers(_) => workspace_default_members,
         };
 
-        Ok(packages
+        let root_only = matches!(selection, PackagesCli::RootPackage(_));
+        let (mut included, mut excluded): SplitVec<&'m Package<ReadToml>> = packages
             .package_set()
             .into_iter()
-            // Deviating from cargo by not supporting patterns
-            .partition(|package| modifications.include(&base_ids, package.name())))
+            .partition(|package| modifications.include(&base_ids, package.name()));
+        if root_only
+            && included.is_empty()
+            && let Some(root_package) = root_package
+            && modifications.exclude(root_package)
+        {
+            miette::bail!(
+                help = "Without '--workspace' only the root package '{root_package}' is selected.",
+                "you excluded the only selected package; add --workspace to select others."
+            )
+        }
+        // Sorted so the tasks generated from them are in the same order every run.
+        included.sort_by(|a, b| a.name().cmp(b.name()));
+        excluded.sort_by(|a, b| a.name().cmp(b.name()));
+        Ok((included, excluded))
     }
 
     pub fn partition_packages_owned(
@@ -83,37 +115,20 @@ impl Workspace {
         })
     }
 
+    /// Partitions by name with [`Workspace::partition_packages`] then collects the mutable refs.
     pub fn partition_packages_mut<'m>(
         &self,
         packages: &'m mut Packages,
     ) -> Result<SplitVec<&'m mut Package<ReadToml>>> {
-        let packages_clone = packages.clone();
-        let selection = PackagesCli::from_flags(
-            self.workspace,
-            self.default_members,
-            &self.exclude,
-            &self.package,
-        );
-        let root_package = packages.root_package_name();
-        let modifications: &PackagesCliModifier<'_> = selection.as_ref();
-        let workspace_members = packages_clone.workspace_members();
-        let workspace_default_members: HashSet<&PackageName> =
-            packages_clone.workspace_default_members();
-
-        let base_ids = match selection {
-            PackagesCli::RootPackage(_) => workspace_members
-                .iter()
-                .filter_map(|&p| (Some(p) == root_package).then_some(p))
-                .collect(),
-            PackagesCli::All(_) => workspace_members,
-            PackagesCli::DefaultMembers(_) => workspace_default_members,
-        };
-
-        Ok(packages
-            .package_set_mut()
-            .into_iter()
-            // Deviating from cargo by not supporting patterns
-            .partition(|package| modifications.include(&base_ids, package.name())))
+        let (included, _) = self.partition_packages(packages)?;
+        let included: HashSet<PackageName> = included.iter().map(|p| p.name().clone()).collect();
+
+        let (mut included, mut excluded): SplitVec<&'m mut Package<ReadToml>> = packages
+            .packages_iter_mut()
+            .partition(|package| included.contains(package.name()));
+        included.sort_by(|a, b| a.name().cmp(b.name()));
+        excluded.sort_by(|a, b| a.name().cmp(b.name()));
+        Ok((included, excluded))
     }
 }
 
@@ -147,23 +162,25 @@ impl<'p> PackagesCliModifier<'p> {
     };
 
     pub fn new(mut include: Option<&'p [String]>, mut exclude: Option<&'p [String]>) -> Self {
-        if let Some(inc) = include {
-            if inc.is_empty() {
-                include = None;
-            }
+        if let Some(inc) = include
+            && inc.is_empty()
+        {
+            include = None;
         }
-        if let Some(ex) = exclude {
-            if ex.is_empty() {
-                exclude = None;
-            }
+        if let Some(ex) = exclude
+            && ex.is_empty()
+        {
+            exclude = None;
         }
         Self { include, exclude }
     }
 
     /// Tests whether to include the package, uses both included and excluded.
-    pub fn include(&self, base_ids: &HashSet<&PackageName>, package: &String) -> bool {
+    ///
+    /// Both are glob patterns, see [`PackageName::matches`].
+    pub fn include(&self, base_ids: &HashSet<&PackageName>, package: &PackageName) -> bool {
         let is_include = if let Some(inc) = self.include {
-            inc.contains(package)
+            inc.iter().any(|pattern| package.matches(pattern))
         } else {
             false
         };
@@ -177,9 +194,9 @@ impl<'p> PackagesCliModifier<'p> {
     }
 
     /// Test whether the package has been explicitly excluded.
-    pub fn exclude(&self, package: &String) -> bool {
+    pub fn exclude(&self, package: &PackageName) -> bool {
         if let Some(exc) = self.exclude {
-            exc.contains(package)
+            exc.iter().any(|pattern| package.matches(pattern))
         } else {
             false
         }
@@ -187,17 +204,23 @@ impl<'p> PackagesCliModifier<'p> {
 }
 
 impl<'p> PackagesCli<'p> {
+    /// Without any flags the root package is selected, or the default members like cargo when
+    /// there is no root package.
     #[instrument]
     pub fn from_flags(
         all: bool,
         default_members: bool,
         exclude: &'p [String],
         package: &'p [String],
+        has_root_package: bool,
     ) -> Self {
         trace!("from_flags");
         use PackagesCliModifier as PackMod;
         let pack_mod = PackMod::new(Some(package), Some(exclude));
         match (all, default_members, exclude.len(), package.len()) {
+            (false, false, 0, 0) if !has_root_package => {
+                PackagesCli::DefaultMembers(PackMod::NO_MOD)
+            }
             (false, false, 0, 0) => PackagesCli::RootPackage(PackMod::NO_MOD),
             (true, false, 0, _) => PackagesCli::All(PackMod::NO_MOD),
             (true, false, _, _) => PackagesCli::All(pack_mod),
@@ -228,6 +251,16 @@ mod tests {
         Packages::from(&metadata)
     }
 
+    #[test]
+    fn parse_package_spec_allows_globs() {
+        assert_eq!(parse_package_spec("cargo-uv").unwrap(), "cargo-uv");
+        assert_eq!(parse_package_spec("cargo-*").unwrap(), "cargo-*");
+        assert_eq!(parse_package_spec("a?c").unwrap(), "a?c");
+        assert!(parse_package_spec("").is_err());
+        assert!(parse_package_spec("cargo uv").is_err());
+        assert!(parse_package_spec("1*").is_err());
+    }
+
     #[test]
     fn verify_app() {
         #[derive(Debug, clap::Parser)]
@@ -295,9 +328,7 @@ mod tests {
         #[test]
         fn single_crate() {
             let packages = packages_from("simple/Cargo.toml");
-            let (included, excluded) = Workspace::default()
-                .partition_packages(&packages)
-                .unwrap();
+            let (included, excluded) = Workspace::default().partition_packages(&packages).unwrap();
             assert_eq!(included.len(), 1);
             assert_eq!(excluded.len(), 0);
         }
@@ -305,9 +336,7 @@ mod tests {
         #[test]
         fn mixed_ws_from_root() {
             let packages = packages_from("mixed_ws/Cargo.toml");
-            let (included, excluded) = Workspace::default()
-                .partition_packages(&packages)
-                .unwrap();
+            let (included, excluded) = Workspace::default().partition_packages(&packages).unwrap();
             // default selects only the workspace root package
             assert_eq!(included.len(), 1);
             assert_eq!(excluded.len(), 2);
@@ -317,9 +346,7 @@ mod tests {
         fn mixed_ws_from_leaf() {
             // cargo metadata resolves back to workspace root regardless of entry manifest
             let packages = packages_from("mixed_ws/c/Cargo.toml");
-            let (included, excluded) = Workspace::default()
-                .partition_packages(&packages)
-                .unwrap();
+            let (included, excluded) = Workspace::default().partition_packages(&packages).unwrap();
             assert_eq!(included.len(), 1);
             assert_eq!(excluded.len(), 2);
         }
@@ -327,21 +354,27 @@ mod tests {
         #[test]
         fn pure_ws_from_root() {
             let packages = packages_from("pure_ws/Cargo.toml");
-            let (included, excluded) = Workspace::default()
-                .partition_packages(&packages)
-                .unwrap();
-            // virtual workspace: no root package → nothing selected by default
-            assert_eq!(included.len(), 0);
-            assert_eq!(excluded.len(), 3);
+            let (included, excluded) = Workspace::default().partition_packages(&packages).unwrap();
+            // virtual workspace: no root package → default members, which is all without
+            // `default-members`
+            assert_eq!(included.len(), 3);
+            assert_eq!(excluded.len(), 0);
+        }
+
+        #[test]
+        fn pure_ws_default_members() {
+            let packages = packages_from("pure_ws_default/Cargo.toml");
+            let (included, excluded) = Workspace::default().partition_packages(&packages).unwrap();
+            assert_eq!(included.len(), 1);
+            assert_eq!(included[0].name().as_str(), "a");
+            assert_eq!(excluded.len(), 2);
         }
 
         #[test]
         fn pure_ws_from_leaf() {
             // When invoked from a leaf manifest, cargo resolves that leaf as the root package
             let packages = packages_from("pure_ws/c/Cargo.toml");
-            let (included, excluded) = Workspace::default()
-                .partition_packages(&packages)
-                .unwrap();
+            let (included, excluded) = Workspace::default().partition_packages(&packages).unwrap();
             assert_eq!(included.len(), 1); // c is the cargo resolve root
             assert_eq!(excluded.len(), 2);
         }
@@ -440,6 +473,86 @@ mod tests {
         }
     }
 
+    mod partition_glob {
+        use super::*;
+
+        fn names(packages: Vec<&Package<ReadToml>>) -> Vec<&str> {
+            let mut names: Vec<&str> = packages.iter().map(|p| p.name().as_str()).collect();
+            names.sort();
+            names
+        }
+
+        #[test]
+        fn package_pattern() {
+            let packages = packages_from("globs/Cargo.toml");
+            let ws = Workspace {
+                package: vec!["api-*".to_owned()],
+                ..Default::default()
+            };
+            let (included, excluded) = ws.partition_packages(&packages).unwrap();
+            assert_eq!(names(included), vec!["api-grpc", "api-http"]);
+            assert_eq!(names(excluded), vec!["worker"]);
+        }
+
+        #[test]
+        fn exclude_pattern() {
+            let packages = packages_from("globs/Cargo.toml");
+            let ws = Workspace {
+                workspace: true,
+                exclude: vec!["api-*".to_owned()],
+                ..Default::default()
+            };
+            let (included, excluded) = ws.partition_packages(&packages).unwrap();
+            assert_eq!(names(included), vec!["worker"]);
+            assert_eq!(names(excluded), vec!["api-grpc", "api-http"]);
+        }
+
+        #[test]
+        fn package_name_matches() {
+            let name = PackageName::from("api-http");
+            for pattern in [
+                "api-http", "api-*", "*", "*http", "a*-h*p", "api-htt?", "*-*",
+            ] {
+                assert!(name.matches(pattern), "{pattern}");
+            }
+            for pattern in ["api", "api-", "api-?", "*grpc", "?api-http", "api-http?"] {
+                assert!(!name.matches(pattern), "{pattern}");
+            }
+        }
+    }
+
+    mod partition_mut {
+        use super::*;
+        use crate::Action;
+
+        #[test]
+        fn bump_included_members() {
+            let mut packages = packages_from("mixed_ws/Cargo.toml");
+            let ws = Workspace {
+                package: vec!["a".to_owned()],
+                ..Default::default()
+            };
+            let (included, excluded) = ws.partition_packages_mut(&mut packages).unwrap();
+            assert_eq!(excluded.len(), 1);
+            for package in included {
+                package
+                    .bump_version(Action::Minor, None, None, false)
+                    .unwrap();
+            }
+
+            let version = |name: &str| {
+                packages
+                    .get_package(&name.into())
+                    .unwrap()
+                    .version()
+                    .to_string()
+            };
+            assert_eq!(version("a"), "0.2.0");
+            assert_eq!(version("b"), "0.2.0");
+            assert_eq!(version("c"), "0.1.1");
+        }
+    }
+
     mod partition_exclude {
         use super::*;
 
@@ -468,5 +581,33 @@ mod tests {
             assert_eq!(included.len(), 2); // a, c
             assert_eq!(excluded.len(), 1); // b
         }
+
+        #[test]
+        fn mixed_ws_exclude_root_alone() {
+            let packages = packages_from("mixed_ws/Cargo.toml");
+            let ws = Workspace {
+                exclude: vec!["b".to_owned()],
+                ..Default::default()
+            };
+            let err = ws.partition_packages(&packages).unwrap_err();
+            assert!(
+                err.to_string()
+                    .contains("you excluded the only selected package; add --workspace"),
+                "{err}"
+            );
+        }
+
+        #[test]
+        fn mixed_ws_exclude_root_with_workspace() {
+            let packages = packages_from("mixed_ws/Cargo.toml");
+            let ws = Workspace {
+                workspace: true,
+                exclude: vec!["b".to_owned()],
+                ..Default::default()
+            };
+            let (included, excluded) = ws.partition_packages(&packages).unwrap();
+            assert_eq!(included.len(), 2); // a, c
+            assert_eq!(excluded.len(), 1); // b
+        }
     }
 }
@@ -1,14 +1,16 @@
 use std::fmt::{self, Display, Formatter};
 
+use clap::error::{ContextKind, ContextValue};
 use miette::Diagnostic;
 use semver::Version;
 
-use crate::Action;
+use crate::{Action, version::parse_partial_version};
 
 #[allow(dead_code)]
 #[derive(Debug, thiserror::Error, miette::Diagnostic)]
 pub enum UvError {
     #[error("{msg}")]
+    #[diagnostic(code(Clap))]
     Clap {
         kind: clap::error::ErrorKind,
         msg: String,
@@ -33,6 +35,7 @@ pub enum UvError {
 }
 
 #[derive(Debug, Clone, thiserror::Error, miette::Diagnostic)]
+#[diagnostic(code(ManifestNotFound))]
 pub struct ManifestNotFoundError {
     #[help]
     pub help: Option<&'static str>,
@@ -50,6 +53,46 @@ impl Display for ManifestNotFoundError {
     }
 }
 
+impl UvError {
+    /// Wraps a [clap::Error] so it is reported by the installed [miette] handler, e.g. as JSON
+    /// with `--error-format json`.
+    pub fn clap(error: &clap::Error, args: &[String]) -> Self {
+        let rendered = error.render().to_string();
+        let rendered = rendered.trim_end();
+        let (msg, help) = rendered.split_once('\n').unwrap_or((rendered, ""));
+        let help = help
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+        // The program name is left out.
+        let source_code = args.iter().skip(1).cloned().collect::<Vec<_>>().join(" ");
+        let invalid_value = match error.get(ContextKind::InvalidValue) {
+            Some(ContextValue::String(value)) => Some(value.as_str()),
+            _ => None,
+        };
+        // e.g. `--publish-wait <SECS>`, only the flag is in the args.
+        let invalid_arg = match error.get(ContextKind::InvalidArg) {
+            Some(ContextValue::String(arg)) => arg.split(' ').next(),
+            _ => None,
+        };
+        let label = [invalid_value, invalid_arg]
+            .into_iter()
+            .flatten()
+            .filter(|value| !value.is_empty())
+            .find_map(|value| source_code.find(value).map(|start| (start, value.len())));
+        Self::Clap {
+            kind: error.kind(),
+            msg: msg.trim_start_matches("error: ").to_string(),
+            help,
+            source_code,
+            label,
+            label_msg: "here",
+        }
+    }
+}
+
 impl From<ManifestNotFoundError> for UvError {
     fn from(value: ManifestNotFoundError) -> Self {
         Self::ManifestNotFound(value)
@@ -92,4 +135,91 @@ impl VersionError {
             label_msg: "".into(),
         }
     }
+
+    pub fn downgrade(old_version: &Version, new_version: &Version) -> Self {
+        let msg =
+            format!("New version {new_version} is lower than the current version {old_version}.");
+        let help = Some("Use '--allow-downgrade' to set a lower version.".to_string());
+
+        Self {
+            old_version: old_version.clone(),
+            bump: Action::Set,
+            msg,
+            help,
+            label: None,
+            label_msg: "".into(),
+        }
+    }
+}
+
+/// A version to set that isn't a valid semver version, missing components are zero-filled.
+#[derive(Debug, Clone, thiserror::Error, Diagnostic)]
+#[error("The version from {origin} is invalid.")]
+#[diagnostic(
+    code(InvalidVersion),
+    help("Use a semantic version, e.g. '1.2.3' or '1.2.3-rc.1'.")
+)]
+pub struct InvalidVersionInput {
+    pub origin: String,
+    #[source_code]
+    pub input: String,
+    #[label("{reason}")]
+    pub label: (usize, usize),
+    pub reason: String,
+}
+
+impl InvalidVersionInput {
+    /// Parses `input` with [`parse_partial_version`], `origin` names where it was read from,
+    /// e.g. `$RELEASE_VERSION`.
+    pub fn parse(input: &str, origin: impl Into<String>) -> Result<Version, Self> {
+        let input = input.trim();
+        parse_partial_version(input).map_err(|e| Self {
+            origin: origin.into(),
+            input: input.to_string(),
+            label: (0, input.len()),
+            reason: e.to_string(),
+        })
+    }
+}
+
+/// A combination of the action and flags that can't work, found by [`Cli::validate`].
+///
+/// [`Cli::validate`]: crate::Cli::validate
+#[derive(Debug, Clone, thiserror::Error, Diagnostic)]
+#[error("{msg}")]
+#[diagnostic(code(InvalidArguments))]
+pub struct InvalidArguments {
+    pub msg: String,
+    #[help]
+    pub help: String,
+    /// The relevant part of the command line, e.g. `cargo uv print --git-tag`.
+    #[source_code]
+    pub source_code: String,
+    #[label("{label_msg}")]
+    pub label: (usize, usize),
+    pub label_msg: String,
+}
+
+impl InvalidArguments {
+    /// Labels `arg` at the end of `cargo uv <action> <arg>`, or the action if `arg` is empty.
+    pub fn new(
+        action: Action,
+        arg: &str,
+        label_msg: impl Into<String>,
+        msg: impl Into<String>,
+        help: impl Into<String>,
+    ) -> Self {
+        let source_code = format!("cargo uv {action} {arg}").trim_end().to_string();
+        let label = match arg.is_empty() {
+            true => (9, action.to_string().len()),
+            false => (source_code.len() - arg.len(), arg.len()),
+        };
+        Self {
+            msg: msg.into(),
+            help: help.into(),
+            source_code,
+            label,
+            label_msg: label_msg.into(),
+        }
+    }
 }
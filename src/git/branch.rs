@@ -1,21 +1,32 @@
 use std::{fmt::Display, str::FromStr};
 
 use clap::builder::OsStr;
+use miette::bail;
+
+/// Marker for a detached `HEAD`, as printed by `git rev-parse --abbrev-ref HEAD`.
+pub static DETACHED_HEAD: &str = "HEAD";
 
 #[derive(Debug, PartialEq, Eq, Default, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Branch {
     #[default]
     Current,
+    /// `HEAD` is not on a branch.
+    Detached,
+    /// A local branch, or a remote-tracking branch when `remote` is set, e.g. `origin/main`.
     Named {
         local: String,
+        remote: Option<String>,
     },
 }
 
 impl AsRef<str> for Branch {
+    /// The name of the branch without the remote, see [`Display`] for `<remote>/<branch>`.
     fn as_ref(&self) -> &str {
         match self {
             Branch::Current => ".",
-            Branch::Named { local } => local,
+            Branch::Detached => DETACHED_HEAD,
+            Branch::Named { local, .. } => local,
         }
     }
 }
@@ -29,6 +40,14 @@ impl Branch {
         matches!(self, Self::Current)
     }
 
+    /// Returns `true` if the branch is [`Detached`].
+    ///
+    /// [`Detached`]: Branch::Detached
+    #[must_use]
+    pub fn is_detached(&self) -> bool {
+        matches!(self, Self::Detached)
+    }
+
     /// Returns `true` if the branch is [`Named`].
     ///
     /// [`Named`]: Branch::Named
@@ -38,7 +57,7 @@ impl Branch {
     }
 
     pub fn as_named(&self) -> Option<&String> {
-        if let Self::Named { local } = self {
+        if let Self::Named { local, .. } = self {
             Some(local)
         } else {
             None
@@ -46,54 +65,197 @@ impl Branch {
     }
 
     pub fn try_into_named(self) -> Result<String, Self> {
-        if let Self::Named { local } = self {
+        if let Self::Named { local, .. } = self {
             Ok(local)
         } else {
             Err(self)
         }
     }
+
+    /// The remote of a remote-tracking branch.
+    pub fn remote(&self) -> Option<&str> {
+        if let Self::Named { remote, .. } = self {
+            remote.as_deref()
+        } else {
+            None
+        }
+    }
+
+    /// Parses a local branch, which may contain a `/`, checking it is a valid git ref name.
+    pub fn local(name: &str) -> miette::Result<Branch> {
+        let name = name.trim();
+        if name.is_empty() || name == "." {
+            return Ok(Branch::Current);
+        }
+        check_ref_name(name)?;
+        Ok(Branch::from(name.to_string()))
+    }
+}
+
+/// Checks `name` against the rules of `git check-ref-format --branch`.
+///
+/// The error names the part of `name` that breaks the rule.
+pub fn check_ref_name(name: &str) -> miette::Result<()> {
+    const HELP: &str = "Branch names can't contain '..', '@{', '//', spaces, control characters or any of '~^:?*[\\', start or end with '/' or end with '.' or '.lock'.";
+    for part in ["..", "@{", "//"] {
+        if name.contains(part) {
+            bail!(
+                help = HELP,
+                "Invalid branch name '{name}': contains '{part}'"
+            );
+        }
+    }
+    if let Some(c) = name
+        .chars()
+        .find(|c| c.is_control() || c.is_whitespace() || "~^:?*[\\".contains(*c))
+    {
+        bail!(help = HELP, "Invalid branch name '{name}': contains {c:?}");
+    }
+    if name.starts_with('/') || name.ends_with('/') {
+        bail!(
+            help = HELP,
+            "Invalid branch name '{name}': starts or ends with '/'"
+        );
+    }
+    if let Some(end) = [".lock", "."].into_iter().find(|end| name.ends_with(end)) {
+        bail!(
+            help = HELP,
+            "Invalid branch name '{name}': ends with '{end}'"
+        );
+    }
+    if name == "@" || name.starts_with('-') {
+        bail!(help = HELP, "Invalid branch name '{name}'");
+    }
+    Ok(())
 }
 
 impl Display for Branch {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let text = match self {
-            Branch::Current => ".",
-            Branch::Named { local, .. } => local,
-        };
-
-        write!(f, "{text}")
+        match self {
+            Branch::Named {
+                local,
+                remote: Some(remote),
+            } => write!(f, "{remote}/{local}"),
+            _ => write!(f, "{}", self.as_ref()),
+        }
     }
 }
 
 impl FromStr for Branch {
     type Err = miette::Error;
 
+    /// Parses `.` as [`Branch::Current`], `HEAD` as [`Branch::Detached`] and
+    /// `<remote>/<branch>` as a remote-tracking branch.
+    ///
+    /// Use [`From<String>`] for a local branch with a `/` in its name.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let s = s.trim();
         if s.is_empty() || s == "." {
             return Ok(Branch::Current);
         }
+        if s == DETACHED_HEAD {
+            return Ok(Branch::Detached);
+        }
+        check_ref_name(s)?;
 
-        Ok(Self::Named {
-            local: String::from(s),
+        Ok(match s.split_once('/') {
+            Some((remote, local)) if !remote.is_empty() && !local.is_empty() => Self::Named {
+                local: String::from(local),
+                remote: Some(String::from(remote)),
+            },
+            _ => Self::Named {
+                local: String::from(s),
+                remote: None,
+            },
         })
     }
 }
 
 impl From<Branch> for clap::builder::OsStr {
     fn from(branch: Branch) -> Self {
-        match branch {
-            Branch::Current => OsStr::from("."),
-            Branch::Named { local } => OsStr::from(local),
-        }
+        OsStr::from(branch.to_string())
     }
 }
 
+/// Always a local branch, `.` is [`Branch::Current`].
 impl From<String> for Branch {
     fn from(branch: String) -> Self {
         match branch.as_str() {
             "." => Branch::Current,
-            _ => Branch::Named { local: branch },
+            _ => Branch::Named {
+                local: branch,
+                remote: None,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_str_remote_tracking() {
+        let branch = Branch::from_str("origin/main").unwrap();
+        assert_eq!(
+            branch,
+            Branch::Named {
+                local: "main".into(),
+                remote: Some("origin".into()),
+            }
+        );
+        assert_eq!(branch.to_string(), "origin/main");
+        assert_eq!(branch.as_ref(), "main");
+        assert_eq!(branch.remote(), Some("origin"));
+    }
+
+    #[test]
+    fn from_str_current_and_detached() {
+        assert_eq!(Branch::from_str(".").unwrap(), Branch::Current);
+        assert_eq!(Branch::from_str("").unwrap(), Branch::Current);
+        assert_eq!(Branch::from_str("HEAD").unwrap(), Branch::Detached);
+        assert_eq!(Branch::Detached.to_string(), "HEAD");
+    }
+
+    #[test]
+    fn from_str_rejects_invalid_names() {
+        for (name, error) in [
+            ("feat/..bad", "contains '..'"),
+            ("my branch", "contains ' '"),
+            ("main@{1}", "contains '@{'"),
+            ("/main", "starts or ends with '/'"),
+            ("main/", "starts or ends with '/'"),
+            ("main.lock", "ends with '.lock'"),
+            ("tab\there", "contains '\\t'"),
+        ] {
+            let err = Branch::from_str(name).unwrap_err().to_string();
+            assert!(err.contains(error), "{name}: {err}");
         }
     }
+
+    #[test]
+    fn local_keeps_slashes() {
+        assert_eq!(
+            Branch::local("feature/new").unwrap(),
+            Branch::Named {
+                local: "feature/new".into(),
+                remote: None,
+            }
+        );
+        assert_eq!(Branch::local(".").unwrap(), Branch::Current);
+        assert!(Branch::local("feature/..new").is_err());
+    }
+
+    #[test]
+    fn from_str_local() {
+        assert_eq!(
+            Branch::from_str("main").unwrap(),
+            Branch::Named {
+                local: "main".into(),
+                remote: None,
+            }
+        );
+        assert!(Branch::from_str("feature/new").is_ok());
+        assert!(Branch::from_str("release-1.2").is_ok());
+    }
 }
@@ -0,0 +1,94 @@
+use semver::Version;
+
+use crate::PushedRef;
+
+#[derive(Debug, thiserror::Error, miette::Diagnostic)]
+#[diagnostic(url(docsrs))]
+pub enum GitError {
+    #[error("HEAD is detached, not on a branch")]
+    #[diagnostic(
+        code(GitError::DetachedHead),
+        help(
+            "Check out a branch first, e.g. 'git checkout <branch>' or 'git switch -c <branch>'."
+        )
+    )]
+    DetachedHead,
+    #[error("Version {version} is already tagged as {tag}")]
+    #[diagnostic(
+        code(GitError::TagExists),
+        help("Choose another version, or use '--force-git' to release it again.")
+    )]
+    TagExists { tag: String, version: Version },
+    #[error("Can't amend {0}, it has already been pushed")]
+    #[diagnostic(
+        code(GitError::AmendPushed),
+        help("Run without '--amend' to create a new release commit.")
+    )]
+    AmendPushed(String),
+    #[error("Can't amend {0}, it isn't a version bump commit")]
+    #[diagnostic(
+        code(GitError::AmendNotVersionBump),
+        help("'--amend' only amends a commit that only changes Cargo.toml and Cargo.lock files.")
+    )]
+    AmendNotVersionBump(String),
+    #[error("Remote {remote} does not exist")]
+    #[diagnostic(
+        code(GitError::RemoteNotFound),
+        help("Available remotes: {}", available.join(", "))
+    )]
+    RemoteNotFound {
+        remote: String,
+        available: Vec<String>,
+    },
+    #[error("Branch {0} does not exist")]
+    #[diagnostic(
+        code(GitError::BranchNotFound),
+        help("Create it first, e.g. 'git branch {0}', or pass an existing branch to '--branch'.")
+    )]
+    BranchNotFound(String),
+    #[error("{package} is {found} on {branch} but was {expected} before switching")]
+    #[diagnostic(
+        code(GitError::BranchVersionMismatch),
+        help(
+            "The new versions are worked out before switching, run cargo-uv from {branch} instead."
+        )
+    )]
+    BranchVersionMismatch {
+        branch: String,
+        package: String,
+        expected: Version,
+        found: Version,
+    },
+    #[error("{branch} is {behind} commit/s behind {remote}/{branch}")]
+    #[diagnostic(
+        code(GitError::BehindRemote),
+        help("Pull the remote changes first, e.g. 'git pull {remote} {branch}'.")
+    )]
+    BehindRemote {
+        remote: String,
+        branch: String,
+        behind: usize,
+    },
+    #[error("Push to {remote} rejected: {}", display_refs(.refs))]
+    #[diagnostic(
+        code(GitError::PushRejected),
+        help("Pull the remote changes, e.g. 'git pull --rebase {remote}', then push again.")
+    )]
+    PushRejected {
+        remote: String,
+        refs: Vec<PushedRef>,
+    },
+    #[error("The dry run changed the repository: {}", changes.join(", "))]
+    #[diagnostic(
+        code(GitError::DryRunNotClean),
+        help("Restore the changed files with 'git restore <file>' and delete any new tags.")
+    )]
+    DryRunNotClean { changes: Vec<String> },
+}
+
+fn display_refs(refs: &[PushedRef]) -> String {
+    refs.iter()
+        .map(|r| r.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -6,18 +6,20 @@ use std::{
 };
 
 use indexmap::IndexSet;
-use miette::bail;
-use semver::Version;
+use miette::{Context, IntoDiagnostic, bail};
 use tracing::{debug, info, instrument, warn};
 
-#[cfg(feature = "unstable")]
-use miette::Context;
-
 use crate::{
-    Branch, Process, ProcessOutput, Result, cli::Suppress, current_span, git::git_file::GitFiles,
+    Branch, Process, ProcessOutput, Result,
+    cli::Suppress,
+    current_span,
+    git::{GitError, PushFlag, PushedRef, git_file::GitFiles},
     process::OutputExt,
 };
 
+/// Prefix of the message of the stashes made by [`Git::stash`].
+const STASH_MESSAGE: &str = "cargo-uv-autostash";
+
 /// Used to indicate if the Root Dir is Set and can be used.
 #[derive(Debug)]
 pub struct NoRootDirSet;
@@ -92,7 +94,7 @@ impl Git<NoRootDirSet> {
         if !quiet {
             cmd.stdout(Stdio::inherit());
         }
-        // cmd.stderr(Stdio::piped());
+        cmd.stderr(Stdio::piped());
         cmd
     }
 }
@@ -109,6 +111,7 @@ impl Git<PathBuf> {
         if !quiet {
             cmd.stdout(Stdio::inherit());
         }
+        cmd.stderr(Stdio::piped());
         cmd
     }
 
@@ -117,23 +120,90 @@ impl Git<PathBuf> {
     }
 
     #[instrument(skip_all)]
-    /// Adds all cargo files (Cargo.toml, Cargo.lock) in whole project to git.
+    /// Adds the files, i.e. the known Cargo.toml and Cargo.lock files, to git.
     ///
     /// Equivilent to: `git add Cargo.toml Cargo.lock`
     ///
-    /// TODO: Confirm if file is in git ignore it doesn't add them.
-    /// BUG: #28 Git add fetal if doesn't match path spec. Change to generate adds of known files.
-    /// add 'Cargo.lock'
-    /// add 'Cargo.toml'
-    /// add 'pack1/Cargo.toml'
-    /// add 'pack2/Cargo.toml'
-    pub fn add_files(&self, files: &Vec<PathBuf>) -> miette::Result<()> {
-        let mut git = self.command(false);
+    /// Files ignored by git are skipped. Relative paths are relative to the root directory.
+    ///
+    /// ## Errors
+    ///
+    /// - Any of the files don't exist, listing the missing files.
+    /// - `git add` fails.
+    pub fn add_files(&self, files: &[PathBuf]) -> miette::Result<()> {
+        let missing: Vec<String> = files
+            .iter()
+            .filter(|file| !self.root_directory.join(file).exists())
+            .map(|file| file.display().to_string())
+            .collect();
+        if !missing.is_empty() {
+            bail!(
+                help = "Check the files were not moved or deleted during the run.",
+                "Files to add to git do not exist:\n{}",
+                missing.join("\n")
+            )
+        }
+
+        let ignored = self.ignored_files(files)?;
+        let files: Vec<&PathBuf> = files
+            .iter()
+            .filter(|file| {
+                let is_ignored = ignored.contains(file);
+                if is_ignored {
+                    info!("Skipping ignored file: {}", file.display());
+                }
+                !is_ignored
+            })
+            .collect();
+        if files.is_empty() {
+            info!("No files to stage");
+            return Ok(());
+        }
 
+        let mut git = self.command(false);
         info!("Staging files: {:?}", files);
-        git.args(["add", "-v"]);
+        git.args(["add", "-v", "--"]);
         git.args(files);
-        Process::Output.run(git).map(|_| ())
+        let cmd = Process::display_command(&git);
+        let output = match Process::Output.run(git)? {
+            ProcessOutput::Output(output) => output,
+            _ => unreachable!(),
+        };
+        if !output.status.success() {
+            bail!(
+                help = format!("Failed to run `{cmd}`"),
+                "{}",
+                output.stderr().trim_end()
+            )
+        }
+        Ok(())
+    }
+
+    /// Returns the files which are ignored by git.
+    ///
+    /// Equivilent to: `git check-ignore -- <files>`
+    #[instrument(skip_all)]
+    pub fn ignored_files(&self, files: &[PathBuf]) -> miette::Result<Vec<PathBuf>> {
+        if files.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut git = self.command(true);
+        git.args(["check-ignore", "--"]);
+        git.args(files);
+        let output = match Process::Output.run(git)? {
+            ProcessOutput::Output(output) => output,
+            _ => unreachable!(),
+        };
+        // Exit code 1 means none of the files are ignored.
+        match output.status.code() {
+            Some(0) => Ok(output.stdout().lines().map(PathBuf::from).collect()),
+            Some(1) => Ok(Vec::new()),
+            _ => bail!(
+                help = "Failed to run 'git check-ignore'",
+                "{}",
+                output.stderr().trim_end()
+            ),
+        }
     }
 }
 
@@ -142,7 +212,8 @@ impl Git<PathBuf> {
     #[instrument(skip_all)]
     pub fn dirty_files(&self) -> miette::Result<GitFiles> {
         let mut git = self.command(true);
-        git.args(["status", "--short"]);
+        // Files in untracked directories are listed so `--dirty-allow` can match them.
+        git.args(["status", "--short", "--untracked-files=all"]);
         let stdout = match Process::Output.run(git)? {
             ProcessOutput::Output(output) => {
                 if output.status.success() {
@@ -162,31 +233,154 @@ impl Git<PathBuf> {
         }
     }
 
+    /// Checks the dirty files are still `dirty_files` and `tag` doesn't exist, after a dry run.
+    #[instrument(skip(self))]
+    pub fn verify_clean(&self, dirty_files: &[PathBuf], tag: Option<&str>) -> Result<()> {
+        let mut changes: Vec<String> = self
+            .dirty_files()?
+            .into_iter()
+            .filter(|file| !dirty_files.contains(&file.path))
+            .map(|file| format!("{file} changed"))
+            .collect();
+        if let Some(tag) = tag
+            && self.tag_exists(tag)?
+        {
+            changes.push(format!("tag {tag} still exists"));
+        }
+        if !changes.is_empty() {
+            Err(GitError::DryRunNotClean { changes })?;
+        }
+        Ok(())
+    }
+
     #[instrument(skip_all)]
-    pub fn commit(&self, message: &str, suppress: Suppress, dry_run: bool) -> miette::Result<()> {
+    /// Commits the staged files.
+    ///
+    /// Equivilent to: `git commit [--dry-run] [--amend] [--gpg-sign] [--author <author>] --file - [-- <paths>]`
+    ///
+    /// The message is piped on stdin so multi-line messages are used verbatim.
+    ///
+    /// Only `paths` are committed, leaving any other staged changes, unless `paths` is empty.
+    /// Files ignored by git are skipped as they can't be committed.
+    ///
+    /// Check the previous commit can be amended with [`Git::check_amend`] first.
+    #[allow(clippy::too_many_arguments)]
+    pub fn commit(
+        &self,
+        message: &str,
+        paths: &[PathBuf],
+        suppress: Suppress,
+        dry_run: bool,
+        sign: bool,
+        author: Option<&str>,
+        amend: bool,
+    ) -> miette::Result<()> {
         let mut git = self.command(suppress.includes_git());
         info!("Creating commit");
         git.args(["commit"]);
 
+        if amend {
+            info!("Amending the previous commit");
+            git.arg("--amend");
+        }
         if dry_run {
             git.arg("--dry-run");
         }
+        if sign {
+            git.arg("--gpg-sign");
+        }
+        if let Some(author) = author {
+            git.args(["--author", author]);
+        }
 
-        git.args(["--message", message]);
+        git.args(["--file", "-"]);
+        if !paths.is_empty() {
+            let ignored = self.ignored_files(paths)?;
+            git.arg("--");
+            git.args(paths.iter().filter(|path| !ignored.contains(path)));
+        }
         let cmd = Process::display_command(&git);
-        let run = Process::Output.run(git)?;
-        let output = run.as_output().unwrap();
+        let output = Process::run_with_stdin(git, message)?;
         if output.status.success() {
+            if !suppress.includes_git() {
+                print!("{}", output.stdout());
+            }
             Ok(())
         } else {
-            miette::bail!("Failed to run `{cmd}`")
+            miette::bail!(
+                help = format!("Failed to run `{cmd}`"),
+                "{}",
+                output.stderr().trim_end()
+            )
         }
     }
 
+    /// Returns the SHA of `HEAD`.
+    ///
+    /// Equivilent to: `git rev-parse HEAD`
+    #[instrument(skip_all)]
+    pub fn head(&self) -> Result<String> {
+        Ok(self.output(&["rev-parse", "HEAD"])?.trim().to_string())
+    }
+
+    /// Checks `HEAD` is an unpushed version bump, so is safe to amend.
+    ///
+    /// A version bump only changes `Cargo.toml` and `Cargo.lock` files.
+    #[instrument(skip_all)]
+    pub fn check_amend(&self) -> Result<()> {
+        let head = self.output(&["rev-parse", "--short", "HEAD"])?;
+        let head = head.trim();
+
+        // Empty when HEAD is reachable from a remote branch.
+        let unpushed = self.output(&["rev-list", "--max-count=1", "HEAD", "--not", "--remotes"])?;
+        if unpushed.trim().is_empty() {
+            Err(GitError::AmendPushed(head.to_string()))?;
+        }
+
+        let changed = self.output(&[
+            "diff-tree",
+            "--root",
+            "--no-commit-id",
+            "--name-only",
+            "-r",
+            "HEAD",
+        ])?;
+        let version_bump = !changed.trim().is_empty()
+            && changed.lines().all(|path| {
+                Path::new(path)
+                    .file_name()
+                    .is_some_and(|name| name == "Cargo.toml" || name == "Cargo.lock")
+            });
+        if !version_bump {
+            Err(GitError::AmendNotVersionBump(head.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Runs a quiet git command, returning the stdout.
+    fn output(&self, args: &[&str]) -> Result<String> {
+        let mut git = self.command(true);
+        git.args(args);
+        let cmd = Process::display_command(&git);
+        let output = match Process::Output.run(git)? {
+            ProcessOutput::Output(output) => output,
+            _ => unreachable!(),
+        };
+        if !output.status.success() {
+            bail!(
+                help = format!("Failed to run `{cmd}`"),
+                "{}",
+                output.stderr().trim_end()
+            )
+        }
+        Ok(output.stdout())
+    }
+
+    /// Runs `git tag` with any additional arguments on the tag generated by [`Git::generate_tag`].
     #[instrument(skip_all)]
     pub fn tag(
         &self,
-        version: &Version,
+        tag: &str,
         suppress: Suppress,
         args: Option<Vec<&str>>,
     ) -> miette::Result<()> {
@@ -195,55 +389,378 @@ impl Git<PathBuf> {
         if let Some(a) = args {
             git.args(a);
         }
-        git.args([&self.generate_tag(version)]);
+        git.arg(tag);
+        let cmd = Process::display_command(&git);
         let output = match Process::Output.run(git)? {
             ProcessOutput::Output(output) => output,
             _ => unreachable!(),
         };
         if !output.status.success() {
             tracing::debug!("stderr: {}", output.stderr());
-            bail!("Failed to tag repository.")
+            bail!(
+                help = format!("Failed to tag repository with: `{cmd}`"),
+                "{}",
+                output.stderr().trim_end()
+            )
         }
         Ok(())
     }
 
+    /// Returns `true` if the tag already exists.
+    ///
+    /// Equivilent to: `git tag --list <tag>`
+    #[instrument(skip_all, fields(tag))]
+    pub fn tag_exists(&self, tag: &str) -> Result<bool> {
+        current_span!().record("tag", tag);
+        let mut git = self.command(true);
+        git.args(["tag", "--list", tag]);
+        let output = match Process::Output.run(git)? {
+            ProcessOutput::Output(output) => output,
+            _ => unreachable!(),
+        };
+        if !output.status.success() {
+            bail!(
+                help = "Failed to run 'git tag --list'",
+                "{}",
+                output.stderr().trim_end()
+            )
+        }
+        Ok(output.stdout().lines().any(|line| line.trim() == tag))
+    }
+
+    /// Returns `true` if the repository has any tag, i.e. it has been released before.
+    ///
+    /// Equivilent to: `git tag --list`
+    #[instrument(skip_all)]
+    pub fn has_any_tag(&self) -> Result<bool> {
+        Ok(!self.output(&["tag", "--list"])?.trim().is_empty())
+    }
+
+    /// Returns the most recent tag reachable from `HEAD`, [`None`] if there isn't one.
+    ///
+    /// Equivilent to: `git describe --tags --abbrev=0`
+    #[instrument(skip_all)]
+    pub fn last_tag(&self) -> Result<Option<String>> {
+        let mut git = self.command(true);
+        git.args(["describe", "--tags", "--abbrev=0"]);
+        let output = Process::Output.run(git)?.try_into_output()?;
+        if output.status.success() {
+            return Ok(Some(output.stdout().trim().to_string()));
+        }
+        let stderr = output.stderr();
+        if stderr.contains("No names found") || stderr.contains("No tags can describe") {
+            debug!("No tag reachable from HEAD");
+            return Ok(None);
+        }
+        bail!(
+            help = "Failed to run 'git describe --tags --abbrev=0'",
+            "{}",
+            stderr.trim_end()
+        )
+    }
+
+    /// Returns the subjects of the commits since `tag`, or of every commit if [`None`], newest
+    /// first.
+    ///
+    /// Equivilent to: `git log [<tag>..HEAD] --pretty=%s`
+    #[instrument(skip_all, fields(tag))]
+    pub fn log_subjects(&self, tag: Option<&str>) -> Result<Vec<String>> {
+        current_span!().record("tag", tag);
+        let range = tag.map(|tag| format!("{tag}..HEAD"));
+        let mut args = vec!["log"];
+        args.extend(range.as_deref());
+        args.push("--pretty=%s");
+        let stdout = self.output(&args)?;
+        Ok(stdout.lines().map(str::to_string).collect())
+    }
+
+    /// Extra arguments for [`Git::tag`] to create an annotated or signed tag.
+    ///
+    /// Returns [`None`] for a lightweight tag.
+    pub fn tag_args(annotate: bool, sign: bool, message: &str) -> Option<Vec<&str>> {
+        let kind = match (annotate, sign) {
+            (_, true) => "--sign",
+            (true, false) => "--annotate",
+            (false, false) => return None,
+        };
+        Some(vec![kind, "--message", message])
+    }
+
+    /// Generates the tag name from the `tag_format` template.
+    ///
+    /// `{version}` is replaced by the version and `{name}` by the package name.
+    ///
+    /// ## Errors
+    ///
+    /// - `tag_format` doesn't contain `{version}`.
+    /// - `tag_format` contains `{name}` but no package name is given.
     #[instrument(skip_all)]
-    pub fn generate_tag(&self, version: impl Display) -> String {
-        let tag = version.to_string();
+    pub fn generate_tag(
+        &self,
+        tag_format: &str,
+        name: Option<&str>,
+        version: impl Display,
+    ) -> Result<String> {
+        if !tag_format.contains("{version}") {
+            bail!(
+                help = "Add '{{version}}' to the '--tag-format'.",
+                "Tag format '{tag_format}' must contain '{{version}}'."
+            )
+        }
+        let mut tag = tag_format.replace("{version}", &version.to_string());
+        if tag.contains("{name}") {
+            let Some(name) = name else {
+                bail!(
+                    help = "'{{name}}' requires a root package. Use '--tag-prefix' or remove '{{name}}' from '--tag-format'.",
+                    "No package name for tag format '{tag_format}'."
+                )
+            };
+            tag = tag.replace("{name}", name);
+        }
         debug! {"Tag: {tag}"};
-        tag
+        Ok(tag)
+    }
+
+    /// Renders the commit and tag message from the `--message-template`.
+    ///
+    /// `{name}` is replaced by the package name, `{version}` by the new version and
+    /// `{previous}` by the version before bumping. Any other placeholder is left as written.
+    ///
+    /// ## Errors
+    ///
+    /// - `template` contains `{name}` but no package name is given.
+    pub fn render_message(
+        template: &str,
+        name: Option<&str>,
+        version: impl Display,
+        previous: impl Display,
+    ) -> Result<String> {
+        let mut message = template
+            .replace("{version}", &version.to_string())
+            .replace("{previous}", &previous.to_string());
+        if message.contains("{name}") {
+            let Some(name) = name else {
+                bail!(
+                    help = "'{{name}}' requires a root package. Remove '{{name}}' from '--message-template'.",
+                    "No package name for message template '{template}'."
+                )
+            };
+            message = message.replace("{name}", name);
+        }
+        debug! {"Message: {message}"};
+        Ok(message)
     }
 
-    /// Pushed just the tag to the remotes
-    #[instrument(skip_all, fields(dry_run))]
+    /// Pushes the tag to the remote, and the current branch if `push_branch` is set.
+    ///
+    /// Equivilent to: `git push [-u] <remote> [<branch>] [<tag_refspec>] --porcelain`
+    ///
+    /// `-u` is only added when the branch has no upstream.
+    #[instrument(skip_all, fields(dry_run, push_branch))]
     pub fn push(
         &self,
-        tag: &str,
+        tag_refspec: Option<&str>,
         suppress: Suppress,
         dry_run: bool,
         remote: &str,
+        push_branch: bool,
     ) -> miette::Result<Child> {
         current_span!().record("dry_run", dry_run);
-        let tag_string = String::from("tags/") + tag;
+        current_span!().record("push_branch", push_branch);
+        let git_push = self.push_command(tag_refspec, suppress, dry_run, remote, push_branch)?;
+        match Process::Spawn.run(git_push) {
+            Ok(ProcessOutput::Child(child)) => Ok(child),
+            Err(e) => Err(e),
+            _ => unreachable!(),
+        }
+    }
+
+    /// The `git push` command for [`Git::push`], `tag_refspec` is e.g. `tags/<tag>`.
+    fn push_command(
+        &self,
+        tag_refspec: Option<&str>,
+        suppress: Suppress,
+        dry_run: bool,
+        remote: &str,
+        push_branch: bool,
+    ) -> miette::Result<Command> {
+        if tag_refspec.is_none() && !push_branch {
+            bail!("Nothing to push, expected a tag or the branch.")
+        }
         info!("Pushing to remote: {remote}");
         let mut git_push = self.command(suppress.includes_git());
         git_push.arg("push");
         if dry_run {
             git_push.arg("--dry-run");
         }
-        git_push.args([remote, &tag_string, "--porcelain"]);
-        match Process::Spawn.run(git_push) {
-            Ok(ProcessOutput::Child(child)) => Ok(child),
-            Err(e) => Err(e),
+        let branch = if push_branch {
+            let branch = self.attached_branch()?;
+            if !self.has_upstream()? {
+                info!("Setting upstream for {branch} to {remote}");
+                git_push.arg("--set-upstream");
+            }
+            Some(branch)
+        } else {
+            None
+        };
+        git_push.arg(remote);
+        if let Some(branch) = branch {
+            git_push.arg(branch.as_ref());
+        }
+        if let Some(tag_refspec) = tag_refspec {
+            git_push.arg(tag_refspec);
+        }
+        git_push.arg("--porcelain");
+        // Captured to parse the porcelain output and decide if a failed push can be retried.
+        git_push.stdout(Stdio::piped());
+        git_push.stderr(Stdio::piped());
+        Ok(git_push)
+    }
+
+    /// Returns `true` if the `git push` stderr shows a transient network failure worth retrying.
+    ///
+    /// Authentication and rejected pushes are never transient.
+    pub fn is_transient_push_failure(stderr: &str) -> bool {
+        const PERMANENT: [&str; 5] = [
+            "authentication failed",
+            "permission denied",
+            "could not read username",
+            "[rejected]",
+            "does not appear to be a git repository",
+        ];
+        const TRANSIENT: [&str; 9] = [
+            "could not resolve host",
+            "connection timed out",
+            "connection reset",
+            "connection refused",
+            "operation timed out",
+            "failed to connect",
+            "the remote end hung up unexpectedly",
+            "early eof",
+            "rpc failed",
+        ];
+        let stderr = stderr.to_lowercase();
+        !PERMANENT.iter().any(|msg| stderr.contains(msg))
+            && TRANSIENT.iter().any(|msg| stderr.contains(msg))
+    }
+
+    /// Returns `true` if the `git push --porcelain` output shows nothing needed pushing.
+    ///
+    /// Either git says `Everything up-to-date` or every ref is `=` up to date, so a non-zero exit
+    /// isn't a failure.
+    pub fn is_push_up_to_date(stdout: &str, stderr: &str) -> bool {
+        let refs = PushedRef::parse_porcelain(stdout);
+        if refs.is_empty() {
+            return stderr.contains("Everything up-to-date");
+        }
+        refs.iter().all(|pushed| pushed.flag == PushFlag::UpToDate)
+    }
+
+    /// Returns `true` if the current branch has an upstream branch set.
+    ///
+    /// Equivilent to: `git rev-parse --abbrev-ref --symbolic-full-name @{upstream}`
+    #[instrument(skip_all)]
+    pub fn has_upstream(&self) -> Result<bool> {
+        let mut git = self.command(true);
+        git.args([
+            "rev-parse",
+            "--abbrev-ref",
+            "--symbolic-full-name",
+            "@{upstream}",
+        ]);
+        match Process::Output.run(git)? {
+            ProcessOutput::Output(output) => Ok(output.status.success()),
             _ => unreachable!(),
         }
     }
 
-    /// Returns a list of remotes for the current branch.
+    /// Fetches `remote`.
     ///
-    /// Returns an error if the list is empty
+    /// Equivilent to: `git fetch <remote>`
+    #[instrument(skip_all, fields(remote))]
+    pub fn fetch(&self, remote: &str, suppress: Suppress) -> Result<()> {
+        current_span!().record("remote", remote);
+        let mut git = self.command(suppress.includes_git());
+        git.args(["fetch", remote]);
+        let output = match Process::Output.run(git)? {
+            ProcessOutput::Output(output) => output,
+            _ => unreachable!(),
+        };
+        if !output.status.success() {
+            bail!(
+                help = format!("Failed to run 'git fetch {remote}'"),
+                "{}",
+                output.stderr().trim_end()
+            )
+        }
+        Ok(())
+    }
+
+    /// Returns the number of commits on `<remote>/<branch>` that are not on `HEAD`.
+    ///
+    /// Returns `0` if the branch has not been pushed to the remote.
+    ///
+    /// Equivilent to: `git rev-list --count HEAD..<remote>/<branch>`
+    #[instrument(skip_all, fields(remote))]
+    pub fn commits_behind(&self, remote: &str, branch: &Branch) -> Result<usize> {
+        current_span!().record("remote", remote);
+        let remote_branch = format!("refs/remotes/{remote}/{branch}");
+        let mut git = self.command(true);
+        git.args(["rev-parse", "--verify", "--quiet", &remote_branch]);
+        match Process::Output.run(git)? {
+            ProcessOutput::Output(output) if !output.status.success() => {
+                debug!("{remote_branch} does not exist");
+                return Ok(0);
+            }
+            ProcessOutput::Output(_) => {}
+            _ => unreachable!(),
+        }
+
+        let mut git = self.command(true);
+        git.args(["rev-list", "--count", &format!("HEAD..{remote_branch}")]);
+        let output = match Process::Output.run(git)? {
+            ProcessOutput::Output(output) => output,
+            _ => unreachable!(),
+        };
+        if !output.status.success() {
+            bail!(
+                help = "Failed to run 'git rev-list --count'",
+                "{}",
+                output.stderr().trim_end()
+            )
+        }
+        output.stdout().trim().parse().into_diagnostic()
+    }
+
+    /// Fetches `remote` and errors if the current branch is behind it.
+    ///
+    /// The fetch is skipped on a dry run, comparing against the last fetch.
+    #[instrument(skip_all, fields(remote, dry_run))]
+    pub fn check_up_to_date(&self, remote: &str, suppress: Suppress, dry_run: bool) -> Result<()> {
+        current_span!().record("remote", remote);
+        current_span!().record("dry_run", dry_run);
+        if dry_run {
+            info!("Skipping 'git fetch {remote}' on a dry run");
+        } else {
+            self.fetch(remote, suppress)?;
+        }
+        let branch = self.attached_branch()?;
+        let behind = self.commits_behind(remote, &branch)?;
+        if behind > 0 {
+            Err(GitError::BehindRemote {
+                remote: remote.to_string(),
+                branch: branch.to_string(),
+                behind,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Returns all remotes in the repository.
+    ///
+    /// Equivilent to: `git remote`
     #[instrument(skip_all)]
-    pub fn remotes(&self) -> miette::Result<Vec<String>> {
+    pub fn all_remotes(&self) -> miette::Result<Vec<String>> {
         let mut git = self.command(true);
         git.args(["remote"]);
 
@@ -252,32 +769,69 @@ impl Git<PathBuf> {
             _ => unreachable!(),
         };
 
-        let remotes: Vec<String> = stdout.lines().map(String::from).collect();
+        Ok(stdout.lines().map(String::from).collect())
+    }
 
-        let mut branch_remotes = IndexSet::new();
+    /// Returns the remote if it exists in the repository.
+    #[instrument(skip_all)]
+    pub fn named_remote(&self, remote: &str) -> Result<String> {
+        let available = self.all_remotes()?;
+        if !available.iter().any(|r| r == remote) {
+            Err(GitError::RemoteNotFound {
+                remote: remote.to_string(),
+                available,
+            })?;
+        }
+        Ok(remote.to_string())
+    }
 
-        for line in self.branch(vec!["--remotes"])?.lines() {
-            let valid_remote = match line.split_once('/') {
-                Some((remote, _branch)) => remote.trim().to_string(),
-                None => {
-                    warn!("Ensure you only run command on a branch with a remote.");
-                    bail!(
-                        help = "Use the branch flag to change to valid branch",
-                        "Failed to find remote for current branch."
-                    )
-                }
-            };
-            assert!(remotes.contains(&valid_remote));
+    /// Returns the remotes of `branch`, the current branch for [`Branch::Current`].
+    ///
+    /// These are the upstream from `git config --get branch.<branch>.remote` and every remote
+    /// with a `<remote>/<branch>` remote-tracking branch, other branches being pushed to a
+    /// remote doesn't count.
+    ///
+    /// Returns an error if the list is empty
+    #[instrument(skip_all)]
+    pub fn remotes(&self, branch: &Branch) -> miette::Result<Vec<String>> {
+        let branch = match branch {
+            Branch::Current => self.attached_branch()?,
+            branch => branch.clone(),
+        };
+        let name = branch.as_ref();
+        let remotes = self.all_remotes()?;
 
-            branch_remotes.insert(valid_remote);
+        let mut branch_remotes = IndexSet::new();
+        let mut git = self.command(true);
+        git.args(["config", "--get", &format!("branch.{name}.remote")]);
+        let output = Process::Output.run(git)?.try_into_output()?;
+        if output.status.success() {
+            branch_remotes.insert(output.stdout().trim().to_string());
+        }
+        for line in self.branch(vec!["--remotes"])?.lines() {
+            // Skips symbolic refs, e.g. `origin/HEAD -> origin/main`.
+            if let Some((remote, remote_branch)) = line.trim().split_once('/')
+                && remote_branch == name
+            {
+                branch_remotes.insert(remote.to_string());
+            }
         }
+        branch_remotes.retain(|remote| {
+            let known = remotes.contains(remote);
+            if !known {
+                debug!("Skipping unknown remote: {remote}");
+            }
+            known
+        });
         info!("Remotes: {:?}", branch_remotes);
 
-        assert!(!branch_remotes.is_empty());
-        assert!(remotes.len() >= branch_remotes.len());
         if branch_remotes.is_empty() {
             warn!("Ensure you only run command on a branch with a remote.");
-            bail!("Failed to find remote for current branch.")
+            bail!(
+                help = "Run `git push -u <remote> <branch>` once, or use '--remote'.",
+                "Failed to find remote for branch {name}. Available remotes: {:?}",
+                remotes
+            )
         }
         Ok(branch_remotes.into_iter().collect())
     }
@@ -324,40 +878,74 @@ impl Git<PathBuf> {
             },
             Err(e) => Err(e.wrap_err("Failed to run 'git branch --show-current'"))?,
         };
-        Ok(Branch::Named {
-            local: current_branch,
-        })
+        Ok(Self::parse_current_branch(&current_branch))
+    }
+
+    /// Returns the current branch, failing with [`GitError::DetachedHead`] when HEAD is detached.
+    pub fn attached_branch(&self) -> Result<Branch> {
+        match self.current_branch()? {
+            Branch::Detached => Err(GitError::DetachedHead)?,
+            branch => Ok(branch),
+        }
+    }
+
+    /// Parses the output of `git branch --show-current`, which is empty for a detached HEAD.
+    fn parse_current_branch(stdout: &str) -> Branch {
+        match stdout.trim() {
+            "" => Branch::Detached,
+            current_branch => Branch::from(current_branch.to_string()),
+        }
+    }
+
+    /// Returns `true` if the local branch exists.
+    ///
+    /// Equivilent to: `git rev-parse --verify --quiet refs/heads/<branch>`
+    pub fn branch_exists(&self, branch: &str) -> Result<bool> {
+        let mut git = self.command(true);
+        git.args([
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("refs/heads/{branch}"),
+        ]);
+        match Process::Output.run(git)? {
+            ProcessOutput::Output(output) => Ok(output.status.success()),
+            _ => unreachable!(),
+        }
     }
 
-    #[cfg(feature = "unstable")]
-    #[allow(unreachable_code, unused_variables)]
-    #[instrument(skip_all, fields(from, to, suppress))]
-    pub fn checkout(&self, branch: &Branch, suppress: Suppress) -> Result<Branch> {
+    /// Switches to `branch`, stashing dirty files first if `stash` is [`Stash::Stash`].
+    ///
+    /// Equivilent to: `git checkout <branch>`
+    ///
+    /// Returns the previous branch and the [`Stash`] state needed to revert the stash.
+    #[instrument(skip_all, fields(from, to))]
+    pub fn checkout(
+        &self,
+        branch: &Branch,
+        suppress: Suppress,
+        stash: Stash,
+    ) -> Result<(Branch, Stash)> {
         let current_branch = self.current_branch()?;
 
         let span = current_span!();
         span.record("from", current_branch.as_ref());
         span.record("to", branch.as_ref());
 
-        tracing::debug!("Switch to {:?}", current_branch);
-
-        // Check if need to stash.
-        // #46
-        // let mut revert_stash = Stash::Dont;
-        // if stash_state.is_stash() {
-        //     revert_stash = self.stash(cli_args.suppress, stash_state)?;
-        // }
-
-        // Changing branch
-        let mut cmd = self.command(suppress.includes_git());
-
-        if let Branch::Named { local } = &branch {
-            cmd.args(["checkout", local.as_ref()]);
-        } else {
+        let Branch::Named { local, .. } = &branch else {
             tracing::warn!("No reason to change to current branch");
             bail!("Can't change branch to current branch.")
         };
 
+        let revert_stash = match stash {
+            Stash::Stash => self.stash(suppress, Stash::Stash)?,
+            _ => Stash::Dont,
+        };
+
+        tracing::debug!("Switching from {} to {}", current_branch, branch);
+        let mut cmd = self.command(suppress.includes_git());
+        cmd.args(["checkout", local.as_ref()]);
+
         let output = match Process::Output
             .run(cmd)
             .context(format!("Failed to run: git checkout {}", &branch))?
@@ -374,52 +962,91 @@ impl Git<PathBuf> {
             );
         }
 
-        Ok(current_branch)
+        Ok((current_branch, revert_stash))
     }
 
+    /// Stashes the changes, including untracked files, for [`Stash::Stash`] and restores them
+    /// for [`Stash::Unstash`].
+    ///
+    /// Only the stash entry made by this process, see [`stash_message`], is applied and
+    /// dropped so any other stashes are left alone.
+    ///
+    /// Equivilent to: `git stash push --include-untracked` to stash and
+    /// `git stash apply --index` then `git stash drop` to restore, so staged changes are
+    /// restored staged.
+    ///
+    /// Returns the [`Stash`] state that reverts the action, [`Stash::Dont`] if nothing was
+    /// stashed as the working tree was clean or there is no stash from cargo-uv to restore.
     pub fn stash(&self, suppress: Suppress, state: Stash) -> Result<Stash> {
-        // TODO: use `git stash {create, store, apply, drop}`
-        // TODO: Ensure no dirty files after stash.
-        let files = self.dirty_files()?;
-        let mut ret_stash: Stash = state;
-
-        let mut git = self.command(suppress.includes_git());
-        git.arg("stash");
-
+        let message = stash_message();
         match state {
             Stash::Stash => {
-                git.arg("pop");
-                ret_stash = Stash::Unstash
+                if self.dirty_files()?.is_empty() {
+                    debug!("No dirty files to stash");
+                    return Ok(Stash::Dont);
+                }
+                self.output(&[
+                    "stash",
+                    "push",
+                    "--include-untracked",
+                    "--quiet",
+                    "--message",
+                    &message,
+                ])?;
+                let dirty = self.dirty_files()?;
+                if !dirty.is_empty() {
+                    bail!(
+                        help = format!("The changes are saved in the stash '{message}'."),
+                        "Files are still dirty after stashing: {}",
+                        dirty
+                            .iter()
+                            .map(|file| file.to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                }
+                Ok(Stash::Unstash)
             }
             Stash::Unstash => {
-                if !files.is_empty() {
-                    git.arg("push");
-                    ret_stash = Stash::Stash
+                let Some(entry) = self.find_stash(&message)? else {
+                    debug!("No stash made by cargo-uv to restore");
+                    return Ok(Stash::Dont);
+                };
+                let applied = self.output(&["stash", "apply", "--index", &entry])?;
+                if !suppress.includes_git() {
+                    print!("{applied}");
                 }
+                self.output(&["stash", "drop", "--quiet", &entry])?;
+                Ok(Stash::Stash)
             }
-            Stash::Dont => return Ok(state),
-        };
-        let command = Process::display_command(&git);
-        let run = Process::Output.run(git)?;
+            Stash::Dont => Ok(state),
+        }
+    }
 
-        let output = run.as_output().unwrap();
-        if !output.status.success() {
-            miette::bail!(
-                help = format!("Failed to run '{}'", command),
-                "{}",
-                output.stderr()
-            );
-        };
-        Ok(ret_stash)
+    /// Returns the `stash@{n}` of the entry with the `message`.
+    fn find_stash(&self, message: &str) -> Result<Option<String>> {
+        let list = self.output(&["stash", "list", "--format=%gd %gs"])?;
+        Ok(list.lines().find_map(|line| {
+            let (entry, subject) = line.split_once(' ')?;
+            // `git stash push` prefixes the message with `On <branch>: `.
+            let (_, stash_message) = subject.rsplit_once(": ")?;
+            (stash_message == message).then(|| entry.to_string())
+        }))
     }
 }
 
+/// Message of the stash made by [`Git::stash`], unique to the process.
+fn stash_message() -> String {
+    format!("{STASH_MESSAGE}-{}", std::process::id())
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Stash {
-    /// Run git stash push
+    /// Stash the changes
     #[default]
     Stash,
-    /// Run git stash pop
+    /// Restore the stashed changes
     Unstash,
     /// Don't run
     Dont,
@@ -449,3 +1076,110 @@ impl Stash {
         matches!(self, Self::Unstash)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transient_push_failures() {
+        assert!(Git::is_transient_push_failure(
+            "fatal: unable to access 'https://github.com/a/b.git/': Could not resolve host: github.com"
+        ));
+        assert!(Git::is_transient_push_failure(
+            "fatal: the remote end hung up unexpectedly"
+        ));
+        assert!(!Git::is_transient_push_failure(
+            "remote: Permission denied to user.\nfatal: unable to access: The remote end hung up unexpectedly"
+        ));
+        assert!(!Git::is_transient_push_failure(
+            "fatal: Authentication failed for 'https://github.com/a/b.git/'"
+        ));
+        assert!(!Git::is_transient_push_failure(""));
+    }
+
+    #[test]
+    fn push_command_uses_the_tag_refspec() {
+        let git = GitBuilder::new()
+            .root_directory(PathBuf::from("repo"))
+            .build();
+        let args = |refspec| {
+            let cmd = git
+                .push_command(Some(refspec), Suppress::ALL, false, "mirror", false)
+                .unwrap();
+            cmd.get_args()
+                .map(|arg| arg.to_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            args("refs/tags/v1.2.3:refs/tags/v1.2.3"),
+            [
+                "-C",
+                "repo",
+                "push",
+                "mirror",
+                "refs/tags/v1.2.3:refs/tags/v1.2.3",
+                "--porcelain"
+            ]
+        );
+        assert_eq!(args("tags/v1.2.3")[4], "tags/v1.2.3");
+    }
+
+    #[test]
+    fn push_up_to_date() {
+        let up_to_date = "To origin\n=\trefs/tags/1.2.3:refs/tags/1.2.3\t[up to date]\nDone\n";
+        assert!(Git::is_push_up_to_date(up_to_date, ""));
+        assert!(Git::is_push_up_to_date("", "Everything up-to-date\n"));
+        let rejected =
+            "To origin\n!\trefs/tags/1.2.3:refs/tags/1.2.3\t[rejected] (already exists)\nDone\n";
+        assert!(!Git::is_push_up_to_date(rejected, ""));
+        assert!(!Git::is_push_up_to_date("", "fatal: Authentication failed"));
+    }
+
+    #[test]
+    fn render_message_placeholders() {
+        let render = |template| Git::render_message(template, Some("cargo-uv"), "0.2.0", "0.1.0");
+        assert_eq!(render("{version}").unwrap(), "0.2.0");
+        assert_eq!(render("{name}").unwrap(), "cargo-uv");
+        assert_eq!(render("{previous}").unwrap(), "0.1.0");
+        assert_eq!(
+            render("chore(release): {name} {version} (was {previous})").unwrap(),
+            "chore(release): cargo-uv 0.2.0 (was 0.1.0)"
+        );
+    }
+
+    #[test]
+    fn render_message_leaves_unknown_placeholders() {
+        assert_eq!(
+            Git::render_message("{version} {date}", Some("a"), "0.2.0", "0.1.0").unwrap(),
+            "0.2.0 {date}"
+        );
+        assert!(Git::render_message("{name} {version}", None, "0.2.0", "0.1.0").is_err());
+        assert_eq!(
+            Git::render_message("{version}", None, "0.2.0", "0.1.0").unwrap(),
+            "0.2.0"
+        );
+    }
+
+    #[test]
+    fn parse_current_branch_named() {
+        assert_eq!(
+            Git::parse_current_branch("main\n"),
+            Branch::Named {
+                local: "main".to_string(),
+                remote: None,
+            }
+        );
+        // Local branches may contain a '/'.
+        assert_eq!(
+            Git::parse_current_branch("feature/x\n").as_ref(),
+            "feature/x"
+        );
+    }
+
+    #[test]
+    fn parse_current_branch_detached() {
+        assert_eq!(Git::parse_current_branch(""), Branch::Detached);
+        assert_eq!(Git::parse_current_branch("\n"), Branch::Detached);
+    }
+}
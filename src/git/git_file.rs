@@ -1,37 +1,145 @@
-use std::{path::PathBuf, str::FromStr, vec::IntoIter};
+use std::{
+    path::{Path, PathBuf},
+    slice::Iter,
+    vec::IntoIter,
+};
 
 use tracing::instrument;
 
-#[derive(Debug, Clone)]
+use crate::packages::glob_matches;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct GitFile {
+    /// The two character `XY` status from `git status --short`.
     pub mode: String,
     pub path: PathBuf,
+    /// The original path if the file was renamed or copied.
+    pub orig_path: Option<PathBuf>,
 }
 
 impl std::fmt::Display for GitFile {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.path.display())
+        match &self.orig_path {
+            Some(orig_path) => write!(f, "{} -> {}", orig_path.display(), self.path.display()),
+            None => write!(f, "{}", self.path.display()),
+        }
     }
 }
 
 impl GitFile {
+    /// Parses a line of `git status --short`, i.e. `XY PATH` or `XY ORIG_PATH -> PATH`.
+    ///
+    /// Paths quoted by git are unquoted.
     #[instrument]
     pub fn parse(line: impl ToString + std::fmt::Debug) -> Option<GitFile> {
         let line = line.to_string();
-        let (mode, path_str) = line.trim().split_once(" ")?;
-        let path = PathBuf::from_str(path_str).ok()?;
-        if mode.trim().is_empty() {
-            None
+        let line = line.trim_end();
+        let mode = line.get(0..2)?;
+        if mode.trim().is_empty() || line.get(2..3)? != " " {
+            return None;
+        }
+        let rest = &line[3..];
+
+        let is_rename = mode.contains(['R', 'C']);
+        let (first, rest) = if is_rename {
+            split_path(rest, Some(" -> "))?
+        } else {
+            split_path(rest, None)?
+        };
+        let (path, orig_path) = if is_rename {
+            let rest = rest.strip_prefix(" -> ")?;
+            let (second, _) = split_path(rest, None)?;
+            (second, Some(PathBuf::from(first)))
         } else {
-            Some(GitFile {
-                mode: mode.trim().to_string(),
-                path,
-            })
+            (first, None)
+        };
+
+        Some(GitFile {
+            mode: mode.to_string(),
+            path: PathBuf::from(path),
+            orig_path,
+        })
+    }
+
+    /// Status of the index, the `X` in `XY`.
+    pub fn index_status(&self) -> char {
+        self.mode.chars().next().unwrap_or(' ')
+    }
+
+    /// Status of the work tree, the `Y` in `XY`.
+    pub fn worktree_status(&self) -> char {
+        self.mode.chars().nth(1).unwrap_or(' ')
+    }
+
+    /// Returns `true` if the file has changes in the index, which `git commit` would include.
+    pub fn is_staged(&self) -> bool {
+        !matches!(self.index_status(), ' ' | '?' | '!')
+    }
+
+    /// Returns `true` if the file has changes in the work tree that aren't staged, including
+    /// untracked files.
+    pub fn is_unstaged(&self) -> bool {
+        self.worktree_status() != ' '
+    }
+}
+
+/// Splits the next path from `s`, unquoting it if quoted.
+///
+/// Unquoted paths end at `separator` or the end of `s`.
+fn split_path<'a>(s: &'a str, separator: Option<&str>) -> Option<(String, &'a str)> {
+    if let Some(quoted) = s.strip_prefix('"') {
+        return unquote(quoted);
+    }
+    match separator.and_then(|sep| s.find(sep)) {
+        Some(idx) => Some((s[..idx].to_string(), &s[idx..])),
+        None => Some((s.to_string(), "")),
+    }
+}
+
+/// Unquotes a C-style quoted path, `s` starting after the opening quote.
+///
+/// Returns the path and the remainder after the closing quote.
+fn unquote(s: &str) -> Option<(String, &str)> {
+    let mut bytes = Vec::new();
+    let mut chars = s.char_indices();
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '"' => {
+                let path = String::from_utf8_lossy(&bytes).into_owned();
+                return Some((path, &s[idx + 1..]));
+            }
+            '\\' => {
+                let (_, escaped) = chars.next()?;
+                let byte = match escaped {
+                    'a' => 0x07,
+                    'b' => 0x08,
+                    'f' => 0x0c,
+                    'n' => b'\n',
+                    'r' => b'\r',
+                    't' => b'\t',
+                    'v' => 0x0b,
+                    '0'..='7' => {
+                        let mut octal = escaped.to_digit(8)?;
+                        for _ in 0..2 {
+                            let (_, digit) = chars.next()?;
+                            octal = octal * 8 + digit.to_digit(8)?;
+                        }
+                        u8::try_from(octal).ok()?
+                    }
+                    c => u8::try_from(c).ok()?,
+                };
+                bytes.push(byte);
+            }
+            c => {
+                let mut buf = [0; 4];
+                bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            }
         }
     }
+    None
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct GitFiles(Vec<GitFile>);
 
 impl GitFiles {
@@ -39,6 +147,35 @@ impl GitFiles {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Drops the files whose path matches one of the `globs`, see `--dirty-allow`.
+    pub fn without_matching(&self, globs: &[String]) -> GitFiles {
+        GitFiles(
+            self.0
+                .iter()
+                .filter(|f| {
+                    let path = f.path.to_string_lossy();
+                    !globs.iter().any(|glob| glob_matches(&path, glob))
+                })
+                .cloned()
+                .collect(),
+        )
+    }
+
+    /// The paths of the files, relative to the git root.
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.0.iter().map(|f| f.path.as_path())
+    }
+
+    /// The files with staged changes.
+    pub fn staged(&self) -> GitFiles {
+        GitFiles(self.0.iter().filter(|f| f.is_staged()).cloned().collect())
+    }
+
+    /// The files with changes in the work tree that aren't staged.
+    pub fn unstaged(&self) -> GitFiles {
+        GitFiles(self.0.iter().filter(|f| f.is_unstaged()).cloned().collect())
+    }
 }
 
 impl std::ops::Deref for GitFiles {
@@ -105,3 +242,125 @@ impl IntoIterator for GitFiles {
         self.0.into_iter()
     }
 }
+
+impl<'a> IntoIterator for &'a GitFiles {
+    type Item = &'a GitFile;
+
+    type IntoIter = Iter<'a, GitFile>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_modified() {
+        let file = GitFile::parse(" M src/lib.rs").unwrap();
+        assert_eq!(file.mode, " M");
+        assert_eq!(file.index_status(), ' ');
+        assert_eq!(file.worktree_status(), 'M');
+        assert_eq!(file.path, PathBuf::from("src/lib.rs"));
+        assert_eq!(file.orig_path, None);
+    }
+
+    #[test]
+    fn without_matching_drops_allowed_paths() {
+        let files = GitFiles::parse(" M docs/generated.md\n M src/lib.rs\n".to_string()).unwrap();
+        let remaining = files.without_matching(&["docs/*.md".to_string()]);
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].path, PathBuf::from("src/lib.rs"));
+        assert_eq!(files.without_matching(&[]), files);
+    }
+
+    #[test]
+    fn iterate_files_and_paths() {
+        let files = GitFiles::parse(" M src/lib.rs\n?? notes.txt\n".to_string()).unwrap();
+        let expected = [Path::new("src/lib.rs"), Path::new("notes.txt")];
+        assert_eq!(files.paths().collect::<Vec<_>>(), expected);
+        let borrowed: Vec<&Path> = (&files).into_iter().map(|f| f.path.as_path()).collect();
+        assert_eq!(borrowed, expected);
+        let owned: Vec<PathBuf> = files.into_iter().map(|f| f.path).collect();
+        assert_eq!(owned, expected);
+    }
+
+    #[test]
+    fn staged_and_unstaged() {
+        let files = GitFiles::parse("M  index.rs\n M tree.rs\nMM both.rs\n?? new.rs\n".to_string())
+            .unwrap();
+        let paths =
+            |files: GitFiles| -> Vec<PathBuf> { files.iter().map(|f| f.path.clone()).collect() };
+        assert_eq!(
+            paths(files.staged()),
+            vec![PathBuf::from("index.rs"), PathBuf::from("both.rs")]
+        );
+        assert_eq!(
+            paths(files.unstaged()),
+            vec![
+                PathBuf::from("tree.rs"),
+                PathBuf::from("both.rs"),
+                PathBuf::from("new.rs")
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_untracked() {
+        let file = GitFile::parse("?? new file.rs").unwrap();
+        assert_eq!(file.mode, "??");
+        assert_eq!(file.path, PathBuf::from("new file.rs"));
+    }
+
+    #[test]
+    fn parse_rename() {
+        let file = GitFile::parse("R  old.rs -> new.rs").unwrap();
+        assert_eq!(file.index_status(), 'R');
+        assert_eq!(file.path, PathBuf::from("new.rs"));
+        assert_eq!(file.orig_path, Some(PathBuf::from("old.rs")));
+        assert_eq!(file.to_string(), "old.rs -> new.rs");
+    }
+
+    #[test]
+    fn parse_quoted_space() {
+        let file = GitFile::parse(r#"?? "with space.rs""#).unwrap();
+        assert_eq!(file.path, PathBuf::from("with space.rs"));
+    }
+
+    #[test]
+    fn parse_quoted_rename() {
+        let file = GitFile::parse(r#"R  "old name.rs" -> "new -> name.rs""#).unwrap();
+        assert_eq!(file.path, PathBuf::from("new -> name.rs"));
+        assert_eq!(file.orig_path, Some(PathBuf::from("old name.rs")));
+    }
+
+    #[test]
+    fn parse_quoted_escapes() {
+        let file = GitFile::parse(r#" M "tab\there\"quote\"\303\251.rs""#).unwrap();
+        assert_eq!(file.path, PathBuf::from("tab\there\"quote\"é.rs"));
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert!(GitFile::parse("").is_none());
+        assert!(GitFile::parse("   path").is_none());
+        assert!(GitFile::parse(r#" M "unterminated"#).is_none());
+    }
+
+    #[test]
+    fn parse_files() {
+        let files =
+            GitFiles::parse(" M a.rs\nR  b.rs -> c.rs\n?? \"d e.rs\"\n".to_string()).unwrap();
+        let paths: Vec<_> = files.iter().map(|f| f.path.clone()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("a.rs"),
+                PathBuf::from("c.rs"),
+                PathBuf::from("d e.rs")
+            ]
+        );
+    }
+}
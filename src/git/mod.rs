@@ -1,12 +1,16 @@
 pub(crate) mod branch;
+mod error;
 #[allow(clippy::module_inception)]
 pub(crate) mod git;
 pub(crate) mod git_file;
+pub(crate) mod push;
 
 pub use branch::Branch;
+pub use error::GitError;
 pub use git::Git;
 pub use git::GitBuilder;
 pub use git::NoRootDirSet;
 pub use git::Stash;
 pub use git_file::GitFile;
 pub use git_file::GitFiles;
+pub use push::{PushFlag, PushedRef};
@@ -0,0 +1,146 @@
+use std::fmt::Display;
+
+/// The status flag of a ref in `git push --porcelain`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PushFlag {
+    /// ` ` Successfully pushed fast-forward.
+    FastForward,
+    /// `+` Successful forced update.
+    Forced,
+    /// `-` Successfully deleted ref.
+    Deleted,
+    /// `*` Successfully pushed new ref.
+    New,
+    /// `!` Ref was rejected or failed to push.
+    Rejected,
+    /// `=` Ref was up to date and did not need pushing.
+    UpToDate,
+}
+
+impl TryFrom<char> for PushFlag {
+    type Error = char;
+
+    fn try_from(flag: char) -> Result<Self, Self::Error> {
+        Ok(match flag {
+            ' ' => PushFlag::FastForward,
+            '+' => PushFlag::Forced,
+            '-' => PushFlag::Deleted,
+            '*' => PushFlag::New,
+            '!' => PushFlag::Rejected,
+            '=' => PushFlag::UpToDate,
+            c => return Err(c),
+        })
+    }
+}
+
+/// A ref line from `git push --porcelain`, i.e. `<flag> \t <from>:<to> \t <summary> (<reason>)`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PushedRef {
+    pub flag: PushFlag,
+    pub from: String,
+    pub to: String,
+    pub summary: String,
+    pub reason: Option<String>,
+}
+
+impl Display for PushedRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.to, self.summary)?;
+        if let Some(reason) = &self.reason {
+            write!(f, " ({reason})")?;
+        }
+        Ok(())
+    }
+}
+
+impl PushedRef {
+    /// Parses a ref line, returning [`None`] for other lines such as `To <url>` and `Done`.
+    pub fn parse(line: &str) -> Option<PushedRef> {
+        let mut chars = line.chars();
+        let flag = PushFlag::try_from(chars.next()?).ok()?;
+        let mut fields = chars.as_str().strip_prefix('\t')?.splitn(2, '\t');
+        let (from, to) = fields.next()?.split_once(':')?;
+        let summary = fields.next()?.trim_end();
+        let (summary, reason) = match summary.split_once(" (") {
+            Some((summary, reason)) => (summary, reason.strip_suffix(')')),
+            None => (summary, None),
+        };
+        Some(PushedRef {
+            flag,
+            from: from.to_string(),
+            to: to.to_string(),
+            summary: summary.to_string(),
+            reason: reason.map(String::from),
+        })
+    }
+
+    /// Parses all the ref lines of `git push --porcelain`.
+    pub fn parse_porcelain(stdout: &str) -> Vec<PushedRef> {
+        stdout.lines().filter_map(PushedRef::parse).collect()
+    }
+
+    /// Only the rejected refs of `git push --porcelain`.
+    pub fn rejected(stdout: &str) -> Vec<PushedRef> {
+        PushedRef::parse_porcelain(stdout)
+            .into_iter()
+            .filter(PushedRef::is_rejected)
+            .collect()
+    }
+
+    /// Returns `true` if the ref is [`PushFlag::Rejected`].
+    #[must_use]
+    pub fn is_rejected(&self) -> bool {
+        self.flag == PushFlag::Rejected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PORCELAIN: &str = "To github.com:Ozy-Viking/cargo_update_version.git
+*\trefs/tags/1.2.3:refs/tags/1.2.3\t[new tag]
+!\trefs/heads/main:refs/heads/main\t[rejected] (non-fast-forward)
+=\trefs/heads/dev:refs/heads/dev\t[up to date]
+ \trefs/heads/feat:refs/heads/feat\t1a2b3c4..5d6e7f8
+Done
+";
+
+    #[test]
+    fn parse_porcelain_refs() {
+        let refs = PushedRef::parse_porcelain(PORCELAIN);
+        assert_eq!(refs.len(), 4);
+        assert_eq!(refs[0].flag, PushFlag::New);
+        assert_eq!(refs[0].to, "refs/tags/1.2.3");
+        assert_eq!(refs[0].summary, "[new tag]");
+        assert_eq!(refs[2].flag, PushFlag::UpToDate);
+        assert_eq!(refs[3].flag, PushFlag::FastForward);
+        assert_eq!(refs[3].summary, "1a2b3c4..5d6e7f8");
+    }
+
+    #[test]
+    fn parse_porcelain_rejected() {
+        let rejected = PushedRef::rejected(PORCELAIN);
+        assert_eq!(
+            rejected,
+            vec![PushedRef {
+                flag: PushFlag::Rejected,
+                from: "refs/heads/main".into(),
+                to: "refs/heads/main".into(),
+                summary: "[rejected]".into(),
+                reason: Some("non-fast-forward".into()),
+            }]
+        );
+        assert_eq!(
+            rejected[0].to_string(),
+            "refs/heads/main: [rejected] (non-fast-forward)"
+        );
+    }
+
+    #[test]
+    fn parse_ignores_other_lines() {
+        assert!(PushedRef::parse("To origin").is_none());
+        assert!(PushedRef::parse("Done").is_none());
+        assert!(PushedRef::parse("").is_none());
+    }
+}
@@ -10,22 +10,27 @@ pub(crate) mod process;
 pub(crate) mod tasks;
 pub mod version;
 
-pub use cargo::Cargo;
-pub use cli::{Action, Cli};
-pub use git::{Branch, Git, GitBuilder, GitFile, GitFiles, NoRootDirSet, Stash};
+pub use cargo::{Cargo, RegistryToken};
+pub use cli::{
+    Action, Cli, ErrorFormat, LogFormat, MetadataMode, OutputFormat, PrintFormat, Suppress,
+};
+pub use git::{
+    Branch, Git, GitBuilder, GitError, GitFile, GitFiles, NoRootDirSet, PushFlag, PushedRef, Stash,
+};
 pub use manifest::error::{
     CargoFileError, CargoFileErrorKind, ItemType, VersionLocationErrorKind, VersionlocationError,
 };
-pub use manifest::generate_packages;
+pub use manifest::{generate_packages, normalize_path};
 pub use manifest::toml_file::{CargoFile, ReadToml, UnreadToml};
 pub use manifest::version_location::{VersionLocation, VersionType};
 pub use miette::Result;
-pub use packages::{Package, PackageError, PackageName, Packages};
+pub use packages::{Package, PackageError, PackageName, Packages, VersionMismatch};
 pub use process::{OutputExt, Process, ProcessOutput};
-pub use tasks::{DisplayTasks, Task, TaskError, Tasks};
+pub use tasks::{DisplayTasks, ReleaseOutput, RunReport, Stage, Task, TaskError, Tasks};
 pub use version::{Bumpable, Incrementable, Setable};
 
-use miette::{IntoDiagnostic, bail};
+use miette::IntoDiagnostic;
+use rusty_viking::MietteDefaultConfig;
 use tracing::{Level, info};
 use tracing_subscriber::util::SubscriberInitExt;
 
@@ -34,13 +39,46 @@ pub static FOOTER: &str = "If the bug continues, raise an issue on github: https
 
 pub type SplitVec<T> = (Vec<T>, Vec<T>);
 
+/// Installs the [miette] report handler for the [ErrorFormat] and the panic hook.
+///
+/// Run before the arguments are parsed, using [ErrorFormat::from_args], as the handler can only
+/// be set once.
+pub fn setup_error_hook(error_format: ErrorFormat) -> miette::Result<()> {
+    match error_format {
+        ErrorFormat::Human => MietteDefaultConfig::init_set_panic_hook(Some(FOOTER.into()))?,
+        ErrorFormat::Json => {
+            miette::set_hook(Box::new(|_| Box::new(miette::JSONReportHandler::new())))?;
+            miette::set_panic_hook();
+        }
+    }
+    Ok(())
+}
+
+/// Generates and runs the tasks for the parsed [Cli], including the cleanup tasks.
+///
+/// Prints what the binary prints, e.g. the JSON summary for [OutputFormat::Json], but never
+/// exits the process. Nothing is run with `--plan`, so the report is empty.
+pub fn run(mut cli: Cli) -> miette::Result<RunReport> {
+    let packages = Packages::from(cli.get_metadata()?);
+    let mut tasks = Tasks::generate_tasks(&cli, packages)?;
+    if cli.plan() {
+        return Ok(RunReport::default());
+    }
+
+    tasks = tasks.run_all(&cli)?.join_all(&cli)?;
+    info!("Completed run, starting cleanup");
+    tasks = tasks.run_cleanup_tasks(&cli)?;
+
+    if cli.output_format().is_json() {
+        println!("{}", tasks.release_output()?.to_json()?);
+    }
+    Ok(tasks.report().clone())
+}
+
 pub fn setup_tracing(args: &Cli) -> miette::Result<()> {
-    let app_level = match args.tracing_level() {
-        Some(l) => l,
-        None => bail!(
-            help = "Raise issue in github please or try a different verbosity level.",
-            "Tracing level not set somehow."
-        ),
+    // Silenced with `-q`, so no subscriber is needed.
+    let Some(app_level) = args.tracing_level() else {
+        return Ok(());
     };
 
     let target = tracing_subscriber::filter::Targets::new()
@@ -50,15 +88,16 @@ pub fn setup_tracing(args: &Cli) -> miette::Result<()> {
     let env_filter = tracing_subscriber::EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(target.to_string()));
 
-    #[allow(unused_mut)]
-    let mut builder = tracing_subscriber::fmt()
+    let builder = tracing_subscriber::fmt()
         .without_time()
-        .with_env_filter(env_filter);
-    #[cfg(debug_assertions)]
-    {
-        builder = builder.with_line_number(true).with_file(true)
+        .with_env_filter(env_filter)
+        .with_line_number(cfg!(debug_assertions))
+        .with_file(cfg!(debug_assertions));
+    match args.log_format() {
+        LogFormat::Human => builder.finish().try_init(),
+        LogFormat::Json => builder.json().finish().try_init(),
     }
-    builder.finish().try_init().into_diagnostic()?;
+    .into_diagnostic()?;
     info!("Tracing level: {}", app_level);
     Ok(())
 }
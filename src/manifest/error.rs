@@ -59,8 +59,14 @@ impl CargoFileErrorKind {
 
 #[derive(Debug, thiserror::Error, miette::Diagnostic)]
 pub enum VersionLocationErrorKind {
-    #[error("Version is set by workspace.")]
-    SetByWorkspace,
+    #[error(
+        "Version is set by workspace.package.version in {}",
+        .workspace_manifest.as_ref().map_or("the workspace root manifest".into(), |path| path.display().to_string())
+    )]
+    SetByWorkspace {
+        /// Manifest with the `[workspace]`, [`None`] if it couldn't be found.
+        workspace_manifest: Option<PathBuf>,
+    },
     #[error("Version not located in {0}")]
     NotFound(VersionLocation),
     #[error("No package defined in toml file.")]
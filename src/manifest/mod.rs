@@ -2,6 +2,8 @@ pub(crate) mod error;
 pub(crate) mod toml_file;
 pub(crate) mod version_location;
 
+use std::path::{Path, PathBuf};
+
 use miette::Result;
 use tracing::{info, instrument, warn};
 
@@ -9,8 +11,11 @@ use crate::{Cli, Packages, display_path, error::ManifestNotFoundError};
 
 #[instrument(skip(args), fields(cargo_file))]
 pub fn generate_packages(args: &mut Cli) -> Result<Packages> {
+    if let Some(metadata) = args.metadata() {
+        return Ok(Packages::from(metadata));
+    }
     let mut cli_path = args.manifest.manifest_path.as_ref();
-    let mut command = args.manifest.metadata();
+    let mut command = args.manifest.metadata_for(args.metadata_mode());
     if let Some(manifest_path) = cli_path.as_mut() {
         let mut manifest_path = manifest_path.clone();
         if manifest_path.is_dir() {
@@ -32,14 +37,9 @@ pub fn generate_packages(args: &mut Cli) -> Result<Packages> {
             };
             msg.retain(|s| s != '\n');
             let source_code = cli_path.as_ref().map(|&s| {
-                s.clone()
-                    .canonicalize()
-                    .unwrap_or(s.clone())
+                normalize_path(&s.canonicalize().unwrap_or(s.clone()))
                     .display()
                     .to_string()
-                    .strip_prefix("\\\\?\\") // Bug fix
-                    .unwrap_or_default()
-                    .to_string()
             });
             let source_len = source_code.clone().unwrap_or_default().len();
 
@@ -53,7 +53,7 @@ pub fn generate_packages(args: &mut Cli) -> Result<Packages> {
             unreachable!()
         }
     };
-    let packages = Packages::from(&metadata);
+    let packages = Packages::from(args.cache_metadata(metadata));
     let cargo_file = packages.root_manifest_path();
     tracing::Span::current().record("cargo_file", display_path!(cargo_file).to_string());
     if cargo_file.exists() {
@@ -72,3 +72,46 @@ pub fn generate_packages(args: &mut Cli) -> Result<Packages> {
 
     Ok(packages)
 }
+
+/// Strips the `\\?\` verbatim prefix [`Path::canonicalize`] adds on Windows, so the path
+/// displays as the user wrote it. Paths without the prefix are returned unchanged.
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let Some(display) = path.to_str() else {
+        return path.to_path_buf();
+    };
+    if let Some(unc) = display.strip_prefix(r"\\?\UNC\") {
+        PathBuf::from(format!(r"\\{unc}"))
+    } else if let Some(local) = display.strip_prefix(r"\\?\") {
+        PathBuf::from(local)
+    } else {
+        path.to_path_buf()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_path_strips_verbatim_prefix() {
+        assert_eq!(
+            normalize_path(Path::new(r"\\?\C:\project\Cargo.toml")),
+            PathBuf::from(r"C:\project\Cargo.toml")
+        );
+        assert_eq!(
+            normalize_path(Path::new(r"\\?\UNC\server\share\Cargo.toml")),
+            PathBuf::from(r"\\server\share\Cargo.toml")
+        );
+    }
+
+    #[test]
+    fn normalize_path_keeps_other_paths() {
+        for path in [
+            r"C:\project\Cargo.toml",
+            "/project/Cargo.toml",
+            "Cargo.toml",
+        ] {
+            assert_eq!(normalize_path(Path::new(path)), PathBuf::from(path));
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use std::{
+    io::Write,
     marker::PhantomData,
     panic::Location,
     path::{Path, PathBuf},
@@ -6,14 +7,20 @@ use std::{
 
 use miette::{IntoDiagnostic, bail};
 use semver::Version;
-use toml_edit::DocumentMut;
+use toml_edit::{DocumentMut, Item, TableLike, Value};
 use tracing::instrument;
 
 use crate::{
     VersionLocation,
-    manifest::error::{CargoFileError, CargoFileErrorKind, VersionlocationError},
+    manifest::{
+        error::{CargoFileError, CargoFileErrorKind, VersionlocationError},
+        version_location::set_value,
+    },
 };
 
+/// Dependency tables that can reference a workspace member.
+const DEPENDENCY_TABLES: [&str; 3] = ["dependencies", "dev-dependencies", "build-dependencies"];
+
 /// Indicator that the cargo file has been read.
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub struct ReadToml;
@@ -156,10 +163,12 @@ impl CargoFile<ReadToml> {
         let pack_err = VersionLocation::Package.set_version(self, &new_version);
         let ws_err = VersionLocation::WorkspacePackage.set_version(self, &new_version);
 
+        // Reported when the member has no workspace version, as it names the workspace root.
+        let mut set_by_workspace = None;
         if let Some(cargo_file_err) = pack_err.err() {
             use crate::manifest::error::VersionLocationErrorKind as VerLocErrKind;
             match &cargo_file_err.kind() {
-                VerLocErrKind::SetByWorkspace => (),
+                VerLocErrKind::SetByWorkspace { .. } => set_by_workspace = Some(cargo_file_err),
                 VerLocErrKind::PackageNotFound => (),
                 VerLocErrKind::NotFound(_) => (),
                 VerLocErrKind::WorkspaceNotFound => unreachable!("Setting package"),
@@ -178,14 +187,17 @@ impl CargoFile<ReadToml> {
         if let Some(ver_loc_error) = ws_err.err() {
             use crate::manifest::error::VersionLocationErrorKind as VerLocErrKind;
             match ver_loc_error.kind() {
-                VerLocErrKind::SetByWorkspace => unreachable!(),
+                VerLocErrKind::SetByWorkspace { .. } => unreachable!(),
                 VerLocErrKind::NotFound(_) => {
                     Err(CargoFileErrorKind::NoPackageOrWorkspaceVersion.to_error(cargo_path))
                 }
                 VerLocErrKind::PackageNotFound => unreachable!(),
-                VerLocErrKind::WorkspaceNotFound => {
-                    Err(CargoFileErrorKind::NoPackageOrWorkspaceVersion.to_error(cargo_path))
-                }
+                VerLocErrKind::WorkspaceNotFound => match set_by_workspace {
+                    Some(err) => Err(CargoFileErrorKind::LocationError(err).to_error(cargo_path)),
+                    None => {
+                        Err(CargoFileErrorKind::NoPackageOrWorkspaceVersion.to_error(cargo_path))
+                    }
+                },
                 VerLocErrKind::ItemInvalid(_) | VerLocErrKind::SemverError(_) => {
                     Err(CargoFileErrorKind::LocationError(ver_loc_error).to_error(cargo_path))
                 }
@@ -195,10 +207,209 @@ impl CargoFile<ReadToml> {
         }
     }
 
+    /// Every version in the manifest keyed by its dotted path.
+    ///
+    /// Includes `package.version`, `workspace.package.version` and the version requirement of
+    /// each dependency that sets one, such as `dependencies.a.version`. Inherited versions are
+    /// skipped.
+    #[instrument(skip_all)]
+    pub fn get_all_versions(&self) -> Vec<(String, Version)> {
+        let mut versions = Vec::new();
+        if let Some(version) = self.get_package_version() {
+            versions.push((VersionLocation::Package.to_string(), version));
+        }
+        if let Some(version) = self.get_workspace_version() {
+            versions.push((VersionLocation::WorkspacePackage.to_string(), version));
+        }
+        let Some(document) = self.contents() else {
+            return versions;
+        };
+
+        for table_name in DEPENDENCY_TABLES {
+            let Some(table) = document.get(table_name).and_then(Item::as_table_like) else {
+                continue;
+            };
+            for (name, item) in table.iter() {
+                let (key, requirement) = match item {
+                    Item::Value(Value::String(requirement)) => {
+                        (format!("{table_name}.{name}"), requirement.value().as_str())
+                    }
+                    _ => match item.get("version").and_then(Item::as_str) {
+                        Some(requirement) => (format!("{table_name}.{name}.version"), requirement),
+                        None => continue,
+                    },
+                };
+                if let Some(version) = requirement_version(requirement) {
+                    versions.push((key, version));
+                }
+            }
+        }
+        versions
+    }
+
+    /// Packages overridden by `[patch]` or `[replace]`, as the section and package name.
+    ///
+    /// Cargo only uses these sections from the workspace root manifest.
+    #[instrument(skip_all)]
+    pub fn get_patched_packages(&self) -> Vec<(String, String)> {
+        let Some(document) = self.contents() else {
+            return Vec::new();
+        };
+        let mut patched = Vec::new();
+        if let Some(sources) = document.get("patch").and_then(Item::as_table_like) {
+            for (source, table) in sources.iter() {
+                let Some(table) = table.as_table_like() else {
+                    continue;
+                };
+                for (name, item) in table.iter() {
+                    let name = item.get("package").and_then(Item::as_str).unwrap_or(name);
+                    patched.push((format!("patch.{source}"), name.to_string()));
+                }
+            }
+        }
+        if let Some(table) = document.get("replace").and_then(Item::as_table_like) {
+            for (spec, _) in table.iter() {
+                // A package id spec such as `foo:0.1.0` or `<registry url>#foo:0.1.0`.
+                let spec = spec.rsplit_once('#').map_or(spec, |(_, spec)| spec);
+                let name = spec.split_once(':').map_or(spec, |(name, _)| name);
+                patched.push(("replace".to_string(), name.to_string()));
+            }
+        }
+        patched
+    }
+
+    /// Sets the version requirement of every dependency on `dependency` to `new_version`.
+    ///
+    /// Checks the dependency tables, including target specific tables and
+    /// `workspace.dependencies`. Dependencies without a version, such as path only or
+    /// `workspace = true`, are left alone. Returns `true` if a requirement changed.
+    #[instrument(skip(self))]
+    pub fn set_dependency_version(&mut self, dependency: &str, new_version: &Version) -> bool {
+        let Some(document) = self.contents_mut() else {
+            return false;
+        };
+        let mut changed = false;
+        let mut update = |table: &mut dyn TableLike| {
+            for (key, item) in table.iter_mut() {
+                let name = item
+                    .get("package")
+                    .and_then(Item::as_str)
+                    .unwrap_or(key.get());
+                if name == dependency {
+                    changed |= set_requirement(item, new_version);
+                }
+            }
+        };
+
+        for name in DEPENDENCY_TABLES {
+            if let Some(table) = document.get_mut(name).and_then(Item::as_table_like_mut) {
+                update(table);
+            }
+        }
+        if let Some(targets) = document.get_mut("target").and_then(Item::as_table_like_mut) {
+            for (_, target) in targets.iter_mut() {
+                let Some(target) = target.as_table_like_mut() else {
+                    continue;
+                };
+                for name in DEPENDENCY_TABLES {
+                    if let Some(table) = target.get_mut(name).and_then(Item::as_table_like_mut) {
+                        update(table);
+                    }
+                }
+            }
+        }
+        if let Some(table) = document
+            .get_mut("workspace")
+            .and_then(|workspace| workspace.get_mut("dependencies"))
+            .and_then(Item::as_table_like_mut)
+        {
+            update(table);
+        }
+        changed
+    }
+
     #[instrument(skip(self))]
     pub fn write_cargo_file(&mut self) -> miette::Result<()> {
         let contents = self.contents.as_ref().unwrap().to_string();
-        std::fs::write(&self.path, contents).into_diagnostic()?;
-        Ok(())
+        write_atomic(&self.path, contents.as_bytes())
     }
 }
+
+/// Writes to a sibling temp file then renames it over `path`, so a partial write is never seen.
+///
+/// The permissions of an existing file are kept. [`std::fs::rename`] replaces an existing file
+/// on Windows as well, though it fails if another process has the file open.
+pub(crate) fn write_atomic(path: &Path, contents: &[u8]) -> miette::Result<()> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| miette::miette!("Not a file: {}", path.display()))?;
+    let temp_path = path.with_file_name(format!(
+        ".{}.{}.tmp",
+        file_name.to_string_lossy(),
+        std::process::id()
+    ));
+
+    let write = || -> std::io::Result<()> {
+        let mut file = std::fs::File::create(&temp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        if let Ok(metadata) = std::fs::metadata(path) {
+            std::fs::set_permissions(&temp_path, metadata.permissions())?;
+        }
+        std::fs::rename(&temp_path, path)
+    };
+    write().map_err(|e| {
+        let _ = std::fs::remove_file(&temp_path);
+        miette::miette!("Failed to write {}: {e}", path.display())
+    })
+}
+
+/// Sets the `version` of a dependency entry, keeping the formatting around it.
+fn set_requirement(item: &mut Item, new_version: &Version) -> bool {
+    let value = match item {
+        Item::Value(Value::InlineTable(table)) => table.get_mut("version"),
+        Item::Table(table) => table.get_mut("version").and_then(Item::as_value_mut),
+        Item::Value(value) => Some(value),
+        _ => None,
+    };
+    let Some(value) = value else {
+        return false;
+    };
+    let Some(requirement) = value.as_str() else {
+        return false;
+    };
+    let new_requirement = dependency_requirement(requirement, new_version);
+    if new_requirement == requirement {
+        return false;
+    }
+    set_value(value, new_requirement);
+    true
+}
+
+/// Keeps the `=`, `^` or `~` operator of a single requirement.
+fn dependency_requirement(requirement: &str, new_version: &Version) -> String {
+    let requirement = requirement.trim();
+    let operator = match requirement.contains(',') {
+        true => "",
+        false => ["=", "^", "~"]
+            .into_iter()
+            .find(|operator| requirement.starts_with(operator))
+            .unwrap_or_default(),
+    };
+    format!("{operator}{new_version}")
+}
+
+/// The version of a single requirement, with any missing minor or patch as zero.
+fn requirement_version(requirement: &str) -> Option<Version> {
+    let requirement = semver::VersionReq::parse(requirement).ok()?;
+    let [comparator] = requirement.comparators.as_slice() else {
+        return None;
+    };
+    let mut version = Version::new(
+        comparator.major,
+        comparator.minor.unwrap_or_default(),
+        comparator.patch.unwrap_or_default(),
+    );
+    version.pre = comparator.pre.clone();
+    Some(version)
+}
@@ -1,4 +1,7 @@
-use std::fmt::Display;
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
 
 use semver::Version;
 use tracing::{info, instrument, trace};
@@ -15,6 +18,20 @@ pub enum VersionType {
     WorkspacePackage,
 }
 
+impl Display for VersionType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                VersionType::Package => "package",
+                VersionType::SetByWorkspace => "inherited",
+                VersionType::WorkspacePackage => "workspace",
+            }
+        )
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum VersionLocation {
     Package,
@@ -67,12 +84,17 @@ impl VersionLocation {
                     ErrKind::NotFound(*self),
                     Some("Package table located."),
                 ))? {
-                    toml_edit::Item::Value(value) => {
-                        Version::parse(value.as_str().unwrap()).map_err(|e| set_err(e.into(), None))
+                    toml_edit::Item::Value(value) if !value.is_inline_table() => {
+                        let version = value.as_str().ok_or(set_err(
+                            ErrKind::ItemInvalid(ItemType::Value),
+                            Some("Expected the version to be a string."),
+                        ))?;
+                        Version::parse(version).map_err(|e| set_err(e.into(), None))
                     }
-                    toml_edit::Item::Table(table) => {
+                    // Both `version.workspace = true` and `version = { workspace = true }`.
+                    table @ (toml_edit::Item::Table(_) | toml_edit::Item::Value(_)) => {
                         let workspace_table = table.get("workspace").ok_or(set_err(
-                            ErrKind::ItemInvalid(ItemType::Table),
+                            ErrKind::ItemInvalid(table.into()),
                             Some("expected version.workspace = <bool>"),
                         ))?;
                         let val = workspace_table.as_bool().ok_or(set_err(
@@ -81,7 +103,9 @@ impl VersionLocation {
                         ))?;
 
                         let msg = format!("in the manifest file: version.workspace = {val}");
-                        Err(ErrKind::SetByWorkspace.to_error(path, Some(msg)))
+                        let workspace_manifest = find_workspace_manifest(path);
+                        Err(ErrKind::SetByWorkspace { workspace_manifest }
+                            .to_error(path, Some(msg)))
                     }
 
                     item => Err(set_err(ErrKind::ItemInvalid(item.into()), None)),
@@ -119,8 +143,14 @@ impl VersionLocation {
                     ErrKind::NotFound(*self),
                     Some("Package table located."),
                 ))? {
-                    toml_edit::Item::Value(value) => Version::parse(value.as_str().unwrap())
-                        .map_err(|e| set_err(e.into(), Some("Workspace Version"))),
+                    toml_edit::Item::Value(value) => {
+                        let version = value.as_str().ok_or(set_err(
+                            ErrKind::ItemInvalid(ItemType::Value),
+                            Some("Expected the workspace version to be a string."),
+                        ))?;
+                        Version::parse(version)
+                            .map_err(|e| set_err(e.into(), Some("Workspace Version")))
+                    }
                     item => Err(set_err(ErrKind::ItemInvalid(item.into()), None)),
                 }
             }
@@ -171,10 +201,17 @@ impl VersionLocation {
                     ErrKind::NotFound(*self),
                     Some("Package table located."),
                 ))? {
-                    toml_edit::Item::Value(value) => {
-                        *value = version.to_string().into();
+                    toml_edit::Item::Value(value) if !value.is_inline_table() => {
+                        set_value(value, version.to_string());
                         Ok(())
                     }
+                    // Never replace an inherited version with a literal.
+                    toml_edit::Item::Table(_) | toml_edit::Item::Value(_) => Err(set_err(
+                        ErrKind::SetByWorkspace {
+                            workspace_manifest: find_workspace_manifest(&path),
+                        },
+                        Some("Inherited versions are set in workspace.package.version."),
+                    )),
                     item => Err(set_err(
                         ErrKind::ItemInvalid(item.into()),
                         Some("Invalid itemtype for setting package version."),
@@ -209,7 +246,7 @@ impl VersionLocation {
                     Some("Package table located."),
                 ))? {
                     toml_edit::Item::Value(value) => {
-                        *value = version.to_string().into();
+                        set_value(value, version.to_string());
                         Ok(())
                     }
                     item => Err(set_err(
@@ -225,3 +262,36 @@ impl VersionLocation {
         Ok(())
     }
 }
+
+/// Replaces the value, keeping the whitespace and comments around it.
+pub(crate) fn set_value(value: &mut toml_edit::Value, new_value: impl Into<toml_edit::Value>) {
+    let decor = value.decor().clone();
+    *value = new_value.into();
+    *value.decor_mut() = decor;
+}
+
+/// Finds the workspace root manifest of a member the same way as cargo.
+///
+/// Uses `package.workspace` if set, otherwise the closest manifest with a `[workspace]`
+/// starting from the member itself.
+pub(crate) fn find_workspace_manifest(manifest_path: &Path) -> Option<PathBuf> {
+    let is_workspace = |path: &Path| {
+        CargoFile::new(path)
+            .ok()
+            .and_then(|cargo_file| cargo_file.contents().map(|c| c.contains_key("workspace")))
+            .unwrap_or(false)
+    };
+    let member_dir = manifest_path.parent()?;
+    let explicit = CargoFile::new(manifest_path).ok().and_then(|cargo_file| {
+        let document = cargo_file.contents()?;
+        let workspace = document.get("package")?.get("workspace")?.as_str()?;
+        Some(member_dir.join(workspace).join("Cargo.toml"))
+    });
+    if explicit.is_some() {
+        return explicit;
+    }
+    member_dir
+        .ancestors()
+        .map(|dir| dir.join("Cargo.toml"))
+        .find(|path| path.exists() && is_workspace(path))
+}
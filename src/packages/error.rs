@@ -1,3 +1,8 @@
+use std::path::PathBuf;
+
+use miette::NamedSource;
+use semver::Version;
+
 use crate::PackageName;
 
 #[derive(Debug, thiserror::Error, miette::Diagnostic)]
@@ -6,9 +11,67 @@ pub enum PackageError {
     #[error("Package ({0}) not found in Cargo.toml")]
     #[diagnostic(code(PackageError::PackageNameNotFound))]
     PackageNameNotFound(PackageName),
+    #[error("Invalid package name '{name}': {reason}")]
+    #[diagnostic(
+        code(PackageError::InvalidPackageName),
+        help(
+            "Package names only contain letters, numbers, '-' and '_', and don't start with a digit."
+        )
+    )]
+    InvalidPackageName { name: String, reason: String },
     #[error("Package name not provided")]
     #[diagnostic(code(PackageError::PackageNameNotProvided))]
     PackageNameNotProvided,
     #[error("No root package or workspace package")]
-    NoRootVersion,
+    #[diagnostic(code(PackageError::NoRootVersion))]
+    NoRootVersion {
+        /// The members and their versions, listed in the help.
+        members: Vec<(PackageName, Version)>,
+        #[help]
+        help: String,
+    },
+    #[error("Dependency cycle between packages: {0:?}")]
+    #[diagnostic(
+        code(PackageError::DependencyCycle),
+        help(
+            "Members are ordered after their dependencies, remove a dependency to break the cycle."
+        )
+    )]
+    DependencyCycle(Vec<PackageName>),
+}
+
+/// Version found on disk does not match the version from cargo metadata.
+#[derive(Debug, thiserror::Error, miette::Diagnostic)]
+#[diagnostic(url(docsrs))]
+pub enum VersionMismatch {
+    #[error("Package ({package}) version mismatch: expected {expected}, found {found}")]
+    #[diagnostic(
+        code(VersionMismatch::Manifest),
+        help("Check {} was not modified during the run.", path.display())
+    )]
+    Manifest {
+        package: PackageName,
+        expected: Version,
+        found: Version,
+        path: PathBuf,
+        #[source_code]
+        source_code: NamedSource<String>,
+        #[label("Found {found}")]
+        label: Option<(usize, usize)>,
+    },
+    #[error("Package ({package}) lockfile mismatch: expected {expected}, found {found}")]
+    #[diagnostic(
+        code(VersionMismatch::Lockfile),
+        help("Run `cargo generate-lockfile` to update {}.", path.display())
+    )]
+    Lockfile {
+        package: PackageName,
+        expected: Version,
+        found: Version,
+        path: PathBuf,
+        #[source_code]
+        source_code: NamedSource<String>,
+        #[label("Found {found}")]
+        label: Option<(usize, usize)>,
+    },
 }
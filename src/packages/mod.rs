@@ -1,4 +1,5 @@
 mod package_name;
+pub(crate) use package_name::glob_matches;
 pub use package_name::PackageName;
 
 mod package;
@@ -9,4 +10,4 @@ mod packages;
 pub use packages::Packages;
 
 mod error;
-pub use error::PackageError;
+pub use error::{PackageError, VersionMismatch};
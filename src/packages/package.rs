@@ -1,6 +1,6 @@
 use crate::{
     Action, Bumpable, CargoFile, PackageName, ReadToml, Result, VersionLocation, current_span,
-    manifest::version_location::VersionType,
+    manifest::version_location::{VersionType, find_workspace_manifest},
 };
 use miette::bail;
 use semver::{BuildMetadata, Prerelease, Version};
@@ -14,6 +14,10 @@ pub struct Package<CargoFileState> {
     version: Version,
     manifest_path: PathBuf,
     cargo_file: CargoFile<CargoFileState>,
+    /// Normal and build dependencies, used to order publishing.
+    dependencies: Vec<PackageName>,
+    /// `false` for `publish = false`, which cargo refuses to publish.
+    publish: bool,
 }
 
 impl<CargoFileState: PartialEq> PartialEq for Package<CargoFileState> {
@@ -70,12 +74,28 @@ impl<CargoFileState> Package<CargoFileState> {
     pub fn version_owned(&self) -> Version {
         self.version.clone()
     }
+
+    /// Names of the normal and build dependencies, excluding dev-dependencies.
+    pub fn dependencies(&self) -> &[PackageName] {
+        &self.dependencies
+    }
+
+    /// `false` if the package is private, i.e. `publish = false`.
+    pub fn publish(&self) -> bool {
+        self.publish
+    }
 }
 
 impl From<cargo_metadata::Package> for Package<ReadToml> {
     fn from(meta_package: cargo_metadata::Package) -> Package<ReadToml> {
         let manifest_path: PathBuf = meta_package.manifest_path.into();
         let cargo_file = CargoFile::new(manifest_path.clone()).expect("from cargo manifest");
+        let dependencies = meta_package
+            .dependencies
+            .iter()
+            .filter(|dep| dep.kind != cargo_metadata::DependencyKind::Development)
+            .map(|dep| dep.name.clone().into())
+            .collect();
         Self {
             name: meta_package.name.to_string().into(),
             version: meta_package.version,
@@ -83,6 +103,11 @@ impl From<cargo_metadata::Package> for Package<ReadToml> {
                 .expect("Cargo manifest run with no error"),
             cargo_file,
             manifest_path,
+            dependencies,
+            // `publish = false` is an empty list of registries.
+            publish: meta_package
+                .publish
+                .is_none_or(|registries| !registries.is_empty()),
         }
     }
 }
@@ -93,7 +118,9 @@ impl Package<ReadToml> {
         let package = match VersionLocation::Package.get_version(cargo_file) {
             Ok(_v) => Ok(VersionType::Package),
             Err(e) => match e.kind() {
-                crate::VersionLocationErrorKind::SetByWorkspace => Ok(VersionType::SetByWorkspace),
+                crate::VersionLocationErrorKind::SetByWorkspace { .. } => {
+                    Ok(VersionType::SetByWorkspace)
+                }
                 _ => Err(e),
             },
         };
@@ -144,8 +171,12 @@ impl Package<ReadToml> {
 
     pub fn write_cargo_file(&mut self) -> Result<Version> {
         if self.version_type() == VersionType::SetByWorkspace {
+            let workspace_manifest = find_workspace_manifest(&self.manifest_path)
+                .map_or("the workspace root manifest".into(), |path| {
+                    path.display().to_string()
+                });
             let msg = format!(
-                "Can't modify SetByWorkspace version from a bool: {}",
+                "Can't write the version of {} as it is set by workspace.package.version in {workspace_manifest}",
                 self.manifest_path.as_os_str().display()
             );
             tracing::error!("{}", msg);
@@ -173,6 +204,8 @@ impl Package<ReadToml> {
             version,
             manifest_path: manifest_path.into(),
             cargo_file,
+            dependencies: Vec::new(),
+            publish: false,
         })
     }
 }
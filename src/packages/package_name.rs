@@ -1,9 +1,12 @@
 use std::{borrow::Borrow, fmt::Display, ops::DerefMut};
 
+use crate::PackageError;
+
 /// Newtype around Package Name.
 ///
 /// `workspace.package` for the workspace package as '.' is an invalid char for a package name.
 #[derive(Debug, Default, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct PackageName(pub String);
 
 impl Display for PackageName {
@@ -68,6 +71,39 @@ impl PartialEq<PackageName> for String {
 }
 
 impl PackageName {
+    /// Validates `name` against cargo's package name rules, the [`workspace_package`] sentinel
+    /// is allowed.
+    ///
+    /// [`workspace_package`]: PackageName::workspace_package
+    pub fn new(name: impl Into<String>) -> Result<PackageName, PackageError> {
+        let name = PackageName(name.into());
+        if name.is_workspace_package() {
+            return Ok(name);
+        }
+        match PackageName::check(&name, |_| false) {
+            Some(reason) => Err(PackageError::InvalidPackageName {
+                name: name.0,
+                reason,
+            }),
+            None => Ok(name),
+        }
+    }
+
+    /// The reason `name` isn't a valid package name, ignoring the characters `allow` accepts.
+    pub(crate) fn check(name: &str, allow: impl Fn(char) -> bool) -> Option<String> {
+        let invalid = name
+            .chars()
+            .find(|&c| !(c.is_ascii_alphanumeric() || c == '-' || c == '_' || allow(c)));
+        match (name.chars().next(), invalid) {
+            (None, _) => Some("the name is empty".into()),
+            (Some(first), _) if first.is_ascii_digit() => {
+                Some(format!("the name can't start with a digit '{first}'"))
+            }
+            (_, Some(c)) => Some(format!("invalid character '{c}'")),
+            _ => None,
+        }
+    }
+
     pub fn is_workspace_package(&self) -> bool {
         self.0 == PackageName::workspace_package()
     }
@@ -76,4 +112,75 @@ impl PackageName {
     pub fn workspace_package() -> PackageName {
         PackageName("workspace.package".into())
     }
+
+    /// Tests the name against a glob `pattern` like cargo's `--package` and `--exclude`.
+    ///
+    /// `*` matches any number of characters and `?` matches exactly one.
+    pub fn matches(&self, pattern: &str) -> bool {
+        glob_matches(&self.0, pattern)
+    }
+}
+
+/// Tests `text` against a glob `pattern`, `*` matches any number of characters, including `/`,
+/// and `?` matches exactly one.
+pub(crate) fn glob_matches(text: &str, pattern: &str) -> bool {
+    let name: Vec<char> = text.chars().collect();
+    let pattern: Vec<char> = pattern.chars().collect();
+    let (mut n, mut p) = (0, 0);
+    // Position of the last `*` and the name position it matched up to.
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    star = Some((star_p, n));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_accepts_valid_names() {
+        for name in [
+            "cargo-uv",
+            "serde_json",
+            "a1",
+            "_private",
+            "workspace.package",
+        ] {
+            assert_eq!(PackageName::new(name).unwrap(), PackageName::from(name));
+        }
+    }
+
+    #[test]
+    fn new_rejects_invalid_names() {
+        for (name, reason) in [
+            ("", "the name is empty"),
+            ("1password", "the name can't start with a digit '1'"),
+            ("my crate", "invalid character ' '"),
+            ("serde.json", "invalid character '.'"),
+        ] {
+            match PackageName::new(name) {
+                Err(PackageError::InvalidPackageName { reason: r, .. }) => assert_eq!(r, reason),
+                other => panic!("{name}: {other:?}"),
+            }
+        }
+    }
 }
@@ -6,13 +6,13 @@ use std::{
 };
 
 use cargo_metadata::Metadata;
-use indexmap::IndexSet;
-use miette::Context;
+use miette::{Context, IntoDiagnostic, NamedSource};
 use semver::Version;
+use toml_edit::DocumentMut;
 use tracing::{debug, instrument};
 
-use super::{Package, PackageError, PackageName};
-use crate::{ReadToml, Result, VersionLocation, display_path};
+use super::{Package, PackageError, PackageName, VersionMismatch};
+use crate::{CargoFile, ReadToml, Result, VersionLocation, VersionType, display_path};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Packages {
@@ -35,6 +35,9 @@ pub struct Packages {
 
     /// The workspace.package of the root Cargo.toml
     workspace_package: Option<Package<ReadToml>>,
+
+    /// Members each member depends on, excluding dev-dependencies.
+    member_dependencies: HashMap<PackageName, Vec<PackageName>>,
 }
 
 impl AsRef<HashMap<PackageName, Package<ReadToml>>> for Packages {
@@ -57,15 +60,30 @@ impl Packages {
         let root_cargo_toml = root_directory.join("Cargo.toml");
         let root_cargo_lock = root_directory.join("Cargo.lock");
         let workspace_package = Package::workspace_package(&root_cargo_toml).ok();
+        let packages: HashMap<PackageName, Package<ReadToml>> =
+            HashMap::from_iter(packages.iter().map(|package| {
+                let package: Package<ReadToml> = package.clone().into();
+                (package.name().clone(), package)
+            }));
+        let member_dependencies = packages
+            .iter()
+            .map(|(name, package)| {
+                let dependencies = package
+                    .dependencies()
+                    .iter()
+                    .filter(|&dep| dep != name && packages.contains_key(dep))
+                    .cloned()
+                    .collect();
+                (name.clone(), dependencies)
+            })
+            .collect();
 
         let mut ret = Self {
             root_directory: root_directory.clone(),
             root_package: None,
             root_version: None,
-            packages: HashMap::from_iter(packages.iter().map(|package| {
-                let package: Package<ReadToml> = package.clone().into();
-                (package.name().clone(), package)
-            })),
+            packages,
+            member_dependencies,
             default_members: HashSet::from_iter(
                 default_members.iter().map(|n| PackageName(n.to_string())),
             ),
@@ -197,8 +215,8 @@ impl Packages {
         self.packages.values().collect::<HashSet<_>>()
     }
 
-    pub fn package_set_mut(&mut self) -> HashSet<&mut Package<ReadToml>> {
-        self.packages.values_mut().collect::<HashSet<_>>()
+    pub fn packages_iter_mut(&mut self) -> impl Iterator<Item = &mut Package<ReadToml>> {
+        self.packages.values_mut()
     }
 
     pub fn get_root_package_version(&self) -> Option<Version> {
@@ -207,55 +225,59 @@ impl Packages {
 
     /// Determine what the root version is for the packages.
     ///
-    /// Order of checks:
-    /// - root version is set
-    /// - root package version
-    /// - workplace.package.version
-    /// - TODO: If all versions are the same use that version
+    /// In order of precedence:
+    /// 1. The root version when set.
+    /// 2. The root package version.
+    /// 3. `workspace.package.version`.
+    /// 4. The version of the members when they all share it.
     ///
+    /// Otherwise [`PackageError::NoRootVersion`].
     pub fn root_version(&self) -> Result<Version, PackageError> {
-        let error_no_root_package = PackageError::NoRootVersion;
-        if self.root_version.is_some() {
-            return Ok(self
-                .root_version
-                .as_ref()
-                .expect("There is a root version")
-                .clone());
-        };
-
-        // Checking the root package
-        if let Some(root_package_name) = &self.root_package {
-            let root_package = self.packages.get(root_package_name);
-            if let Some(root_package) = root_package {
-                return Ok(root_package.version().clone());
-            };
-        };
+        self.root_version
+            .clone()
+            .or_else(|| self.get_root_package_version())
+            .or_else(|| self.workspace_package_version())
+            .or_else(|| self.unanimous_member_version())
+            .ok_or_else(|| self.no_root_version())
+    }
 
-        if let Some(workspace_package) = self.workspace_package.as_ref() {
-            // Checking the workspace package
-            match VersionLocation::WorkspacePackage.get_version(workspace_package.cargo_file()) {
-                Ok(v) => return Ok(v),
-                Err(e) => match e.kind() {
-                    crate::VersionLocationErrorKind::SetByWorkspace => unreachable!(),
-                    crate::VersionLocationErrorKind::NotFound(_) => (),
-                    crate::VersionLocationErrorKind::PackageNotFound => unreachable!(),
-                    crate::VersionLocationErrorKind::WorkspaceNotFound => (),
-                    crate::VersionLocationErrorKind::ItemInvalid(_) => (),
-                    crate::VersionLocationErrorKind::SemverError(_) => (),
-                },
-            };
-        }
-        let mut versions = IndexSet::new();
-        for ver in self.packages().values().map(|p| p.version()) {
-            versions.insert(ver);
+    /// [`PackageError::NoRootVersion`] listing the member versions to pick from.
+    pub fn no_root_version(&self) -> PackageError {
+        let mut members: Vec<_> = self
+            .packages
+            .values()
+            .map(|package| (package.name().clone(), package.version().clone()))
+            .collect();
+        members.sort();
+        let list: String = members
+            .iter()
+            .map(|(name, version)| format!("\n  {name} {version}"))
+            .collect();
+        PackageError::NoRootVersion {
+            help: format!(
+                "The members have differing versions:{list}\nSelect one with `-p <name>`, or all of them with `--workspace`."
+            ),
+            members,
         }
+    }
 
-        if versions.len() == 1 {
-            Ok(versions.pop().unwrap().clone())
-        } else {
-            Err(error_no_root_package)
-        }
+    /// `workspace.package.version` read from the root manifest.
+    fn workspace_package_version(&self) -> Option<Version> {
+        let workspace_package = self.workspace_package.as_ref()?;
+        VersionLocation::WorkspacePackage
+            .get_version(workspace_package.cargo_file())
+            .ok()
     }
+
+    /// The version of every member if they are all the same.
+    fn unanimous_member_version(&self) -> Option<Version> {
+        let mut versions = self.packages.values().map(Package::version);
+        let first = versions.next()?;
+        versions
+            .all(|version| version == first)
+            .then(|| first.clone())
+    }
+
     pub fn workspace_package(&self) -> Option<&Package<ReadToml>> {
         self.workspace_package.as_ref()
     }
@@ -274,6 +296,66 @@ impl Packages {
     pub fn root_directory(&self) -> &Path {
         &self.root_directory
     }
+
+    /// Orders `package_names` so each package comes after its dependencies.
+    ///
+    /// Only dependencies within `package_names` are considered, ties are ordered by name.
+    #[instrument(skip_all)]
+    pub fn publish_order(
+        &self,
+        package_names: &[PackageName],
+    ) -> Result<Vec<PackageName>, PackageError> {
+        let ordered = self.topological_order(package_names.iter().collect())?;
+        debug!("Publish order: {:?}", ordered);
+        Ok(ordered)
+    }
+
+    /// Orders every member after the members it depends on, ties broken by name.
+    ///
+    /// Errors with [`PackageError::DependencyCycle`] naming the members in the cycle.
+    pub fn dependency_graph(&self) -> Result<Vec<PackageName>, PackageError> {
+        self.topological_order(self.member_dependencies.keys().collect())
+    }
+
+    fn topological_order(
+        &self,
+        mut remaining: Vec<&PackageName>,
+    ) -> Result<Vec<PackageName>, PackageError> {
+        remaining.sort();
+        remaining.dedup();
+        let mut ordered: Vec<PackageName> = Vec::with_capacity(remaining.len());
+        let dependencies = |name: &PackageName| {
+            self.member_dependencies
+                .get(name)
+                .map(Vec::as_slice)
+                .unwrap_or_default()
+        };
+
+        while !remaining.is_empty() {
+            let ready = remaining.iter().position(|&name| {
+                dependencies(name)
+                    .iter()
+                    .all(|dep| !remaining.contains(&dep))
+            });
+            match ready {
+                Some(idx) => ordered.push(remaining.remove(idx).clone()),
+                None => {
+                    // Drop the dependents of the cycle until only the cycle is left.
+                    while let Some(idx) = remaining.iter().position(|&name| {
+                        !remaining
+                            .iter()
+                            .any(|&other| dependencies(other).contains(name))
+                    }) {
+                        remaining.remove(idx);
+                    }
+                    return Err(PackageError::DependencyCycle(
+                        remaining.into_iter().cloned().collect(),
+                    ));
+                }
+            }
+        }
+        Ok(ordered)
+    }
 }
 
 impl Packages {
@@ -282,19 +364,18 @@ impl Packages {
         let root_package = self.root_package.as_ref();
         let path_base = self.root_directory();
         let make_relative = |package: &Package<ReadToml>| {
-            PathBuf::new()
-                .join(".")
-                .join(
-                    package
-                        .manifest_path()
-                        .parent()
-                        .unwrap()
-                        .strip_prefix(path_base)
-                        .unwrap(),
-                )
-                .as_os_str()
-                .to_string_lossy()
-                .into_owned()
+            let manifest_dir = package
+                .manifest_path()
+                .parent()
+                .unwrap_or(package.manifest_path());
+            match manifest_dir.strip_prefix(path_base) {
+                Ok(relative) => PathBuf::from(".").join(relative),
+                // The member is outside the workspace root, e.g. through a symlinked directory.
+                Err(_) => manifest_dir.to_path_buf(),
+            }
+            .as_os_str()
+            .to_string_lossy()
+            .into_owned()
         };
         let _ = writeln!(
             ret_string,
@@ -303,7 +384,12 @@ impl Packages {
         );
         if let Some(root) = root_package {
             let package = self.get_package(root).unwrap();
-            let _ = writeln!(ret_string, "Root package: {root} {}", package.version(),);
+            let _ = writeln!(
+                ret_string,
+                "Root package: {root} {} ({})",
+                package.version(),
+                package.version_type()
+            );
         }
 
         if !self.default_members.is_empty() {
@@ -336,15 +422,17 @@ impl Packages {
             if Some((name, package)) == last {
                 let _ = writeln!(
                     ret_string,
-                    "└─ {name} {}: {}",
+                    "└─ {name} {} ({}): {}",
                     package.version(),
+                    package.version_type(),
                     make_relative(package)
                 );
             } else {
                 let _ = writeln!(
                     ret_string,
-                    "├─ {name} {}: {}",
+                    "├─ {name} {} ({}): {}",
                     package.version(),
+                    package.version_type(),
                     make_relative(package)
                 );
             }
@@ -353,6 +441,15 @@ impl Packages {
     }
 }
 
+/// Location of the first `"<version>"` after `search_from` in `contents`.
+fn version_span(contents: &str, search_from: &str, version: &Version) -> Option<(usize, usize)> {
+    let start = contents.find(search_from).unwrap_or_default();
+    let version = version.to_string();
+    contents[start..]
+        .find(&format!("\"{version}\""))
+        .map(|idx| (start + idx + 1, version.len()))
+}
+
 impl From<&Metadata> for Packages {
     #[track_caller]
     #[instrument(skip_all)]
@@ -415,6 +512,131 @@ impl Packages {
             .context("setting workspace.package version")
     }
 
+    /// Used by [`Task::VerifyVersion`]
+    ///
+    /// Re-reads the manifest (and the lockfile if present) and checks the version matches
+    /// the version reported by cargo metadata.
+    ///
+    /// [`Task::VerifyVersion`]: crate::Task::VerifyVersion
+    #[instrument(skip(self))]
+    pub fn verify_version(&self, package_name: &PackageName) -> Result<Version> {
+        let package = if package_name.is_workspace_package() {
+            self.workspace_package()
+        } else {
+            self.packages.get(package_name)
+        }
+        .ok_or(miette::miette!("No package by name: {package_name}"))?;
+        let expected = package.version().clone();
+
+        let (path, location) = match package.version_type() {
+            VersionType::Package => (package.manifest_path().as_path(), VersionLocation::Package),
+            VersionType::SetByWorkspace | VersionType::WorkspacePackage => {
+                (self.root_manifest_path(), VersionLocation::WorkspacePackage)
+            }
+        };
+        let cargo_file = CargoFile::new(path)?;
+        let found = location.get_version(&cargo_file)?;
+        if found != expected {
+            let contents = cargo_file
+                .contents()
+                .map(|c| c.to_string())
+                .unwrap_or_default();
+            let search_from = match location {
+                VersionLocation::Package => "[package]",
+                VersionLocation::WorkspacePackage => "[workspace.package]",
+            };
+            Err(VersionMismatch::Manifest {
+                package: package_name.clone(),
+                label: version_span(&contents, search_from, &found),
+                source_code: NamedSource::new(display_path!(path).to_string(), contents),
+                path: path.to_path_buf(),
+                expected: expected.clone(),
+                found,
+            })?;
+        }
+
+        if !package_name.is_workspace_package() && self.root_cargo_lock.exists() {
+            self.verify_lockfile_version(package_name, &expected)?;
+        }
+
+        Ok(expected)
+    }
+
+    /// Checks the local package entry in the root Cargo.lock matches the expected version.
+    fn verify_lockfile_version(
+        &self,
+        package_name: &PackageName,
+        expected: &Version,
+    ) -> Result<()> {
+        let path = self.root_cargo_lock_path();
+        let contents = std::fs::read_to_string(path).into_diagnostic()?;
+        let lockfile = contents.parse::<DocumentMut>().into_diagnostic()?;
+        let Some(lock_packages) = lockfile.get("package").and_then(|p| p.as_array_of_tables())
+        else {
+            return Ok(());
+        };
+
+        let found = lock_packages
+            .iter()
+            .filter(|p| !p.contains_key("source"))
+            .find(|p| p.get("name").and_then(|n| n.as_str()) == Some(package_name.as_str()))
+            .and_then(|p| p.get("version").and_then(|v| v.as_str()))
+            .map(Version::parse);
+
+        match found {
+            Some(Ok(found)) if &found != expected => Err(VersionMismatch::Lockfile {
+                package: package_name.clone(),
+                label: version_span(&contents, &format!("name = \"{package_name}\""), &found),
+                source_code: NamedSource::new(display_path!(path).to_string(), contents),
+                path: path.to_path_buf(),
+                expected: expected.clone(),
+                found,
+            })?,
+            Some(Err(e)) => Err(e).into_diagnostic()?,
+            _ => Ok(()),
+        }
+    }
+
+    /// Used by [`Task::UpdateDependents`]
+    ///
+    /// Re-reads every member manifest and the root manifest and sets the version requirement
+    /// of any dependency on `package_name` to `new_version`. Returns the manifests changed,
+    /// which are only written when not a dry run.
+    ///
+    /// [`Task::UpdateDependents`]: crate::Task::UpdateDependents
+    #[instrument(skip(self))]
+    pub fn update_dependents(
+        &self,
+        package_name: &PackageName,
+        new_version: &Version,
+        dry_run: bool,
+    ) -> Result<Vec<PathBuf>> {
+        let mut manifests: Vec<&Path> = self
+            .packages
+            .values()
+            .map(|package| package.manifest_path().as_path())
+            .collect();
+        manifests.push(self.root_manifest_path());
+        manifests.sort();
+        manifests.dedup();
+
+        let mut updated = Vec::new();
+        for path in manifests {
+            let mut cargo_file = CargoFile::new(path)?;
+            if cargo_file.set_dependency_version(package_name.as_str(), new_version) {
+                if !dry_run {
+                    cargo_file.write_cargo_file()?;
+                }
+                tracing::info!(
+                    "Updated {package_name} requirement in {}",
+                    display_path!(path)
+                );
+                updated.push(path.to_path_buf());
+            }
+        }
+        Ok(updated)
+    }
+
     /// Used by [`Task::WriteCargoToml`]
     ///
     /// [`Task::WriteCargoToml`]: crate::Task::WriteCargoToml
@@ -2,7 +2,8 @@ use miette::{IntoDiagnostic, bail};
 use tracing::instrument;
 
 use crate::{Result, current_span};
-use std::process::{Child, Command, Output};
+use std::io::Write;
+use std::process::{Child, Command, Output, Stdio};
 
 pub trait OutputExt {
     fn stderr(&self) -> String;
@@ -11,11 +12,11 @@ pub trait OutputExt {
 
 impl OutputExt for Output {
     fn stderr(&self) -> String {
-        String::from_iter(self.stderr.iter().map(|&c| char::from(c)))
+        String::from_utf8_lossy(&self.stderr).into_owned()
     }
 
     fn stdout(&self) -> String {
-        String::from_iter(self.stdout.iter().map(|&c| char::from(c)))
+        String::from_utf8_lossy(&self.stdout).into_owned()
     }
 }
 
@@ -87,13 +88,53 @@ impl Process {
         let span = current_span!();
         span.record("program", cmd.get_program().to_str().unwrap_or_default());
 
-        tracing::debug!("Running: {}", Process::display_command(&cmd));
+        let command = Process::display_command(&cmd);
+        tracing::debug!("Running: {command}");
         match self {
-            Process::Output => Ok(ProcessOutput::Output(cmd.output().into_diagnostic()?)),
+            Process::Output => {
+                cmd.stderr(Stdio::piped());
+                let output = cmd.output().into_diagnostic()?;
+                Process::log_failure(&command, &output);
+                Ok(ProcessOutput::Output(output))
+            }
             Process::Spawn => Ok(ProcessOutput::Child(cmd.spawn().into_diagnostic()?)),
         }
     }
 
+    /// Logs the stderr of a command that exited with a non-zero status.
+    fn log_failure(command: &str, output: &Output) {
+        if !output.status.success() {
+            tracing::debug!(
+                "`{command}` exited with {}: {}",
+                output.status,
+                output.stderr().trim_end()
+            );
+        }
+    }
+
+    /// Runs the command with `input` written to its stdin, capturing stdout and stderr.
+    ///
+    /// Used for arguments that are better not passed on the command line, e.g. `git commit --file -`.
+    #[track_caller]
+    #[instrument(skip(cmd, input), name = "Process::run_with_stdin", fields(program))]
+    pub fn run_with_stdin(mut cmd: Command, input: &str) -> Result<Output> {
+        let span = current_span!();
+        span.record("program", cmd.get_program().to_str().unwrap_or_default());
+
+        let command = Process::display_command(&cmd);
+        tracing::debug!("Running: {command}");
+        cmd.stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let mut child = cmd.spawn().into_diagnostic()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(input.as_bytes()).into_diagnostic()?;
+        }
+        let output = child.wait_with_output().into_diagnostic()?;
+        Process::log_failure(&command, &output);
+        Ok(output)
+    }
+
     /// Turns a [Command] into a [String] for displaying.
     ///
     /// ```
@@ -133,4 +174,29 @@ mod tests {
         cmd.args(["not", "a", "command"]);
         assert_eq!("git not a command", Process::display_command(&cmd).as_str());
     }
+
+    #[test]
+    fn output_captures_stderr_of_failing_command() {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "echo failed >&2; exit 3"]);
+        let output = Process::Output.run(cmd).unwrap().try_into_output().unwrap();
+        assert_eq!(output.status.code(), Some(3));
+        assert_eq!(output.stderr(), "failed\n");
+    }
+
+    #[test]
+    fn output_decodes_utf8() {
+        let mut cmd = Command::new("printf");
+        cmd.arg("café");
+        let output = Process::Output.run(cmd).unwrap().try_into_output().unwrap();
+        assert_eq!(output.stdout(), "café");
+    }
+
+    #[test]
+    fn run_with_stdin_pipes_input() {
+        let input = "first line\n\nsecond paragraph\n";
+        let output = Process::run_with_stdin(Command::new("cat"), input).unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout(), input);
+    }
 }
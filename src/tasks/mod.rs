@@ -1,10 +1,14 @@
 mod predict_tasks;
+mod release_output;
+pub use release_output::ReleaseOutput;
+mod run_report;
+pub use run_report::RunReport;
 pub use predict_tasks::DisplayTasks;
 #[allow(clippy::module_inception)]
 mod tasks;
 pub use tasks::Tasks;
 mod task;
-pub use task::Task;
+pub use task::{Stage, Task};
 
 use std::process::{ExitStatus, Output};
 
@@ -19,8 +23,14 @@ pub struct TaskError {
     pub errored_task: Task,
     pub output: String,
     pub status_code: Option<ExitStatus>,
-    #[help]
     pub msg: String,
+    /// The [`TaskError::msg`] then the completed and remaining tasks, so the state of the
+    /// release is known.
+    #[help]
+    pub help: String,
+    /// The underlying error of the task, kept so its labels are rendered.
+    #[related]
+    pub related: Vec<miette::Report>,
 }
 
 impl TaskError {
@@ -30,16 +40,42 @@ impl TaskError {
         output: Option<Output>,
         msg: impl Into<String>,
     ) -> Self {
+        let completed_tasks = tasks.completed_tasks();
+        let incomplete_tasks = tasks.incomplete_tasks();
+        let msg = msg.into();
+        let help = format!(
+            "{msg}\nCompleted: {}; Remaining: {}",
+            Self::task_list(&completed_tasks),
+            Self::task_list(&incomplete_tasks)
+        );
         Self {
-            completed_tasks: tasks.completed_tasks(),
-            incomplete_tasks: tasks.incomplete_tasks(),
+            completed_tasks,
+            incomplete_tasks,
             errored_task,
             output: output
                 .as_ref()
                 .map(|o| o.stderr())
                 .unwrap_or("Unknown Output".into()),
             status_code: output.as_ref().map(|o| o.status),
-            msg: msg.into(),
+            msg,
+            help,
+            related: Vec::new(),
         }
     }
+
+    fn task_list(tasks: &[Task]) -> String {
+        if tasks.is_empty() {
+            return "none".into();
+        }
+        tasks
+            .iter()
+            .map(|task| task.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    pub fn with_related(mut self, report: miette::Report) -> Self {
+        self.related.push(report);
+        self
+    }
 }
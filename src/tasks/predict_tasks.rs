@@ -1,25 +1,47 @@
-use std::{env::current_dir, fmt::Display, path::PathBuf};
+use std::{
+    collections::HashMap,
+    env::current_dir,
+    fmt::Display,
+    path::{Path, PathBuf},
+};
 
+use anstyle::{AnsiColor, Style};
 use miette::{IntoDiagnostic, ensure};
+use semver::Version;
 
-use crate::{Action, Bumpable, Cli, PackageName, Packages, Result, Task, Tasks, VersionType};
-#[cfg(feature = "unstable")]
-use crate::{Branch, Stash};
+use crate::{
+    Action, Branch, Bumpable, CargoFile, Cli, GitError, GitFiles, PackageError, PackageName,
+    Packages, Result, Stage, Stash, Task, Tasks, VersionType,
+};
 pub trait Displayable {
     const LAST_ITEM_PREFIX: &str = "└─ ";
     const ITEM_PREFIX: &str = "├─ ";
     const EXTRA_LINE_PREFIX: &str = "│  ";
+    const LAST_EXTRA_LINE_PREFIX: &str = "   ";
 }
 
 pub struct DisplayTasks<'a> {
     tasks: &'a Tasks,
+    color: bool,
 }
 
 impl Displayable for DisplayTasks<'_> {}
 
 impl<'a> DisplayTasks<'a> {
+    const INDEX_STYLE: Style = Style::new().bold();
+    const TYPE_STYLE: Style = AnsiColor::Cyan.on_default();
+
     pub fn new(tasks: &'a Tasks) -> Self {
-        Self { tasks }
+        Self {
+            tasks,
+            color: false,
+        }
+    }
+
+    /// Colors the index and type of each task, see [`Cli::color`].
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
     }
 
     pub fn display(&self) -> Result<()> {
@@ -27,30 +49,48 @@ impl<'a> DisplayTasks<'a> {
         Ok(())
     }
 
-    fn task_item_string(&self, idx: usize, task: &'a Task) -> String {
-        let task_string = format!("{idx}. {task}\n");
-        let mut ret = task_string.lines();
-        let first_line = ret.next().expect("At least 1 line");
-        let mut rem_lines = vec![String::from(DisplayTasks::ITEM_PREFIX) + first_line];
-        for line in ret {
-            let l = String::from(DisplayTasks::EXTRA_LINE_PREFIX) + line;
-            rem_lines.push(l);
+    /// The task under its index, the lines after the first are aligned with the description.
+    fn task_item_string(&self, idx: usize, task: &'a Task, last: bool) -> String {
+        let index = format!("{idx}. ");
+        let task_string = task.to_string();
+        let mut lines = task_string.lines();
+        let first_line = lines.next().expect("At least 1 line");
+        let (item_prefix, extra_line_prefix) = match last {
+            true => (Self::LAST_ITEM_PREFIX, Self::LAST_EXTRA_LINE_PREFIX),
+            false => (Self::ITEM_PREFIX, Self::EXTRA_LINE_PREFIX),
+        };
+        let mut rem_lines = vec![format!(
+            "{item_prefix}{}{}",
+            self.style(Self::INDEX_STYLE, &index),
+            self.style_type(first_line)
+        )];
+        let indent = " ".repeat(index.len());
+        for line in lines {
+            rem_lines.push(format!("{extra_line_prefix}{indent}{line}"));
         }
 
-        rem_lines.join("\n") + "\n"
+        let mut ret = rem_lines.join("\n") + "\n";
+        if last {
+            ret.push('\n');
+        }
+        ret
     }
 
-    fn task_item_string_last(&self, idx: usize, task: &'a Task) -> String {
-        let task_string = format!("{idx}. {task}\n");
-        let mut ret = task_string.lines();
-        let first_line = ret.next().expect("At least 1 line");
-        let mut rem_lines = vec![String::from(DisplayTasks::LAST_ITEM_PREFIX) + first_line];
-        for line in ret {
-            let l = String::from(DisplayTasks::EXTRA_LINE_PREFIX) + line;
-            rem_lines.push(l);
+    /// Styles the type of the task, the text before the first `: `.
+    fn style_type(&self, line: &str) -> String {
+        match line.split_once(": ") {
+            Some((task_type, rest)) => {
+                format!("{}: {rest}", self.style(Self::TYPE_STYLE, task_type))
+            }
+            None => self.style(Self::TYPE_STYLE, line),
         }
+    }
 
-        rem_lines.join("\n") + "\n" + "\n"
+    fn style(&self, style: Style, text: &str) -> String {
+        match self.color {
+            true => format!("{style}{text}{style:#}"),
+            false => text.to_string(),
+        }
     }
 
     pub fn tasks(&self) -> Vec<&Task> {
@@ -63,14 +103,9 @@ impl Display for DisplayTasks<'_> {
         let mut display = String::new();
         display.push_str(&format!("{} Tasks:\n", self.tasks.len()));
         let tasks = self.tasks();
-        let last = tasks.last().expect("No way to have 0 tasks");
+        let last = tasks.len();
         for (idx, task) in tasks.iter().enumerate() {
-            let s = if task == last {
-                self.task_item_string_last(idx + 1, task)
-            } else {
-                self.task_item_string(idx + 1, task)
-            };
-            display.push_str(&s);
+            display.push_str(&self.task_item_string(idx + 1, task, idx + 1 == last));
         }
 
         write!(f, "{display}")
@@ -79,26 +114,58 @@ impl Display for DisplayTasks<'_> {
 
 impl<'a> Tasks {
     /// Generate tasks from user defined [Cli] arguments.
+    ///
+    /// With `--plan` git isn't run, so the checks against the repository are skipped and the
+    /// remotes are only known from `--remote`.
     pub fn generate_tasks(cli_args: &'a Cli, packages: Packages) -> Result<Self> {
-        cli_args.try_allow_dirty()?;
+        let plan = cli_args.plan();
+        if !plan {
+            cli_args.try_allow_dirty()?;
+        }
         let cwd = current_dir().into_diagnostic()?;
         let root_cargo_lock = packages.root_cargo_lock_path().to_path_buf();
         let mut tasks = Tasks::new(packages);
         let git = cli_args.git()?;
-        #[cfg(feature = "unstable")]
-        let git_files = git.dirty_files()?;
         let workspace = cli_args.workspace();
         let pre_release = cli_args.pre();
         let build = cli_args.build();
         let force_version = cli_args.force_version();
+        let allow_downgrade = cli_args.allow_downgrade();
 
-        #[cfg(feature = "unstable")]
-        let current_branch = git.current_branch()?;
-        #[cfg(feature = "unstable")]
-        let mut git_stash = None;
+        // Fail fast on a detached HEAD before any changes are made.
+        if cli_args.git_tag() && !plan {
+            git.attached_branch()?;
+        }
+        // A repository isn't required without '--git-tag', so failing to list tags is ignored.
+        ensure!(
+            !(cli_args.first_release() && !plan && git.has_any_tag().unwrap_or_default()),
+            help = "Drop '--first-release' to release as usual.",
+            "'--first-release' requires a repository without tags, but it has been tagged before."
+        );
+        if cli_args.git_amend() && !plan {
+            git.check_amend()?;
+        }
+        let remotes = || -> Result<Vec<String>> {
+            match (cli_args.git_remote(), plan) {
+                (Some(remote), true) => Ok(vec![remote.to_string()]),
+                (Some(remote), false) => Ok(vec![git.named_remote(remote)?]),
+                (None, true) => Ok(vec![String::from("<remote>")]),
+                (None, false) => git.remotes(&cli_args.git_branch()),
+            }
+        };
 
-        #[cfg(feature = "unstable")]
-        let change_branch = if let Branch::Named { local } = cli_args.git_branch() {
+        // The release is made on `--branch`, stashing the dirty files and switching back after.
+        let mut git_stash = None;
+        let change_branch = if let Branch::Named { local, .. } = cli_args.git_branch() {
+            let (current_branch, git_files) = match plan {
+                true => (Branch::Current, GitFiles::default()),
+                false => {
+                    if !git.branch_exists(&local)? {
+                        Err(GitError::BranchNotFound(local.clone()))?;
+                    }
+                    (git.attached_branch()?, git.dirty_files()?)
+                }
+            };
             if !git_files.is_empty() {
                 let git_stash_task = Task::GitStash {
                     branch: current_branch.clone(),
@@ -109,7 +176,8 @@ impl<'a> Tasks {
             }
             let c: Task = Task::GitSwitchBranch {
                 to: local.into(),
-                from: current_branch.clone(),
+                from: current_branch,
+                restore: false,
             };
             tasks.insert(c.clone(), None);
             Some(c)
@@ -117,22 +185,110 @@ impl<'a> Tasks {
             None
         };
 
+        // Pre-flight, so nothing is changed when the branch is behind.
+        if cli_args.require_up_to_date() {
+            let remotes = match remotes() {
+                // The branch of a first release may not have been pushed, so can't be behind.
+                Err(e) if cli_args.first_release() => {
+                    tracing::info!("Skipping the up to date check: {e}");
+                    Vec::new()
+                }
+                remotes => remotes?,
+            };
+            for remote in remotes {
+                tasks.insert(Task::GitUpToDate(remote), None);
+            }
+        }
+
+        // Before the manifests change so the committed lockfile is checked.
+        if cli_args.locked() {
+            tasks.insert(Task::CargoVerifyLock, None);
+        }
+
+        for command in cli_args.pre_hooks() {
+            tasks.insert(
+                Task::Hook {
+                    command: command.clone(),
+                    stage: Stage::Prepare,
+                },
+                None,
+            );
+        }
+
         let mut change_workspace_package_version: bool = cli_args.workspace_package(); // #40
         let mut paths_to_add: Vec<PathBuf> = Vec::new();
         let (included, excluded) = tasks.partition_packages_owned(workspace)?;
         ensure!(
             !included.is_empty(),
-            help = "Check you are not excluding your root package without including others.",
-            "No packages to modify. Excluded are: {:?}",
+            help = "Check '--package' and '--exclude' select at least one member, or add '--workspace'.",
+            "No packages to modify. Excluded are: {}",
             excluded
                 .iter()
                 .map(|p| p.name().to_string())
                 .collect::<Vec<_>>()
+                .join(", ")
         );
         drop(excluded);
+        // Private members are still bumped and tagged, only publishing them is skipped.
+        let publish_packages: Vec<PackageName> = included
+            .iter()
+            .filter(|p| {
+                let skip = !p.publish() && cli_args.exclude_private();
+                if skip && cli_args.cargo_publish() {
+                    tracing::info!("Not publishing private package: {}", p.name());
+                }
+                !skip
+            })
+            .map(|p| p.name().clone())
+            .collect();
+
+        // One version for every selected member and the workspace package, resolved from the
+        // highest current version so none are downgraded by a bump.
+        let lockstep_version = if cli_args.lockstep() {
+            let highest = included
+                .iter()
+                .map(|p| p.version_owned())
+                .chain(
+                    tasks
+                        .packages()
+                        .workspace_package()
+                        .map(|p| p.version_owned()),
+                )
+                .max()
+                .expect("At least one package is included");
+            match cli_args.action() {
+                action @ (Action::Pre | Action::Patch | Action::Minor | Action::Major) => {
+                    let mut new_version = highest;
+                    new_version.bump(action, pre_release, build, force_version)?;
+                    Some(new_version)
+                }
+                Action::Set => Some(Task::resolve_set_version(
+                    &highest,
+                    cli_args.set_version(),
+                    pre_release,
+                    allow_downgrade,
+                )?),
+                Action::Print | Action::Verify | Action::Tree => None,
+            }
+        } else {
+            None
+        };
+        if lockstep_version.is_some() && tasks.packages().workspace_package().is_some() {
+            change_workspace_package_version = true;
+        }
+        tasks.set_lockstep_version(lockstep_version.clone());
+        // Version each package will be published with.
+        let mut publish_versions: HashMap<PackageName, Version> = HashMap::new();
+        let mut set_by_workspace: Vec<PackageName> = Vec::new();
+        // New version of each package whose version changes.
+        let mut version_changes: Vec<(PackageName, Version)> = Vec::new();
         for package in included {
-            if package.version_type() == VersionType::SetByWorkspace {
+            // Verify checks members individually as the lockfile lists each member.
+            if package.version_type() == VersionType::SetByWorkspace
+                && cli_args.action() != Action::Verify
+            {
                 change_workspace_package_version = true;
+                set_by_workspace.push(package.name().clone());
                 tracing::info!(
                     "Changing Workspace Package Version due to: {}",
                     package.name()
@@ -141,15 +297,33 @@ impl<'a> Tasks {
                 paths_to_add.push(package.manifest_path_owned());
 
                 // As the action needs to be applied to all included packages.
-                let task = Task::from_action(
-                    cli_args.action(),
-                    &package,
-                    cli_args.set_version(),
-                    pre_release,
-                    build,
-                    force_version,
-                )?;
+                let task = match &lockstep_version {
+                    Some(new_version) => Task::from_lockstep(
+                        cli_args.action(),
+                        &package,
+                        new_version,
+                        allow_downgrade,
+                    )?,
+                    None => Task::from_action(
+                        cli_args.action(),
+                        &package,
+                        cli_args.set_version(),
+                        pre_release,
+                        build,
+                        force_version,
+                        allow_downgrade,
+                    )?,
+                };
 
+                publish_versions.insert(
+                    package.name().clone(),
+                    task.new_version()
+                        .cloned()
+                        .unwrap_or(package.version_owned()),
+                );
+                if let Some(new_version) = task.new_version() {
+                    version_changes.push((package.name().clone(), new_version.clone()));
+                }
                 tasks.insert(task.clone(), None);
                 if !cli_args.dry_run() && task.is_version_change() {
                     tasks.insert(Task::WriteCargoToml(package.name().clone()), None);
@@ -164,51 +338,201 @@ impl<'a> Tasks {
                 .ok_or(miette::miette!("workspace.pa"))?;
             let ws_name = workspace_package.name().clone();
             let mut new_version = workspace_package.version_owned();
+            let current_version = new_version.clone();
 
-            let task = match cli_args.action() {
-                Action::Pre | Action::Patch | Action::Minor | Action::Major => {
+            let task = match (&lockstep_version, cli_args.action()) {
+                (Some(lockstep_version), action) => Task::from_lockstep(
+                    action,
+                    workspace_package,
+                    lockstep_version,
+                    allow_downgrade,
+                )?,
+                (None, Action::Pre | Action::Patch | Action::Minor | Action::Major) => {
                     new_version.bump(cli_args.action(), pre_release, build, force_version)?;
                     Task::BumpWorkspace {
                         bump: cli_args.action(),
                         new_version,
                     }
                 }
-                Action::Set => Task::SetWorkspace {
-                    new_version: cli_args.set_version.clone().ok_or(miette::miette!(
-                        "Expected a new version for Task::from_action when action is Set"
-                    ))?,
-                },
-                Action::Print => Task::DisplayVersion(PackageName::workspace_package()),
-                Action::Tree => Task::WorkspaceTree,
+                (None, Action::Set) => {
+                    let new_version = Task::resolve_set_version(
+                        &new_version,
+                        cli_args.set_version(),
+                        pre_release,
+                        allow_downgrade,
+                    )?;
+                    if new_version == current_version && !force_version {
+                        tracing::info!("{ws_name} is already {new_version}");
+                        Task::DisplayVersion(PackageName::workspace_package())
+                    } else {
+                        Task::SetWorkspace { new_version }
+                    }
+                }
+                (None, Action::Print) => Task::DisplayVersion(PackageName::workspace_package()),
+                (None, Action::Verify) => Task::VerifyVersion(PackageName::workspace_package()),
+                (None, Action::Tree) => Task::WorkspaceTree,
             };
+            let ws_version = task.new_version().cloned().unwrap_or(current_version);
+            for package_name in set_by_workspace {
+                if task.is_version_change() {
+                    version_changes.push((package_name.clone(), ws_version.clone()));
+                }
+                publish_versions.insert(package_name, ws_version.clone());
+            }
             tasks.insert(task.clone(), None);
             if !cli_args.dry_run() && task.is_version_change() {
                 tasks.insert(Task::WriteCargoToml(ws_name), None);
             }
         }
 
-        let new_version = tasks.root_version()?;
-        if cli_args.git_tag() {
-            tasks.insert(Task::CargoGenerateLock, None);
-            paths_to_add.push(root_cargo_lock);
-            paths_to_add = paths_to_add
+        // Patches are only read from the root manifest, so a bumped crate may no longer match.
+        let root_manifest = tasks.packages().root_manifest_path();
+        let patched = CargoFile::new(root_manifest)?.get_patched_packages();
+        for (package_name, new_version) in &version_changes {
+            for (section, _) in patched
                 .iter()
-                .map(|p| match p.strip_prefix(&cwd) {
-                    Ok(path) => path.to_path_buf(),
-                    Err(_) => p.clone(),
-                })
-                .collect();
-            tasks.insert(Task::GitAdd(paths_to_add), None);
-            tasks.insert(Task::GitCommit, None);
-            tasks.insert(Task::GitTag(new_version.clone()), None);
-            if cli_args.git_push() {
-                for remote in git.remotes()? {
+                .filter(|(_, name)| name == package_name.as_str())
+            {
+                tracing::warn!(
+                    "{package_name} is overridden in [{section}] of {}, check it still matches {new_version}.",
+                    root_manifest.display()
+                );
+            }
+        }
+
+        // After the manifests are written as the dependents are re-read from disk.
+        if cli_args.update_dependents() {
+            for (package_name, new_version) in version_changes {
+                let dependents =
+                    tasks
+                        .packages()
+                        .update_dependents(&package_name, &new_version, true)?;
+                for path in dependents {
+                    if !paths_to_add.contains(&path) {
+                        paths_to_add.push(path);
+                    }
+                }
+                tasks.insert(
+                    Task::UpdateDependents {
+                        package_name,
+                        new_version,
+                    },
+                    None,
+                );
+            }
+        }
+
+        // Setting the current version changes nothing, so there is nothing to release unless it
+        // has never been released.
+        let version_changed = tasks.tasks().iter().any(|task| task.is_version_change());
+        let release =
+            cli_args.action() != Action::Set || cli_args.first_release() || version_changed;
+        if !release {
+            tracing::info!("No version changed, skipping the git and publish tasks.");
+        }
+
+        let new_version = tasks.root_version()?;
+        let tag = git.generate_tag(
+            &cli_args.tag_format(),
+            tasks
+                .packages()
+                .root_package_name_unchecked()
+                .map(|name| name.as_str()),
+            &new_version,
+        )?;
+        // Check before anything is committed so a commit isn't made that can't be tagged.
+        let tag_exists = release && cli_args.git_tag() && !plan && git.tag_exists(&tag)?;
+        // Releasing a tagged version again is almost always a mistake, even when not tagging.
+        // A repository isn't required without '--git-tag', so failing to list tags is ignored.
+        let version_tagged = tag_exists
+            || (!cli_args.git_tag()
+                && !plan
+                && tasks.tasks().iter().any(|task| task.is_version_change())
+                && git.tag_exists(&tag).unwrap_or_default());
+        if version_tagged && !cli_args.git_force() {
+            Err(GitError::TagExists {
+                tag: tag.clone(),
+                version: new_version.clone(),
+            })?;
+        }
+        if release && let Some(changelog) = cli_args.changelog() {
+            let path = tasks.packages().root_directory().join(changelog);
+            // A dry run doesn't write the changelog, so it may not exist to add.
+            if !cli_args.dry_run() {
+                paths_to_add.push(path.clone());
+            }
+            tasks.insert(
+                Task::UpdateChangelog {
+                    path,
+                    version: new_version.clone(),
+                },
+                None,
+            );
+        }
+        // A dry run would delete the existing tag after forcing it, so only a tag this run
+        // creates is deleted in the cleanup.
+        let creates_tag = release && cli_args.git_tag() && !(tag_exists && cli_args.dry_run());
+        // A first release of the current version has nothing to commit, so `HEAD` is tagged.
+        let commit = version_changed || cli_args.changelog().is_some();
+        if release && cli_args.git_tag() {
+            if commit {
+                // Regenerating would modify a '--locked' lockfile, which is only verified.
+                if cli_args.include_lockfile() && !cli_args.locked() {
+                    tasks.insert(Task::CargoGenerateLock, None);
+                    paths_to_add.push(root_cargo_lock);
+                }
+                paths_to_add = paths_to_add
+                    .iter()
+                    .map(|p| match p.strip_prefix(&cwd) {
+                        Ok(path) => path.to_path_buf(),
+                        Err(_) => p.clone(),
+                    })
+                    .collect();
+                let commit_paths = match cli_args.commit_paths() {
+                    true => paths_to_add.clone(),
+                    false => Vec::new(),
+                };
+                tasks.insert(Task::GitAdd(paths_to_add), None);
+                tasks.insert(Task::GitCommit(commit_paths), None);
+            }
+            if creates_tag {
+                tasks.insert(Task::GitTag(tag.clone()), None);
+            }
+        }
+
+        // Pushing is independent of tagging, only the tag push needs the local tag.
+        if release && cli_args.git_push() {
+            let push_tag = !cli_args.no_tag_push();
+            ensure!(
+                !push_tag || cli_args.git_tag(),
+                help = "Add '--git-tag' to create the tag, or use '--no-tag-push' with '--push-branch' to only push the branch.",
+                "'--git-push' pushes the new tag {tag} which requires '--git-tag'."
+            );
+            for remote in remotes()? {
+                tasks.insert(
+                    Task::GitPush {
+                        remote,
+                        branch: cli_args.git_branch(),
+                        tag: push_tag.then(|| tag.clone()),
+                    },
+                    None,
+                );
+            }
+        }
+
+        // After the GitCommit so the tree is clean without '--allow-dirty'.
+        if release && cli_args.cargo_publish() {
+            // Dependencies first so they are on the registry for their dependents.
+            for package_name in tasks.packages().publish_order(&publish_packages)? {
+                tasks.insert(Task::CargoPublish(package_name.clone()), None);
+                if cli_args.publish_wait().is_some() && !cli_args.dry_run() {
+                    let version = publish_versions
+                        .remove(&package_name)
+                        .ok_or(PackageError::PackageNameNotFound(package_name.clone()))?;
                     tasks.insert(
-                        Task::GitPush {
-                            remote,
-                            #[cfg(feature = "unstable")]
-                            branch: cli_args.git_branch(),
-                            tag: new_version.to_string(),
+                        Task::WaitForPublish {
+                            package_name,
+                            version,
                         },
                         None,
                     );
@@ -216,22 +540,42 @@ impl<'a> Tasks {
             }
         }
 
-        if cli_args.cargo_publish() {
-            tasks.insert(Task::CargoPublish, None);
+        // Runs once the pushes and publishes have finished.
+        for command in cli_args.post_hooks() {
+            tasks.insert(
+                Task::Hook {
+                    command: command.clone(),
+                    stage: Stage::Cleanup,
+                },
+                None,
+            );
         }
 
         // 2nd Last
+        if creates_tag && cli_args.dry_run() {
+            tasks.insert(Task::DeleteGitTag(tag.clone()), None);
+        }
         if cli_args.dry_run() {
-            tasks.insert(Task::DeleteGitTag(new_version.clone()), None);
+            let dirty_files = match plan {
+                true => Vec::new(),
+                false => git.dirty_files()?.paths().map(Path::to_path_buf).collect(),
+            };
+            let tag = creates_tag.then_some(tag);
+            tasks.insert(Task::VerifyClean { dirty_files, tag }, None);
         }
 
         // Last
-        #[cfg(feature = "unstable")]
-        if let Some(Task::GitSwitchBranch { to, from }) = change_branch {
-            tasks.insert(Task::GitSwitchBranch { to: from, from: to }, None);
+        if let Some(Task::GitSwitchBranch { to, from, .. }) = change_branch {
+            tasks.insert(
+                Task::GitSwitchBranch {
+                    to: from,
+                    from: to,
+                    restore: true,
+                },
+                None,
+            );
         }
 
-        #[cfg(feature = "unstable")]
         if let Some(Task::GitStash {
             branch,
             stash: _stash,
@@ -246,8 +590,11 @@ impl<'a> Tasks {
             );
         }
 
+        tasks.sort_by_stage();
         if cli_args.display_tasks() {
-            DisplayTasks::new(&tasks).display()?;
+            DisplayTasks::new(&tasks)
+                .with_color(cli_args.color())
+                .display()?;
         }
 
         Ok(tasks)
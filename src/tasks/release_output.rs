@@ -0,0 +1,64 @@
+use miette::IntoDiagnostic;
+use semver::Version;
+
+use crate::Result;
+
+/// Summary of a completed run for `--output-format json`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct ReleaseOutput {
+    /// The new version of the root package.
+    pub version: String,
+    /// SHA of the release commit, [`None`] when no commit was made.
+    pub commit: Option<String>,
+    /// The git tag, [`None`] when no tag was made.
+    pub tag: Option<String>,
+    pub pushed_remotes: Vec<String>,
+}
+
+impl ReleaseOutput {
+    pub fn new(
+        version: &Version,
+        commit: Option<String>,
+        tag: Option<String>,
+        pushed_remotes: Vec<String>,
+    ) -> Self {
+        Self {
+            version: version.to_string(),
+            commit,
+            tag,
+            pushed_remotes,
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string(self).into_diagnostic()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn release_output_json() {
+        let output = ReleaseOutput::new(
+            &Version::new(1, 2, 3),
+            Some("0123abcd".into()),
+            Some("v1.2.3".into()),
+            vec!["origin".into()],
+        );
+        assert_eq!(
+            output.to_json().unwrap(),
+            r#"{"version":"1.2.3","commit":"0123abcd","tag":"v1.2.3","pushed_remotes":["origin"]}"#
+        );
+    }
+
+    #[test]
+    fn release_output_json_without_git() {
+        let output = ReleaseOutput::new(&Version::new(1, 2, 3), None, None, Vec::new());
+        assert_eq!(
+            output.to_json().unwrap(),
+            r#"{"version":"1.2.3","commit":null,"tag":null,"pushed_remotes":[]}"#
+        );
+    }
+}
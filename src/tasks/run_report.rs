@@ -0,0 +1,34 @@
+use semver::Version;
+
+use super::Task;
+
+/// What a run of [`Tasks`] did, filled in as the tasks complete.
+///
+/// [`Tasks`]: crate::Tasks
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RunReport {
+    /// The new version of the root package, set before running as [`Tasks::root_version`]
+    /// ignores completed tasks.
+    ///
+    /// [`Tasks::root_version`]: crate::Tasks::root_version
+    pub version: Option<Version>,
+    /// SHA of the commit made by [`Task::GitCommit`], [`None`] on a dry run.
+    pub commit: Option<String>,
+    /// The git tag, [`None`] when no tag was made.
+    pub tag: Option<String>,
+    pub pushed_remotes: Vec<String>,
+    /// `true` if a [`Task::CargoPublish`] completed.
+    pub published: bool,
+}
+
+impl RunReport {
+    /// Records the outcome of a completed task.
+    pub fn record(&mut self, task: &Task) {
+        match task {
+            Task::GitTag(tag) => self.tag = Some(tag.clone()),
+            Task::GitPush { remote, .. } => self.pushed_remotes.push(remote.clone()),
+            Task::CargoPublish(_) => self.published = true,
+            _ => {}
+        }
+    }
+}
@@ -1,18 +1,28 @@
-use std::{fmt::Display, path::PathBuf, process::Child};
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+    process::{Child, Command},
+};
 
+use miette::IntoDiagnostic;
 use semver::{BuildMetadata, Prerelease, Version};
 use tracing::instrument;
 
-use crate::{Action, Bumpable, Cargo, Cli, Git, Package, PackageName, Packages, ReadToml, Result};
-
-#[cfg(feature = "unstable")]
-use crate::{Branch, Stash};
+use crate::{
+    Action, Branch, Bumpable, Cargo, Cli, Git, OutputExt, Package, PackageName, Packages, Process,
+    ReadToml, Result, Setable, Stash, Suppress, manifest::toml_file::write_atomic,
+};
 
+/// Serialized with the `serde` feature, see [`Tasks::dry_run_plan`].
+///
+/// [`Tasks::dry_run_plan`]: crate::Tasks::dry_run_plan
 #[derive(Hash, PartialEq, Debug, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Task {
     // Display
     DisplayVersion(PackageName),
     WorkspaceTree,
+    VerifyVersion(PackageName),
 
     // Version adjustment
     Set {
@@ -33,32 +43,92 @@ pub enum Task {
     },
 
     // Git
-    #[cfg(feature = "unstable")]
     GitStash {
         branch: Branch,
         stash: Stash,
     },
+    /// Pre-flight check the branch isn't behind the remote.
+    GitUpToDate(String),
     GitAdd(Vec<PathBuf>),
-    GitCommit,
+    /// Commits only these paths with `--commit-paths`, everything staged when empty.
+    GitCommit(Vec<PathBuf>),
     GitPush {
         remote: String,
 
-        #[cfg(feature = "unstable")]
         branch: Branch,
-        tag: String,
+        /// [`None`] when only the branch is pushed.
+        tag: Option<String>,
     },
-    #[cfg(feature = "unstable")]
     GitSwitchBranch {
         to: Branch,
         from: Branch,
+        /// `true` when switching back to the original branch after the release.
+        restore: bool,
+    },
+    GitTag(String),
+    /// Cleanup of a dry run, only added with the [`GitTag`] that created the tag.
+    ///
+    /// [`GitTag`]: Task::GitTag
+    DeleteGitTag(String),
+    /// Cleanup of a dry run, errors if the working tree or tags changed.
+    VerifyClean {
+        /// Paths that were dirty before the run.
+        dirty_files: Vec<PathBuf>,
+        /// Tag created then deleted by the dry run.
+        tag: Option<String>,
+    },
+
+    /// User command run with the shell, `stage` is [`Stage::Prepare`] for `--pre-hook` and
+    /// [`Stage::Cleanup`] for `--post-hook`.
+    Hook {
+        command: String,
+        stage: Stage,
     },
-    GitTag(Version),
-    DeleteGitTag(Version),
 
     // Cargo
     WriteCargoToml(PackageName),
-    CargoPublish,
+    /// Sets the requirement on the package in the manifests of its dependents.
+    UpdateDependents {
+        package_name: PackageName,
+        new_version: Version,
+    },
+    /// Adds a section for the release to the top of the changelog at `path`.
+    UpdateChangelog {
+        path: PathBuf,
+        version: Version,
+    },
+    CargoPublish(PackageName),
+    /// Waits for the [`Task::CargoPublish`] of the package to finish and appear on the registry.
+    WaitForPublish {
+        package_name: PackageName,
+        version: Version,
+    },
     CargoGenerateLock,
+    /// Pre-flight check the lockfile is up to date for `--locked`.
+    CargoVerifyLock,
+}
+
+/// Stages of a release in the order [`Tasks::run_all`] runs them.
+///
+/// Tasks are sorted by stage before running, tasks of the same stage keep their order.
+///
+/// Spawned tasks are all started before any is joined, a later stage only starts once the
+/// spawned tasks of the earlier stages finish.
+///
+/// [`Tasks::run_all`]: crate::Tasks::run_all
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Stage {
+    /// Pre-flight checks and getting the working tree ready.
+    Prepare,
+    /// Changing and writing the versions.
+    Version,
+    /// Committing and tagging the release.
+    Commit,
+    /// Pushing and publishing, run as child processes.
+    Release,
+    /// Restoring the working tree.
+    Cleanup,
 }
 
 impl Display for Task {
@@ -66,6 +136,7 @@ impl Display for Task {
         let text = match self {
             Task::DisplayVersion(package) => &format!("Print Version: {}", package),
             Task::WorkspaceTree => "Display Workspace Tree",
+            Task::VerifyVersion(package) => &format!("Verify Version: {}", package),
             Task::Bump {
                 package_name: package,
                 bump,
@@ -79,29 +150,49 @@ impl Display for Task {
             Task::SetWorkspace {
                 new_version: version,
             } => &format!("Set Workspace: {}", version),
-            Task::CargoPublish => "Cargo Publish",
+            Task::CargoPublish(package) => &format!("Cargo Publish: {}", package),
+            Task::WaitForPublish {
+                package_name,
+                version,
+            } => &format!("Wait For Publish: {} {}", package_name, version),
             Task::WriteCargoToml(package) => &format!("Write Cargo.toml for: {}", package),
-            #[cfg(feature = "unstable")]
+            Task::UpdateDependents {
+                package_name,
+                new_version,
+            } => &format!("Update Dependents: {} -> {}", package_name, new_version),
+            Task::UpdateChangelog { path, version } => {
+                &format!("Update Changelog: {} {}", path.display(), version)
+            }
             Task::GitSwitchBranch { to, .. } => &format!("Change branch: {}", to),
+            Task::GitUpToDate(remote) => &format!("Git Check Up To Date: {}", remote),
             Task::GitAdd(paths) => &format!("Git Add: {:#?}", paths),
-            #[cfg(feature = "unstable")]
             Task::GitStash {
                 branch,
                 stash: state,
             } => &format!("Git Stash: {state:?} files on {}", branch),
-            #[cfg(feature = "unstable")]
+            Task::GitPush {
+                remote,
+                branch: Branch::Current,
+                tag,
+            } => &format!(
+                "Git Push: {} to {remote}",
+                tag.as_deref().unwrap_or("branch")
+            ),
             Task::GitPush {
                 remote,
                 branch,
                 tag,
-            } => &format!("Git Push: {tag} to {remote} on {branch}"),
-
-            #[cfg(not(feature = "unstable"))]
-            Task::GitPush { remote, tag } => &format!("Git Push: {tag} to {remote}"),
-            Task::GitCommit => "Git Commit",
-            Task::GitTag(version) => &format!("Git Tag: {}", version),
-            Task::DeleteGitTag(version) => &format!("Delete Git Tag: {}", version),
+            } => &format!(
+                "Git Push: {} to {remote} on {branch}",
+                tag.as_deref().unwrap_or("branch")
+            ),
+            Task::GitCommit(..) => "Git Commit",
+            Task::GitTag(tag) => &format!("Git Tag: {}", tag),
+            Task::DeleteGitTag(tag) => &format!("Delete Git Tag: {}", tag),
+            Task::VerifyClean { .. } => "Verify Clean",
+            Task::Hook { command, stage } => &format!("Hook ({stage:?}): {command}"),
             Task::CargoGenerateLock => "Cargo Generate Lockfile",
+            Task::CargoVerifyLock => "Cargo Verify Lockfile",
         };
         write!(f, "{}", text)
     }
@@ -118,6 +209,17 @@ impl Task {
 }
 
 impl Task {
+    /// The new version of a version change task.
+    pub fn new_version(&self) -> Option<&Version> {
+        match self {
+            Task::Set { new_version, .. }
+            | Task::SetWorkspace { new_version }
+            | Task::Bump { new_version, .. }
+            | Task::BumpWorkspace { new_version, .. } => Some(new_version),
+            _ => None,
+        }
+    }
+
     pub fn is_version_change(&self) -> bool {
         matches!(
             self,
@@ -144,6 +246,14 @@ impl Task {
         matches!(self, Self::DisplayVersion(..))
     }
 
+    /// Returns `true` if the task is [`GitCommit`].
+    ///
+    /// [`GitCommit`]: Task::GitCommit
+    #[must_use]
+    pub fn is_git_commit(&self) -> bool {
+        matches!(self, Self::GitCommit(..))
+    }
+
     /// Returns `true` if the task is [`GitPush`].
     ///
     /// [`GitPush`]: Task::GitPush
@@ -152,7 +262,6 @@ impl Task {
         matches!(self, Self::GitPush { .. })
     }
 
-    #[cfg(feature = "unstable")]
     /// Returns `true` if the task is [`GitSwitchBranch`].
     ///
     /// [`GitSwitchBranch`]: Task::GitSwitchBranch
@@ -162,7 +271,41 @@ impl Task {
     }
 
     pub fn is_run_after_completed(&self) -> bool {
-        self.is_delete_git_tag()
+        self.is_delete_git_tag() || matches!(self, Task::VerifyClean { .. })
+    }
+
+    /// Returns `true` if [`Task::run`] returns a child process to join later.
+    pub fn is_spawned(&self) -> bool {
+        matches!(self, Task::GitPush { .. } | Task::CargoPublish(..))
+    }
+
+    pub fn stage(&self) -> Stage {
+        match self {
+            Task::GitUpToDate(..) | Task::CargoVerifyLock => Stage::Prepare,
+            Task::GitSwitchBranch { restore: false, .. } => Stage::Prepare,
+            Task::GitSwitchBranch { restore: true, .. } => Stage::Cleanup,
+            Task::GitStash { stash, .. } => match stash {
+                Stash::Stash => Stage::Prepare,
+                Stash::Unstash | Stash::Dont => Stage::Cleanup,
+            },
+            Task::DisplayVersion(..)
+            | Task::WorkspaceTree
+            | Task::VerifyVersion(..)
+            | Task::Set { .. }
+            | Task::SetWorkspace { .. }
+            | Task::Bump { .. }
+            | Task::BumpWorkspace { .. }
+            | Task::WriteCargoToml(..)
+            | Task::UpdateDependents { .. }
+            | Task::UpdateChangelog { .. }
+            | Task::CargoGenerateLock => Stage::Version,
+            Task::GitAdd(..) | Task::GitCommit(..) | Task::GitTag(..) => Stage::Commit,
+            Task::GitPush { .. } | Task::CargoPublish(..) | Task::WaitForPublish { .. } => {
+                Stage::Release
+            }
+            Task::DeleteGitTag(..) | Task::VerifyClean { .. } => Stage::Cleanup,
+            Task::Hook { stage, .. } => *stage,
+        }
     }
 }
 
@@ -175,6 +318,7 @@ impl<'a> Task {
         pre_release: Option<&Prerelease>,
         build: Option<&BuildMetadata>,
         force_version: bool,
+        allow_downgrade: bool,
     ) -> Result<Task> {
         match action {
             Action::Pre | Action::Patch | Action::Minor | Action::Major => {
@@ -186,14 +330,84 @@ impl<'a> Task {
                     new_version,
                 })
             }
-            Action::Set => Ok(Task::Set {
-                new_version: set_version.ok_or(miette::miette!(
-                    "Expected a version for Task::from_action when the action is `Set`"
-                ))?,
-                package_name: package.name().clone(),
-            }),
+            Action::Set => {
+                let new_version = Task::resolve_set_version(
+                    package.version(),
+                    set_version,
+                    pre_release,
+                    allow_downgrade,
+                )?;
+                // Nothing to change, so only the version is shown.
+                if new_version == *package.version() && !force_version {
+                    tracing::info!("{} is already {new_version}", package.name());
+                    return Ok(Task::DisplayVersion(package.name().clone()));
+                }
+                Ok(Task::Set {
+                    new_version,
+                    package_name: package.name().clone(),
+                })
+            }
             Action::Tree => Ok(Task::WorkspaceTree),
             Action::Print => Ok(Task::DisplayVersion(package.name().clone())),
+            Action::Verify => Ok(Task::VerifyVersion(package.name().clone())),
+        }
+    }
+}
+
+impl Task {
+    /// The version change to the shared `new_version` for `--lockstep`.
+    ///
+    /// [`Action::Set`] gives a set task, otherwise a bump task that records the action.
+    pub fn from_lockstep(
+        action: Action,
+        package: &Package<ReadToml>,
+        new_version: &Version,
+        allow_downgrade: bool,
+    ) -> Result<Task> {
+        let new_version = package
+            .version_owned()
+            .try_set_version(new_version.clone(), allow_downgrade)?;
+        let workspace = package.name().is_workspace_package();
+        Ok(match (action, workspace) {
+            (Action::Set, false) => Task::Set {
+                package_name: package.name().clone(),
+                new_version,
+            },
+            (Action::Set, true) => Task::SetWorkspace { new_version },
+            (bump, false) => Task::Bump {
+                package_name: package.name().clone(),
+                bump,
+                new_version,
+            },
+            (bump, true) => Task::BumpWorkspace { bump, new_version },
+        })
+    }
+
+    /// Resolves the version for [`Action::Set`] from the current version.
+    ///
+    /// - Only `set_version`: the new version.
+    /// - Only `pre_release`: the current version with the pre-release replaced.
+    /// - Both: the new version with the pre-release replaced.
+    pub fn resolve_set_version(
+        current_version: &Version,
+        set_version: Option<Version>,
+        pre_release: Option<&Prerelease>,
+        allow_downgrade: bool,
+    ) -> Result<Version> {
+        match (set_version, pre_release) {
+            (Some(mut new_version), pre_release) => {
+                if let Some(pre) = pre_release {
+                    new_version.pre = pre.clone();
+                }
+                current_version
+                    .clone()
+                    .try_set_version(new_version, allow_downgrade)
+            }
+            // Only changing the pre-release is intentional so isn't a downgrade.
+            (None, Some(pre)) => current_version.clone().set_prerelease(pre.clone()),
+            (None, None) => Err(miette::miette!(
+                "Expected a version or pre-release for Task::from_action when the action is `Set`"
+            )),
         }
     }
 }
@@ -208,27 +422,59 @@ impl Task {
         packages: &mut Packages,
         git: &Git<PathBuf>,
         cargo: &Cargo,
+        message: Option<&str>,
     ) -> Result<Option<Child>> {
         tracing::debug!("Starting task: {}", self);
         let dry_run = cli_args.dry_run();
         let no_verify = cli_args.no_verify();
         let allow_dirty = cli_args.allow_dirty();
-        let root_version = packages.root_version()?;
         let suppress = cli_args.suppress();
+        // Only the summary is printed for other formats.
+        let human = cli_args.output_format().is_human();
         let ret: Result<Option<Child>> = match self {
-            Task::GitPush { remote, tag, .. } => git.push(tag, suppress, dry_run, remote).map(Some),
-            Task::CargoPublish => cargo
-                .publish(suppress, dry_run, no_verify, allow_dirty)
+            Task::GitPush { remote, tag, .. } => git
+                .push(
+                    tag.as_deref()
+                        .map(|tag| cli_args.tag_refspec(tag))
+                        .as_deref(),
+                    suppress,
+                    dry_run,
+                    remote,
+                    cli_args.push_branch(),
+                )
+                .map(Some),
+            Task::CargoPublish(package_name) => cargo
+                .publish(package_name, suppress, dry_run, no_verify, allow_dirty)
                 .map(Some),
+            Task::WaitForPublish {
+                package_name,
+                version,
+            } => cargo
+                .wait_for_publish(
+                    package_name,
+                    version,
+                    cli_args.publish_wait().unwrap_or_default(),
+                    cli_args.publish_poll_interval(),
+                )
+                .map(|_| None),
             Task::DisplayVersion(package_name) => {
-                let package = packages
+                // Printed together by `Tasks::run_all`, for a JSON array of the packages.
+                packages
                     .get_package(package_name)
                     .ok_or(miette::miette!("No package with name {}", package_name))?;
-                println!("{} {}", package_name, package.version());
                 Ok(None)
             }
             Task::WorkspaceTree => {
-                println!("{}", packages.display_tree());
+                if human && !suppress.is_all() {
+                    println!("{}", packages.display_tree());
+                }
+                Ok(None)
+            }
+            Task::VerifyVersion(package_name) => {
+                let version = packages.verify_version(package_name)?;
+                if human {
+                    println!("{} {}", package_name, version);
+                }
                 Ok(None)
             }
             Task::Set {
@@ -247,29 +493,274 @@ impl Task {
                     .set_workspace_package_version(new_version.clone())
                     .map(|_| None)
             }
-            Task::DeleteGitTag(version) => git
-                .tag(version, suppress, Some(vec!["--delete"]))
-                .map(|_| None),
-            #[cfg(feature = "unstable")]
-            Task::GitSwitchBranch { to, .. } => git.checkout(to, suppress).map(|_| None),
+            Task::DeleteGitTag(tag) => git.tag(tag, suppress, Some(vec!["--delete"])).map(|_| None),
+            Task::VerifyClean { dirty_files, tag } => {
+                git.verify_clean(dirty_files, tag.as_deref()).map(|_| None)
+            }
+            Task::GitSwitchBranch { to, .. } => {
+                git.checkout(to, suppress, Stash::Dont).map(|_| None)
+            }
             Task::WriteCargoToml(package_name) => {
                 packages.write_cargo_file(package_name).map(|_| None)
             }
+            Task::UpdateDependents {
+                package_name,
+                new_version,
+            } => packages
+                .update_dependents(package_name, new_version, dry_run)
+                .map(|_| None),
 
-            #[cfg(feature = "unstable")]
-            Task::GitStash { .. } => todo!(),
+            Task::GitStash { stash, .. } => git.stash(suppress, *stash).map(|_| None),
+            Task::GitUpToDate(remote) => git
+                .check_up_to_date(remote, suppress, dry_run)
+                .map(|_| None),
             Task::GitAdd(files) => git.add_files(files).map(|_| None),
-            Task::GitCommit => git
-                .commit(
-                    &cli_args.git_message().unwrap_or(root_version.to_string()),
-                    suppress,
-                    dry_run,
-                )
+            Task::GitCommit(paths) => Task::message_or_version(message, packages)
+                .and_then(|message| {
+                    git.commit(
+                        &message,
+                        paths,
+                        suppress,
+                        dry_run,
+                        cli_args.sign_commit(),
+                        cli_args.commit_author(),
+                        cli_args.git_amend(),
+                    )
+                })
                 .map(|_| None),
-            Task::GitTag(version) => git.tag(version, suppress, None).map(|_| None),
+            Task::GitTag(tag) => {
+                let message = Task::message_or_version(message, packages)?;
+                let mut args =
+                    Git::tag_args(cli_args.git_annotate(), cli_args.git_sign(), &message)
+                        .unwrap_or_default();
+                if cli_args.git_force() {
+                    args.push("--force");
+                }
+                git.tag(tag, suppress, Some(args)).map(|_| None)
+            }
+            Task::UpdateChangelog { path, version } => {
+                Task::update_changelog(path, version, git, dry_run, suppress).map(|_| None)
+            }
+            Task::Hook { command, .. } => {
+                Task::run_hook(command, packages.root_directory(), dry_run, suppress).map(|_| None)
+            }
             Task::CargoGenerateLock => cargo.generate_lockfile().map(|_| None),
+            Task::CargoVerifyLock => cargo.verify_lockfile().map(|_| None),
         };
         tracing::trace!("Finishing task: {} with status Ok:{}", self, ret.is_ok());
         ret
     }
+
+    /// Runs the hook with the shell in `dir`, on a dry run it is only printed.
+    fn run_hook(command: &str, dir: &Path, dry_run: bool, suppress: Suppress) -> Result<()> {
+        if dry_run {
+            if !suppress.is_all() {
+                println!("Would run hook: {command}");
+            }
+            return Ok(());
+        }
+        let mut shell = if cfg!(windows) {
+            let mut shell = Command::new("cmd");
+            shell.arg("/C");
+            shell
+        } else {
+            let mut shell = Command::new("sh");
+            shell.arg("-c");
+            shell
+        };
+        shell.arg(command).current_dir(dir);
+        let output = Process::Output.run(shell)?.try_into_output()?;
+        if !suppress.is_all() {
+            print!("{}", output.stdout());
+        }
+        if !output.status.success() {
+            let code = output.status.code().unwrap_or_default();
+            miette::bail!(
+                help = format!(
+                    "Check the hook runs from {} without cargo-uv.",
+                    dir.display()
+                ),
+                "Hook `{command}` exited with code {code}: {}",
+                output.stderr().trim_end()
+            );
+        }
+        Ok(())
+    }
+
+    /// The commit and tag message, the root version if there is no message.
+    ///
+    /// Only read when needed, as a pure workspace has no root version while its members are
+    /// bumped one at a time.
+    fn message_or_version(message: Option<&str>, packages: &Packages) -> Result<String> {
+        match message {
+            Some(message) => Ok(message.to_string()),
+            None => Ok(packages.root_version()?.to_string()),
+        }
+    }
+
+    /// Adds the commit subjects since the last tag to the changelog, on a dry run the section
+    /// is only printed.
+    fn update_changelog(
+        path: &Path,
+        version: &Version,
+        git: &Git<PathBuf>,
+        dry_run: bool,
+        suppress: Suppress,
+    ) -> Result<()> {
+        // Without a tag there is no previous release, so every commit is listed.
+        let last_tag = match git.has_any_tag()? {
+            true => git.last_tag()?,
+            false => None,
+        };
+        let subjects = git.log_subjects(last_tag.as_deref())?;
+        let section = Task::changelog_section(version, &today(), &subjects);
+        if dry_run {
+            if !suppress.is_all() {
+                println!("Would add to {}:\n{section}", path.display());
+            }
+            return Ok(());
+        }
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => Err(e).into_diagnostic()?,
+        };
+        let contents = Task::prepend_changelog_section(&contents, &section);
+        write_atomic(path, contents.as_bytes())
+    }
+
+    /// The `## [<version>] - <date>` section listing the commit subjects.
+    fn changelog_section(version: &Version, date: &str, subjects: &[String]) -> String {
+        let mut section = format!("## [{version}] - {date}\n\n");
+        for subject in subjects {
+            section.push_str(&format!("- {subject}\n"));
+        }
+        section.push('\n');
+        section
+    }
+
+    /// Inserts the section before the first `## ` heading, keeping any title above it.
+    ///
+    /// A new changelog is given a `# Changelog` title.
+    fn prepend_changelog_section(contents: &str, section: &str) -> String {
+        if contents.trim().is_empty() {
+            return format!("# Changelog\n\n{section}");
+        }
+        let heading = contents
+            .match_indices("## ")
+            .map(|(idx, _)| idx)
+            .find(|idx| *idx == 0 || contents[..*idx].ends_with('\n'));
+        match heading {
+            Some(idx) => format!("{}{section}{}", &contents[..idx], &contents[idx..]),
+            None => format!("{}\n\n{section}", contents.trim_end()),
+        }
+    }
+}
+
+/// Today's date in UTC as `YYYY-MM-DD`.
+fn today() -> String {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Converts days since 1970-01-01 to a `(year, month, day)` in the proleptic Gregorian calendar.
+///
+/// From Howard Hinnant's `civil_from_days`.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(v: &str) -> Version {
+        Version::parse(v).unwrap()
+    }
+
+    #[test]
+    fn resolve_set_version_only_version() {
+        let new_version =
+            Task::resolve_set_version(&version("1.4.0"), Some(version("1.5.0")), None, false);
+        assert_eq!(new_version.unwrap(), version("1.5.0"));
+    }
+
+    #[test]
+    fn resolve_set_version_only_pre() {
+        let pre = Prerelease::new("rc.1").unwrap();
+        let new_version = Task::resolve_set_version(&version("1.4.0"), None, Some(&pre), false);
+        assert_eq!(new_version.unwrap(), version("1.4.0-rc.1"));
+    }
+
+    #[test]
+    fn resolve_set_version_and_pre() {
+        let pre = Prerelease::new("rc.1").unwrap();
+        let new_version =
+            Task::resolve_set_version(&version("1.4.0"), Some(version("2.0.0")), Some(&pre), false);
+        assert_eq!(new_version.unwrap(), version("2.0.0-rc.1"));
+    }
+
+    #[test]
+    fn resolve_set_version_requires_version_or_pre() {
+        assert!(Task::resolve_set_version(&version("1.4.0"), None, None, false).is_err());
+    }
+
+    #[test]
+    fn changelog_section_lists_subjects() {
+        let subjects = ["fix: two".to_string(), "feat: one".to_string()];
+        assert_eq!(
+            Task::changelog_section(&version("0.2.0"), "2024-02-29", &subjects),
+            "## [0.2.0] - 2024-02-29\n\n- fix: two\n- feat: one\n\n"
+        );
+    }
+
+    #[test]
+    fn changelog_section_is_prepended() {
+        let section = "## [0.2.0] - 2024-02-29\n\n- new\n\n";
+        assert_eq!(
+            Task::prepend_changelog_section("", section),
+            format!("# Changelog\n\n{section}")
+        );
+        assert_eq!(
+            Task::prepend_changelog_section("# Changelog\n\n## [0.1.0] - 2024-01-01\n", section),
+            format!("# Changelog\n\n{section}## [0.1.0] - 2024-01-01\n")
+        );
+        assert_eq!(
+            Task::prepend_changelog_section("# Changelog\nNotes.\n", section),
+            format!("# Changelog\nNotes.\n\n{section}")
+        );
+    }
+
+    #[test]
+    fn civil_from_days_dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+    }
+
+    #[test]
+    fn stages_commit_before_release() {
+        let push = Task::GitPush {
+            remote: "origin".into(),
+            branch: Branch::Current,
+            tag: None,
+        };
+        assert!(Task::WriteCargoToml("a".into()).stage() < Task::GitCommit(Vec::new()).stage());
+        assert!(Task::GitTag("0.1.0".into()).stage() < push.stage());
+        assert_eq!(push.stage(), Task::CargoPublish("a".into()).stage());
+        assert!(push.is_spawned() && !Task::GitTag("0.1.0".into()).is_spawned());
+    }
 }
@@ -1,21 +1,37 @@
-use std::process::Child;
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    process::{Child, Output},
+    time::{Duration, Instant},
+};
 
 use indexmap::{IndexMap, IndexSet};
+use miette::IntoDiagnostic;
 
 use semver::Version;
 use tracing::{info, instrument};
 
 use crate::{
-    Cli, Package, PackageError, Packages, ReadToml, Result, SplitVec, cli::Workspace, current_span,
+    Branch, Cargo, Cli, Git, GitError, OutputExt, Package, Packages, PushedRef, ReadToml, Result,
+    SplitVec, cli::Workspace, current_span, manifest::toml_file::write_atomic,
 };
 
-use super::{Task, TaskError};
+use super::{ReleaseOutput, RunReport, Stage, Task, TaskError};
+
+/// Wait between polling passes of [`Tasks::join_all`] while a child is still running.
+const JOIN_POLL_INTERVAL: Duration = Duration::from_millis(20);
 
 #[derive(Debug)]
 pub struct Tasks {
     tasks: IndexMap<Task, Option<Child>>,
     completed: IndexSet<Task>,
     packages: Packages,
+    /// Filled in as the tasks complete.
+    report: RunReport,
+    /// Contents of the files before a task wrote them, [`None`] if it didn't exist.
+    snapshots: IndexMap<PathBuf, Option<Vec<u8>>>,
+    /// Version shared by every package with `--lockstep`.
+    lockstep_version: Option<Version>,
 }
 
 impl Tasks {
@@ -24,6 +40,9 @@ impl Tasks {
             packages,
             tasks: IndexMap::default(),
             completed: IndexSet::default(),
+            report: RunReport::default(),
+            snapshots: IndexMap::default(),
+            lockstep_version: None,
         }
     }
 
@@ -60,6 +79,7 @@ impl Tasks {
     ///
     /// Returns if the task is newly completed.
     pub fn complete_task(&mut self, task: &Task) -> bool {
+        self.report.record(task);
         self.completed.insert(task.clone())
     }
 
@@ -101,11 +121,35 @@ impl Tasks {
     pub fn tasks(&self) -> Vec<&Task> {
         self.tasks.keys().by_ref().collect()
     }
+    /// Tasks with a running [Child], joined by [`Tasks::join_all`].
+    pub fn spawned_tasks(&self) -> Vec<&Task> {
+        self.tasks
+            .iter()
+            .filter(|(task, child)| child.is_some() && !self.completed.contains(*task))
+            .map(|(task, _)| task)
+            .collect()
+    }
+
     pub fn tasks_owned(&self) -> Vec<Task> {
         self.tasks.keys().cloned().collect()
     }
 
-    #[cfg(feature = "unstable")]
+    /// The tasks [`Tasks::run_all`] will run in order, without the cleanup tasks.
+    ///
+    /// Nothing is run, so the plan can be inspected or serialized first.
+    pub fn dry_run_plan(&self) -> Vec<Task> {
+        self.tasks
+            .keys()
+            .filter(|task| !task.is_run_after_completed())
+            .cloned()
+            .collect()
+    }
+
+    /// Sorts the tasks by [`Task::stage`], keeping the order of tasks in the same stage.
+    pub fn sort_by_stage(&mut self) {
+        self.tasks.sort_by(|a, _, b, _| a.stage().cmp(&b.stage()));
+    }
+
     pub fn get_change_branch(&self) -> Option<&Task> {
         for task in self.tasks() {
             match task {
@@ -124,6 +168,10 @@ impl Tasks {
         &mut self.packages
     }
 
+    pub fn set_lockstep_version(&mut self, lockstep_version: Option<Version>) {
+        self.lockstep_version = lockstep_version;
+    }
+
     pub fn set_packages(&mut self, packages: Packages) {
         self.packages = packages;
     }
@@ -173,17 +221,40 @@ impl AsMut<IndexSet<Task>> for Tasks {
 impl Tasks {
     #[instrument(skip_all)]
     pub fn run_all(mut self, cli_args: &Cli) -> Result<Self> {
-        tracing::debug!("Starting running tasks sequentially");
+        tracing::debug!("Starting running tasks by stage");
         let git = cli_args.git()?;
-        let cargo = cli_args.cargo()?;
+        let cargo = self.cargo(cli_args)?;
+        self.sort_by_stage();
         let task_list = self.tasks_owned();
         let mut packages = self.packages.clone();
+        self.report.version = Some(self.root_version()?);
+        let message = self.git_message(cli_args)?;
 
         for task in task_list {
             if task.is_run_after_completed() {
                 continue;
             }
-            match task.run(cli_args, &mut packages, &git, &cargo) {
+            // Publishing must finish before checking the registry for it.
+            if let Task::WaitForPublish { package_name, .. } = &task {
+                self = self.wait_for_child(Task::CargoPublish(package_name.clone()))?;
+            }
+            // A dependency must be on the registry before its dependents are published.
+            if let Task::CargoPublish(package_name) = &task {
+                let dependencies = packages
+                    .get_package(package_name)
+                    .map(|package| package.dependencies().to_vec())
+                    .unwrap_or_default();
+                for dependency in dependencies {
+                    self = self.wait_for_child(Task::CargoPublish(dependency))?;
+                }
+            }
+            for spawned in self.spawned_tasks_before(task.stage()) {
+                self = self.wait_for_child(spawned)?;
+            }
+            if !cli_args.no_rollback() {
+                self.snapshot(&task, &packages)?;
+            }
+            match task.run(cli_args, &mut packages, &git, &cargo, message.as_deref()) {
                 Ok(Some(c)) => {
                     let child = self
                         .get_mut(&task)
@@ -191,32 +262,83 @@ impl Tasks {
                     *child = Some(c)
                 }
                 Ok(None) => {
+                    if task.is_git_commit() && !cli_args.dry_run() {
+                        self.report.commit = Some(git.head()?);
+                    }
+                    // The manifests written must be the ones on the branch switched to.
+                    if let Task::GitSwitchBranch {
+                        to, restore: false, ..
+                    } = &task
+                    {
+                        match self.switched_packages(cli_args, to) {
+                            Ok(switched) => {
+                                packages = switched.clone();
+                                self.packages = switched;
+                            }
+                            Err(e) => {
+                                let msg = e.to_string();
+                                return Err(self.fail(task, None, msg).with_related(e))?;
+                            }
+                        }
+                    }
                     self.complete_task(&task);
                 }
                 Err(e) => {
                     tracing::error!("{task}, {e}");
-                    return Err(TaskError::from_tasks(self, task, None, e.to_string()))?;
+                    let msg = e.to_string();
+                    return Err(self.fail(task, None, msg).with_related(e))?;
                 }
             }
         }
+        self.print_versions(cli_args, &packages)?;
 
         Ok(self)
     }
 
+    /// Prints the versions of the [`Task::DisplayVersion`] tasks in the `--format`.
+    fn print_versions(&self, cli_args: &Cli, packages: &Packages) -> Result<()> {
+        let versions: Vec<_> = self
+            .completed
+            .iter()
+            .filter_map(|task| match task {
+                Task::DisplayVersion(name) => packages
+                    .get_package(name)
+                    .map(|package| (name.clone(), package.version().clone())),
+                _ => None,
+            })
+            .collect();
+        if versions.is_empty() || !cli_args.output_format().is_human() {
+            return Ok(());
+        }
+        print!("{}", cli_args.print_format().render(&versions)?);
+        Ok(())
+    }
+
     #[allow(clippy::result_large_err)]
     #[instrument(skip_all, fields(remaining_tasks), name = "Tasks::join_all")]
     /// Joins all remaining [Task] with [Child] process.
-    pub fn join_all(mut self) -> miette::Result<Tasks, TaskError> {
+    pub fn join_all(mut self, cli_args: &Cli) -> miette::Result<Tasks, TaskError> {
         tracing::debug!("Starting to join tasks: {}", self.remaining_tasks_left());
         let span = current_span!();
-        while self.remaining_tasks() {
-            'tasks: for task in self.incomplete_tasks() {
+        let mut push_attempts: HashMap<Task, u32> = HashMap::new();
+        // When each child was first seen running, reset when a push is retried.
+        let mut started: HashMap<Task, Instant> = HashMap::new();
+        let mut passes = 0;
+        // The cleanup tasks are left for `run_cleanup_tasks`.
+        while self.incomplete_tasks().len() > self.run_after_completed_tasks().len() {
+            if passes > 0 {
+                // Only reached while a child is still running, so don't spin on `try_wait`.
+                std::thread::sleep(JOIN_POLL_INTERVAL);
+            }
+            passes += 1;
+            let tasks = self.incomplete_tasks().into_iter();
+            'tasks: for task in tasks.filter(|task| !task.is_run_after_completed()) {
                 let child_option = match self.get_mut(&task) {
                     Some(c) => c,
                     None => {
                         span.record("remaining_tasks", self.remaining_tasks_left());
                         tracing::info!("No child process existed for: {}", task);
-                        self.completed.insert(task);
+                        self.complete_task(&task);
                         continue 'tasks;
                     }
                 };
@@ -226,19 +348,38 @@ impl Tasks {
                         Ok(Some(exit_status)) => exit_status,
                         Ok(None) => {
                             // Task still going
+                            let elapsed = started
+                                .entry(task.clone())
+                                .or_insert_with(Instant::now)
+                                .elapsed();
+                            if let Some(timeout) = cli_args.process_timeout()
+                                && elapsed >= timeout
+                            {
+                                if let Err(e) = child.kill() {
+                                    tracing::warn!("Failed to kill {task}: {e}");
+                                }
+                                let output = child_option
+                                    .take()
+                                    .and_then(|child| child.wait_with_output().ok());
+                                span.record("remaining_tasks", self.remaining_tasks_left());
+                                let msg =
+                                    format!("{task} timed out after {elapsed:.1?} and was killed");
+                                tracing::error!("{msg}");
+                                return Err(self.fail(task, output, msg));
+                            }
                             continue 'tasks;
                         }
                         Err(e) => {
                             span.record("remaining_tasks", self.remaining_tasks_left());
                             let msg = format!("Error occured while running {task:?}: {}", e);
                             tracing::error!(msg);
-                            return Err(TaskError::from_tasks(self, task, None, ""));
+                            return Err(self.fail(task, None, ""));
                         }
                     }
                 } else {
                     span.record("remaining_tasks", self.remaining_tasks_left());
                     tracing::info!("No child process existed for: {}", task);
-                    self.completed.insert(task);
+                    self.complete_task(&task);
                     continue 'tasks;
                 };
                 let output = child_option
@@ -246,17 +387,70 @@ impl Tasks {
                     .expect("Already contuned if none.")
                     .wait_with_output()
                     .expect("Already checked in try_wait.");
+                // The remote already having every ref isn't a failed push.
+                let exit_status_success = exit_status.success()
+                    || (task.is_git_push()
+                        && Git::is_push_up_to_date(&output.stdout(), &output.stderr()));
+                if exit_status_success && !exit_status.success() {
+                    tracing::info!("{task} exited with {exit_status} but the remote is up to date");
+                }
+
+                if !exit_status_success && task.as_git_push().is_some() {
+                    let attempt = push_attempts.entry(task.clone()).or_default();
+                    if *attempt < cli_args.push_retries()
+                        && Git::is_transient_push_failure(&output.stderr())
+                    {
+                        *attempt += 1;
+                        let delay = cli_args.push_retry_delay(*attempt);
+                        tracing::warn!(
+                            "{task} failed, retrying in {delay:?} ({attempt}/{}): {}",
+                            cli_args.push_retries(),
+                            output.stderr().trim_end()
+                        );
+                        std::thread::sleep(delay);
+                        match self.rerun_task(&task, cli_args) {
+                            Ok(child) => {
+                                started.remove(&task);
+                                *self
+                                    .get_mut(&task)
+                                    .expect("task should be present in tasks") = child;
+                                continue 'tasks;
+                            }
+                            Err(e) => {
+                                let msg = e.to_string();
+                                return Err(self.fail(task, None, msg).with_related(e));
+                            }
+                        }
+                    }
+                }
 
-                if !exit_status.success() {
+                if !exit_status_success {
                     let msg = format!(
                         "{task:?} exited with code: {:?}",
                         output.status.code().unwrap_or_default()
                     );
                     span.record("remaining_tasks", self.remaining_tasks_left());
                     tracing::error!("{msg}");
-                    return Err(TaskError::from_tasks(self, task, Some(output), msg));
+                    let rejected = match &task {
+                        Task::GitPush { remote, .. } => {
+                            let refs = PushedRef::rejected(&output.stdout());
+                            (!refs.is_empty()).then(|| GitError::PushRejected {
+                                remote: remote.clone(),
+                                refs,
+                            })
+                        }
+                        _ => None,
+                    };
+                    let error = self.fail(task, Some(output), msg);
+                    return Err(match rejected {
+                        Some(rejected) => error.with_related(rejected.into()),
+                        None => error,
+                    });
                 }
-                self.completed.insert(task.clone());
+                if task.as_git_push().is_some() && !cli_args.suppress().includes_git() {
+                    print!("{}", output.stdout());
+                }
+                self.complete_task(&task);
                 span.record("remaining_tasks", self.remaining_tasks_left());
                 tracing::info!("{task:?} Complete");
             }
@@ -271,22 +465,186 @@ impl Tasks {
         Ok(self)
     }
 
+    /// Saves the files the task writes so [`Tasks::rollback`] can restore them.
+    fn snapshot(&mut self, task: &Task, packages: &Packages) -> Result<()> {
+        let paths = match task {
+            Task::WriteCargoToml(package_name) if package_name.is_workspace_package() => {
+                vec![packages.root_manifest_path().to_path_buf()]
+            }
+            Task::WriteCargoToml(package_name) => packages
+                .get_package(package_name)
+                .map(|package| package.manifest_path_owned())
+                .into_iter()
+                .collect(),
+            Task::UpdateDependents {
+                package_name,
+                new_version,
+            } => packages.update_dependents(package_name, new_version, true)?,
+            Task::CargoGenerateLock => vec![packages.root_cargo_lock_path().to_path_buf()],
+            Task::UpdateChangelog { path, .. } => vec![path.clone()],
+            _ => return Ok(()),
+        };
+        for path in paths {
+            if self.snapshots.contains_key(&path) {
+                continue;
+            }
+            let contents = match std::fs::read(&path) {
+                Ok(contents) => Some(contents),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+                Err(e) => Err(e).into_diagnostic()?,
+            };
+            self.snapshots.insert(path, contents);
+        }
+        Ok(())
+    }
+
+    /// Restores the files written by the tasks to their contents before running.
+    ///
+    /// Skipped once [`Task::GitCommit`] has completed, as the written files are committed.
+    pub fn rollback(&mut self) -> Result<()> {
+        if self.completed.iter().any(Task::is_git_commit) {
+            tracing::warn!("Not rolling back as the new version is committed.");
+            return Ok(());
+        }
+        for (path, contents) in self.snapshots.drain(..) {
+            match contents {
+                Some(contents) => write_atomic(&path, &contents)?,
+                None => std::fs::remove_file(&path).into_diagnostic()?,
+            }
+            tracing::warn!("Rolled back: {}", path.display());
+        }
+        Ok(())
+    }
+
+    /// Rolls back the written files then creates the [TaskError] for the failed task.
+    fn fail(mut self, task: Task, output: Option<Output>, msg: impl Into<String>) -> TaskError {
+        match self.rollback() {
+            Ok(()) => TaskError::from_tasks(self, task, output, msg),
+            Err(e) => TaskError::from_tasks(self, task, output, msg).with_related(e),
+        }
+    }
+
+    /// [Cargo] for the workspace root from [`Packages::root_manifest_path`].
+    fn cargo(&self, cli_args: &Cli) -> Result<Cargo> {
+        Ok(cli_args
+            .cargo()?
+            .with_manifest_path(self.packages.root_manifest_path().to_path_buf()))
+    }
+
+    /// Waits for the [Child] of the task and completes it, fails if it exited unsuccessfully.
+    fn wait_for_child(mut self, task: Task) -> Result<Self> {
+        let Some(child) = self.get_mut(&task).and_then(Option::take) else {
+            return Ok(self);
+        };
+        let output = child.wait_with_output().into_diagnostic()?;
+        if !output.status.success() {
+            let msg = format!(
+                "{task:?} exited with code: {:?}",
+                output.status.code().unwrap_or_default()
+            );
+            return Err(self.fail(task, Some(output), msg))?;
+        }
+        self.complete_task(&task);
+        Ok(self)
+    }
+
+    /// Spawned tasks still running from a stage before `stage`.
+    fn spawned_tasks_before(&self, stage: Stage) -> Vec<Task> {
+        self.spawned_tasks()
+            .into_iter()
+            .filter(|task| task.stage() < stage)
+            .cloned()
+            .collect()
+    }
+
+    /// Message for the commit and tag, [`None`] to use the version.
+    ///
+    /// Either `--message` or the rendered `--message-template`, with `{previous}` taken
+    /// from the packages before any task ran.
+    fn git_message(&self, cli_args: &Cli) -> Result<Option<String>> {
+        if let Some(message) = cli_args.git_message() {
+            return Ok(Some(message));
+        }
+        let Some(template) = cli_args.message_template() else {
+            return Ok(None);
+        };
+        let previous = self.packages.root_version()?;
+        let version = match &self.report.version {
+            Some(version) => version.clone(),
+            None => self.root_version()?,
+        };
+        let name = self.packages.root_package_name_unchecked();
+        Git::render_message(template, name.map(|n| n.as_ref()), version, previous).map(Some)
+    }
+
+    /// Reads the packages again after switching to `branch`, erroring if a version differs as
+    /// the new versions were worked out from the previous branch.
+    fn switched_packages(&self, cli_args: &Cli, branch: &Branch) -> Result<Packages> {
+        let switched = Packages::from(&cli_args.read_metadata()?);
+        for (name, package) in self.packages.packages() {
+            if let Some(found) = switched.get_package(name)
+                && found.version() != package.version()
+            {
+                Err(GitError::BranchVersionMismatch {
+                    branch: branch.to_string(),
+                    package: name.to_string(),
+                    expected: package.version().clone(),
+                    found: found.version().clone(),
+                })?;
+            }
+        }
+        Ok(switched)
+    }
+
+    /// Runs the task again, used to retry a task with a failed [Child].
+    fn rerun_task(&self, task: &Task, cli_args: &Cli) -> Result<Option<Child>> {
+        let git = cli_args.git()?;
+        let cargo = self.cargo(cli_args)?;
+        let mut packages = self.packages.clone();
+        let message = self.git_message(cli_args)?;
+        task.run(cli_args, &mut packages, &git, &cargo, message.as_deref())
+    }
+
     #[instrument(skip_all, fields(cleanup_tasks))]
     pub fn run_cleanup_tasks(self, cli_args: &Cli) -> Result<Self> {
         tracing::debug!("Starting running cleanup tasks");
         let git = cli_args.git()?;
-        let cargo = cli_args.cargo()?;
+        let cargo = self.cargo(cli_args)?;
         let task_list = self.run_after_completed_tasks();
         current_span!().record("cleanup_tasks", format!("{:?}", &task_list));
         tracing::trace!("cleanup tasks");
         let mut packages = self.packages.clone();
+        let message = self.git_message(cli_args)?;
         for task in task_list {
-            task.run(cli_args, &mut packages, &git, &cargo)?;
+            task.run(cli_args, &mut packages, &git, &cargo, message.as_deref())?;
         }
         Ok(self)
     }
 }
 
+impl Tasks {
+    /// Summary of the completed tasks for [`OutputFormat::Json`].
+    ///
+    /// [`OutputFormat::Json`]: crate::OutputFormat::Json
+    pub fn release_output(&self) -> Result<ReleaseOutput> {
+        let version = match &self.report.version {
+            Some(version) => version.clone(),
+            None => self.root_version()?,
+        };
+        Ok(ReleaseOutput::new(
+            &version,
+            self.report.commit.clone(),
+            self.report.tag.clone(),
+            self.report.pushed_remotes.clone(),
+        ))
+    }
+
+    /// What the run did, e.g. the version, tag and remotes pushed to.
+    pub fn report(&self) -> &RunReport {
+        &self.report
+    }
+}
+
 impl Tasks {
     pub fn partition_packages(
         &self,
@@ -313,14 +671,21 @@ impl Tasks {
             tasks: IndexMap::from_iter(tasks),
             completed: self.completed.clone(),
             packages: self.packages.clone(),
+            report: self.report.clone(),
+            snapshots: self.snapshots.clone(),
+            lockstep_version: self.lockstep_version.clone(),
         }
     }
 
     /// Order of presedence:
+    /// 0. The `--lockstep` version
     /// 1. Root package version
     /// 2. `workspace.package` version
     /// 3. ws members sharing the same version ... version
     pub fn root_version(&self) -> Result<Version> {
+        if let Some(lockstep_version) = &self.lockstep_version {
+            return Ok(lockstep_version.clone());
+        }
         let packages_root_version = self.packages.root_version()?;
         let version_tasks = self.version_change_tasks();
         let root_package = self.packages.get_root_package();
@@ -372,7 +737,7 @@ impl Tasks {
         if versions.len() == 1 {
             Ok(versions.pop().expect("Length of 1").1)
         } else {
-            Err(PackageError::NoRootVersion)?
+            Err(self.packages.no_root_version())?
         }
     }
 }
@@ -383,12 +748,10 @@ impl Tasks {
 mod tests {
 
     use super::*;
-    #[cfg(feature = "unstable")]
     use std::str::FromStr;
 
-    #[cfg(feature = "unstable")]
     use crate::Branch;
-    use crate::{Action, Bumpable, Cli, Packages};
+    use crate::{Action, Bumpable, Cli, DisplayTasks, Packages};
 
     static TEST_BIN_NAME: &str = "cargo-uv";
 
@@ -408,7 +771,7 @@ mod tests {
         Packages::from(meta)
     }
 
-    fn task_list<'a>(mut packages: Packages) -> Vec<Task> {
+    fn task_list(mut packages: Packages) -> Vec<Task> {
         let package = packages
             .get_root_package_mut()
             .expect("known that simple has a root package");
@@ -424,11 +787,10 @@ mod tests {
             },
             Task::GitPush {
                 remote: "origin".into(),
-                #[cfg(feature = "unstable")]
                 branch: Branch::from_str("main").unwrap(),
-                tag: package.version().to_string(),
+                tag: Some(package.version().to_string()),
             },
-            Task::CargoPublish,
+            Task::CargoPublish(package.name().clone()),
         ]
     }
 
@@ -450,4 +812,172 @@ mod tests {
             tasks.tasks()
         )
     }
+
+    #[test]
+    fn join_all_waits_for_a_running_child() {
+        let packages = simple_packages();
+        let cli = default_cli("tests/fixtures/simple/Cargo.toml");
+        let mut tasks = Tasks::new(packages);
+        let child = std::process::Command::new("sleep")
+            .arg("0.3")
+            .spawn()
+            .unwrap();
+        tasks.insert(Task::CargoPublish("simple".into()), Some(child));
+
+        let start = Instant::now();
+        tasks.join_all(&cli).unwrap();
+        let elapsed = start.elapsed();
+        // Waits for the child, give slow machines plenty of room.
+        assert!(elapsed >= Duration::from_millis(300), "{elapsed:?}");
+        assert!(elapsed < Duration::from_secs(10), "{elapsed:?}");
+    }
+
+    #[test]
+    fn join_all_kills_a_task_past_the_timeout() {
+        let packages = simple_packages();
+        let mut cli = default_cli("tests/fixtures/simple/Cargo.toml");
+        cli.process_timeout = Some(1);
+        let mut tasks = Tasks::new(packages);
+        let child = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        tasks.insert(Task::CargoPublish("simple".into()), Some(child));
+
+        let start = Instant::now();
+        let error = tasks.join_all(&cli).unwrap_err();
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert!(error.msg.contains("timed out after"), "{}", error.msg);
+        assert_eq!(error.errored_task, Task::CargoPublish("simple".into()));
+    }
+
+    #[test]
+    fn up_to_date_push_succeeds() {
+        let packages = simple_packages();
+        let cli = default_cli("tests/fixtures/simple/Cargo.toml");
+        let push = task_list(packages.clone())[1].clone();
+        let mut tasks = Tasks::new(packages);
+        // Exits non-zero while the remote already has the tag.
+        let child = std::process::Command::new("sh")
+            .args([
+                "-c",
+                "printf 'To origin\\n=\\trefs/tags/1.0.0:refs/tags/1.0.0\\t[up to date]\\nDone\\n'; exit 1",
+            ])
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        tasks.insert(push.clone(), Some(child));
+
+        let tasks = tasks.join_all(&cli).unwrap();
+        assert_eq!(tasks.completed_tasks(), vec![push]);
+    }
+
+    #[test]
+    fn task_error_renders_completed_and_remaining() {
+        let packages = simple_packages();
+        let task_list = task_list(packages.clone());
+        let mut tasks = Tasks::new(packages);
+        for task in &task_list {
+            tasks.insert(task.clone(), None);
+        }
+        tasks.complete_task(&task_list[0]);
+
+        let error = tasks.fail(task_list[1].clone(), None, "Push failed");
+        let mut rendered = String::new();
+        miette::GraphicalReportHandler::new_themed(miette::GraphicalTheme::unicode_nocolor())
+            .with_width(200)
+            .render_report(&mut rendered, &error)
+            .unwrap();
+        assert!(rendered.contains("Push failed"), "{rendered}");
+        assert!(
+            rendered.contains(&format!(
+                "Completed: {}; Remaining: {}, {}",
+                task_list[0], task_list[1], task_list[2]
+            )),
+            "{rendered}"
+        );
+    }
+
+    #[test]
+    fn sort_by_stage_keeps_order_within_a_stage() {
+        let packages = simple_packages();
+        let task_list = task_list(packages.clone());
+        let mut tasks = Tasks::new(packages);
+        tasks.insert(task_list[1].clone(), None);
+        tasks.insert(Task::GitTag("v1.0.0".into()), None);
+        tasks.insert(task_list[2].clone(), None);
+        tasks.insert(task_list[0].clone(), None);
+
+        tasks.sort_by_stage();
+        assert_eq!(
+            tasks.tasks_owned(),
+            vec![
+                task_list[0].clone(),
+                Task::GitTag("v1.0.0".into()),
+                task_list[1].clone(),
+                task_list[2].clone(),
+            ]
+        );
+    }
+
+    #[test]
+    fn release_output_from_completed_tasks() {
+        let packages = simple_packages();
+        let task_list = task_list(packages.clone());
+        let mut tasks = Tasks::new(packages);
+        for task in &task_list {
+            tasks.insert(task.clone(), None);
+        }
+        tasks.insert(Task::GitTag("v1.0.0".into()), None);
+        tasks.report.version = Some(tasks.root_version().unwrap());
+        tasks.report.commit = Some("0123abcd".into());
+        for task in tasks.tasks_owned() {
+            tasks.complete_task(&task);
+        }
+
+        let output = tasks.release_output().unwrap();
+        assert_eq!(
+            output,
+            ReleaseOutput::new(
+                &Version::new(1, 0, 0),
+                Some("0123abcd".into()),
+                Some("v1.0.0".into()),
+                vec!["origin".into()],
+            )
+        );
+    }
+
+    #[test]
+    fn display_tasks_plain() {
+        let mut tasks = Tasks::new(simple_packages());
+        for task in [
+            Task::Set {
+                package_name: "simple".into(),
+                new_version: Version::new(1, 0, 0),
+            },
+            Task::GitAdd(vec!["Cargo.toml".into(), "Cargo.lock".into()]),
+            Task::GitCommit(Vec::new()),
+            Task::GitAdd(vec!["CHANGELOG.md".into()]),
+        ] {
+            tasks.insert(task, None);
+        }
+
+        let expected = "\
+4 Tasks:
+├─ 1. Set simple: 1.0.0
+├─ 2. Git Add: [
+│         \"Cargo.toml\",
+│         \"Cargo.lock\",
+│     ]
+├─ 3. Git Commit
+└─ 4. Git Add: [
+          \"CHANGELOG.md\",
+      ]
+
+";
+        assert_eq!(DisplayTasks::new(&tasks).to_string(), expected);
+        let colored = DisplayTasks::new(&tasks).with_color(true).to_string();
+        assert!(colored.contains("\u{1b}["), "{colored}");
+    }
 }
@@ -97,6 +97,9 @@ impl FromStr for Identifier {
         Identifier::validate_input(s)?;
 
         if s.chars().all(|c| c.is_ascii_digit()) {
+            if s.len() > 1 && s.starts_with('0') {
+                return Err(IdentifierError::LeadingZero(s.to_string()));
+            }
             u64::from_str(s).map_err(IdentifierError::from)?;
             Ok(Self {
                 kind: IdentifierKind::Numeric,
@@ -232,56 +235,6 @@ impl AsRef<char> for AsciiType {
     }
 }
 
-impl AsciiType {
-    /// Returns `true` if the ascii type is [`UpperAscii`].
-    ///
-    /// [`UpperAscii`]: AsciiType::UpperAscii
-    #[must_use]
-    fn is_upper_ascii(&self) -> bool {
-        matches!(self, Self::UpperAscii(..))
-    }
-
-    fn as_upper_ascii(&self) -> Option<&char> {
-        if let Self::UpperAscii(v) = self {
-            Some(v)
-        } else {
-            None
-        }
-    }
-
-    /// Returns `true` if the ascii type is [`LowerAscii`].
-    ///
-    /// [`LowerAscii`]: AsciiType::LowerAscii
-    #[must_use]
-    fn is_lower_ascii(&self) -> bool {
-        matches!(self, Self::LowerAscii(..))
-    }
-
-    fn as_lower_ascii(&self) -> Option<&char> {
-        if let Self::LowerAscii(v) = self {
-            Some(v)
-        } else {
-            None
-        }
-    }
-
-    /// Returns `true` if the ascii type is [`Number`].
-    ///
-    /// [`Number`]: AsciiType::Number
-    #[must_use]
-    fn is_number(&self) -> bool {
-        matches!(self, Self::Number(..))
-    }
-
-    fn as_number(&self) -> Option<&char> {
-        if let Self::Number(v) = self {
-            Some(v)
-        } else {
-            None
-        }
-    }
-}
-
 impl Borrow<char> for AsciiType {
     fn borrow(&self) -> &char {
         self.as_ref()
@@ -302,6 +255,9 @@ pub enum IdentifierError {
     InvalidChar(char, usize),
     #[error("Expected to Numeric")]
     ExpectedNumeric,
+    /// Numeric identifiers must not include leading zeroes.
+    #[error("Numeric identifier has a leading zero: {0}")]
+    LeadingZero(String),
     #[error("{0}")]
     ParseIntError(ParseIntError),
 }
@@ -418,4 +374,19 @@ mod tests {
         );
         assert_eq!(Ident::from_str("alpha").unwrap(), alpha("alpha"));
     }
+
+    #[test]
+    pub fn from_str_leading_zero() {
+        assert_eq!(Ident::from_str("0").unwrap(), numeric(0));
+        assert_eq!(Ident::from_str("10").unwrap(), numeric(10));
+        assert_eq!(
+            Ident::from_str("00").unwrap_err(),
+            IdentErr::LeadingZero("00".into())
+        );
+        assert_eq!(
+            Ident::from_str("01").unwrap_err(),
+            IdentErr::LeadingZero("01".into())
+        );
+        assert_eq!(Ident::from_str("0a").unwrap(), alpha("0a"));
+    }
 }
@@ -1,6 +1,8 @@
 //! [SemVer Spec](https://semver.org/spec/v2.0.0.html)
 
 pub mod identifiers;
+mod partial;
 pub mod pre_release;
 mod version_extentions;
+pub use partial::parse_partial_version;
 pub use version_extentions::{Bumpable, Incrementable, Setable};
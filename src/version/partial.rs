@@ -0,0 +1,44 @@
+use semver::Version;
+
+/// Parses `MAJOR`, `MAJOR.MINOR` or a full version, zero-filling the missing components.
+///
+/// The pre-release and build metadata are kept, e.g. `1.2-rc.1` is `1.2.0-rc.1`.
+pub fn parse_partial_version(input: &str) -> Result<Version, semver::Error> {
+    let (core, rest) = input.split_at(input.find(['-', '+']).unwrap_or(input.len()));
+    let missing = match core.split('.').count() {
+        1 if !core.is_empty() => ".0.0",
+        2 => ".0",
+        _ => "",
+    };
+    Version::parse(&format!("{core}{missing}{rest}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_fills_missing_components() {
+        assert_eq!(parse_partial_version("2").unwrap(), Version::new(2, 0, 0));
+        assert_eq!(parse_partial_version("2.3").unwrap(), Version::new(2, 3, 0));
+        assert_eq!(
+            parse_partial_version("2.3.4").unwrap(),
+            Version::new(2, 3, 4)
+        );
+        assert_eq!(
+            parse_partial_version("2.3.4-rc.1").unwrap(),
+            Version::parse("2.3.4-rc.1").unwrap()
+        );
+        assert_eq!(
+            parse_partial_version("2.3+build.5").unwrap(),
+            Version::parse("2.3.0+build.5").unwrap()
+        );
+    }
+
+    #[test]
+    fn rejects_invalid() {
+        for input in ["", "v2", "2.", "2..3", "2.3.4.5", "2.x", "-rc.1", "2.3-"] {
+            assert!(parse_partial_version(input).is_err(), "{input}");
+        }
+    }
+}
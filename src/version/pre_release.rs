@@ -1,7 +1,7 @@
 //! ## Semantic Versioning Prerelease
 //!
 //! - A pre-release version MAY be denoted by appending a hyphen and a series of dot separated identifiers
-//!     immediately following the patch version.
+//!   immediately following the patch version.
 //! - Identifiers MUST comprise only ASCII alphanumerics and hyphens [0-9A-Za-z-].
 //! - Identifiers MUST NOT be empty.
 //! - Numeric identifiers MUST NOT include leading zeroes.
@@ -21,7 +21,7 @@
 //!    3. Numeric identifiers always have lower precedence than non-numeric identifiers.
 //!
 //!    4. A larger set of pre-release fields has a higher precedence than a smaller set,
-//!     if all of the preceding identifiers are equal.
+//!       if all of the preceding identifiers are equal.
 //!
 //! Example: 1.0.0-alpha < 1.0.0-alpha.1 < 1.0.0-alpha.beta < 1.0.0-beta < 1.0.0-beta.2 < 1.0.0-beta.11 < 1.0.0-rc.1 < 1.0.0
 //!
@@ -68,7 +68,10 @@ use std::{fmt::Display, marker::PhantomData, ops::Deref, str::FromStr};
 
 use semver::Prerelease;
 
-use crate::{Incrementable, Result, version::identifiers::Identifier};
+use crate::{
+    Incrementable, Result,
+    version::identifiers::{Identifier, IdentifierError},
+};
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 /// Prerelease able to be Bumped.
@@ -186,6 +189,69 @@ pub enum PreError {
     NoField(usize),
 }
 
+/// Invalid pre-release with the offending section labeled.
+#[derive(Debug, Clone, thiserror::Error, miette::Diagnostic, PartialEq, Eq)]
+#[error("Invalid pre-release: {msg}")]
+#[diagnostic(help(
+    "Pre-release identifiers must only contain [0-9A-Za-z-] and be separated by '.'"
+))]
+pub struct InvalidPrerelease {
+    msg: String,
+    #[source_code]
+    source_code: String,
+    #[label("{label_msg}")]
+    label: (usize, usize),
+    label_msg: String,
+}
+
+impl InvalidPrerelease {
+    fn new(pre: &str, error: semver::Error) -> Self {
+        let mut label = (0, pre.len());
+        let mut label_msg = String::from("Invalid pre-release");
+        let mut offset = 0;
+        for field in pre.split('.') {
+            match Identifier::from_str(field) {
+                Ok(_) => (),
+                Err(IdentifierError::EmptyIdent) => {
+                    label = (offset, 0);
+                    label_msg = "Empty identifier".into();
+                    break;
+                }
+                Err(IdentifierError::InvalidChar(c, idx)) => {
+                    let start = field.char_indices().nth(idx).map_or(0, |(i, _)| i);
+                    label = (offset + start, c.len_utf8());
+                    label_msg = format!("Invalid character: {c}");
+                    break;
+                }
+                Err(IdentifierError::LeadingZero(_)) => {
+                    label = (offset, field.len());
+                    label_msg = "Leading zero".into();
+                    break;
+                }
+                Err(_) => {
+                    label = (offset, field.len());
+                    break;
+                }
+            }
+            offset += field.len() + 1;
+        }
+
+        Self {
+            msg: error.to_string(),
+            source_code: pre.to_string(),
+            label,
+            label_msg,
+        }
+    }
+}
+
+/// Parse a [`Prerelease`] returning an error labeling the invalid section.
+///
+/// Used as the value parser for `--pre`.
+pub fn parse_prerelease(pre: &str) -> Result<Prerelease, InvalidPrerelease> {
+    Prerelease::new(pre).map_err(|e| InvalidPrerelease::new(pre, e))
+}
+
 impl<PreType> From<Prerelease> for Pre<PreType> {
     fn from(value: Prerelease) -> Self {
         Self::new(value.as_str()).expect("Coming from semver package")
@@ -275,6 +341,33 @@ mod tests {
         assert_eq!(bumpable.to_string(), bumped.to_string());
     }
 
+    #[test]
+    fn parse_prerelease_valid() {
+        assert_eq!(
+            parse_prerelease("rc.1").unwrap(),
+            Prerelease::new("rc.1").unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_prerelease_labels_invalid_char() {
+        let err = parse_prerelease("rc.1@").unwrap_err();
+        assert_eq!(err.label, (4, 1));
+        assert_eq!(err.source_code, "rc.1@");
+    }
+
+    #[test]
+    fn parse_prerelease_labels_empty_identifier() {
+        let err = parse_prerelease("rc..1").unwrap_err();
+        assert_eq!(err.label, (3, 0));
+    }
+
+    #[test]
+    fn parse_prerelease_labels_leading_zero() {
+        let err = parse_prerelease("rc.01").unwrap_err();
+        assert_eq!(err.label, (3, 2));
+    }
+
     #[test]
     fn moving_between_prerelease_type() {
         let pre_str = "1.1.1.1.1";
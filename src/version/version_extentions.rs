@@ -3,7 +3,11 @@ use semver::{BuildMetadata, Prerelease, Version};
 use std::str::FromStr;
 use tracing::instrument;
 
-use crate::{Action, Result, current_span, error::VersionError};
+use crate::{
+    Action, Result, current_span,
+    error::VersionError,
+    version::pre_release::{Pre, PreBumpable},
+};
 pub trait Bumpable {
     /// Used to bump the version then set the [`Prerelease`] and [`BuildMetadata`].
     fn bump(
@@ -181,6 +185,10 @@ impl Bumpable for Version {
 pub trait Setable {
     fn set_version(&mut self, new_version: Version) -> Result<Version>;
     fn set_prerelease(&mut self, new_prerelease: Prerelease) -> Result<Version>;
+
+    /// Sets the version ensuring the new version is not lower than the current one
+    /// unless `allow_downgrade` is set.
+    fn try_set_version(&mut self, new_version: Version, allow_downgrade: bool) -> Result<Version>;
 }
 impl Setable for Version {
     #[instrument(skip_all, fields(from, to))]
@@ -200,6 +208,20 @@ impl Setable for Version {
         Ok(self.clone())
     }
 
+    #[instrument(skip_all, fields(from, to))]
+    fn try_set_version(&mut self, new_version: Version, allow_downgrade: bool) -> Result<Version> {
+        let span = current_span!();
+        span.record("from", self.to_string());
+        span.record("to", new_version.to_string());
+        if new_version < *self {
+            if !allow_downgrade {
+                Err(VersionError::downgrade(self, &new_version))?;
+            }
+            tracing::warn!("Downgrading version from {} to {}", self, new_version);
+        }
+        self.set_version(new_version)
+    }
+
     #[instrument(skip_all, fields(from, to))]
     fn set_prerelease(&mut self, new_prerelease: Prerelease) -> Result<Version> {
         let span = current_span!();
@@ -229,6 +251,25 @@ impl Incrementable for u64 {
     }
 }
 
+impl Incrementable for Version {
+    /// Increment the last field of the pre-release by n, i.e. `1.0.0-rc.1` -> `1.0.0-rc.4`.
+    ///
+    /// Does nothing if the pre-release is empty.
+    #[instrument(skip(self), fields(from, to))]
+    fn increment_by(&mut self, n: u64) {
+        let span = current_span!();
+        span.record("from", self.to_string());
+        if self.pre.is_empty() {
+            tracing::warn!("No pre-release to increment.");
+            return;
+        }
+        let mut pre = Pre::<PreBumpable>::from(self.pre.clone());
+        pre.increment_by(n);
+        self.pre = pre.into();
+        span.record("to", self.to_string());
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use semver::{BuildMetadata, Prerelease, Version};
@@ -360,6 +401,49 @@ mod tests {
         assert_eq!(v, target);
     }
 
+    #[test]
+    fn increment_version_pre_by_1() {
+        let mut v = version!("1.0.0-rc.1");
+        v.increment();
+        assert_eq!(v, version!("1.0.0-rc.2"));
+    }
+
+    #[test]
+    fn increment_version_pre_by_n() {
+        let mut v = version!("1.0.0-rc.1");
+        v.increment_by(3);
+        assert_eq!(v, version!("1.0.0-rc.4"));
+        assert_eq!(v.to_string(), "1.0.0-rc.4");
+    }
+
+    #[test]
+    fn increment_version_without_pre_is_noop() {
+        let mut v = version!(1 0 0);
+        v.increment_by(3);
+        assert_eq!(v, version!(1 0 0));
+    }
+
+    #[test]
+    fn try_set_version_lower_errors() {
+        let mut v = version!(0 2 0);
+        assert!(v.try_set_version(version!(0 1 0), false).is_err());
+        assert_eq!(v, version!(0 2 0));
+    }
+
+    #[test]
+    fn try_set_version_lower_with_allow_downgrade() {
+        let mut v = version!(0 2 0);
+        v.try_set_version(version!(0 1 0), true).unwrap();
+        assert_eq!(v, version!(0 1 0));
+    }
+
+    #[test]
+    fn try_set_version_higher() {
+        let mut v = version!(0 2 0);
+        v.try_set_version(version!("0.3.0-rc.1"), false).unwrap();
+        assert_eq!(v, version!("0.3.0-rc.1"));
+    }
+
     #[test]
     fn set_prerelease_updates_pre_only() {
         let mut v = version!(1 0 0);
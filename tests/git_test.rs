@@ -0,0 +1,2048 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use cargo_uv::{
+    Branch, Cargo, Cli, Git, GitBuilder, GitError, PackageName, Packages, RunReport, Stage, Stash,
+    Suppress, Task, Tasks,
+};
+
+fn run_git(root: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "git {args:?} failed");
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Creates an empty repo in the temp dir with a commit on `main` and a `release` branch.
+fn temp_repo(name: &str) -> (PathBuf, Git<PathBuf>) {
+    let root = std::env::temp_dir().join(format!("cargo-uv-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+
+    let git = |args: &[&str]| run_git(&root, args);
+    git(&["init", "--initial-branch", "main"]);
+    git(&["config", "user.name", "cargo-uv"]);
+    git(&["config", "user.email", "cargo-uv@example.com"]);
+    std::fs::write(root.join("file.txt"), "main\n").unwrap();
+    git(&["add", "file.txt"]);
+    git(&["commit", "--message", "initial"]);
+    git(&["branch", "release"]);
+
+    let repo = GitBuilder::new().root_directory(root.clone()).build();
+    (root, repo)
+}
+
+#[test]
+fn checkout_returns_previous_branch() {
+    let (root, git) = temp_repo("checkout");
+
+    let (previous, revert) = git
+        .checkout(
+            &Branch::from("release".to_string()),
+            Suppress::ALL,
+            Stash::Dont,
+        )
+        .unwrap();
+    assert_eq!(previous, Branch::from("main".to_string()));
+    assert_eq!(revert, Stash::Dont);
+    assert_eq!(
+        git.current_branch().unwrap(),
+        Branch::from("release".to_string())
+    );
+
+    let (previous, _) = git.checkout(&previous, Suppress::ALL, Stash::Dont).unwrap();
+    assert_eq!(previous, Branch::from("release".to_string()));
+    assert_eq!(
+        git.current_branch().unwrap(),
+        Branch::from("main".to_string())
+    );
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn checkout_stashes_dirty_files() {
+    let (root, git) = temp_repo("checkout-stash");
+    std::fs::write(root.join("file.txt"), "modified\n").unwrap();
+
+    let (_, revert) = git
+        .checkout(
+            &Branch::from("release".to_string()),
+            Suppress::ALL,
+            Stash::Stash,
+        )
+        .unwrap();
+    assert_eq!(revert, Stash::Unstash);
+    assert!(git.dirty_files().unwrap().is_empty());
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn checkout_missing_branch_errors() {
+    let (root, git) = temp_repo("checkout-missing");
+
+    let err = git
+        .checkout(
+            &Branch::from("missing".to_string()),
+            Suppress::ALL,
+            Stash::Dont,
+        )
+        .unwrap_err();
+    assert!(err.to_string().contains("missing"), "{err}");
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn stash_switch_and_restore_modified_tree() {
+    let (root, git) = temp_repo("stash-restore");
+    std::fs::write(root.join("file.txt"), "modified\n").unwrap();
+
+    assert_eq!(
+        git.stash(Suppress::ALL, Stash::Stash).unwrap(),
+        Stash::Unstash
+    );
+    assert!(git.dirty_files().unwrap().is_empty());
+
+    let (previous, _) = git
+        .checkout(
+            &Branch::from("release".to_string()),
+            Suppress::ALL,
+            Stash::Dont,
+        )
+        .unwrap();
+    git.checkout(&previous, Suppress::ALL, Stash::Dont).unwrap();
+
+    assert_eq!(
+        git.stash(Suppress::ALL, Stash::Unstash).unwrap(),
+        Stash::Stash
+    );
+    assert_eq!(
+        std::fs::read_to_string(root.join("file.txt")).unwrap(),
+        "modified\n"
+    );
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn stash_restores_staged_and_untracked_files() {
+    let (root, git) = temp_repo("stash-staged");
+    std::fs::write(root.join("staged.txt"), "staged\n").unwrap();
+    run_git(&root, &["add", "staged.txt"]);
+    std::fs::write(root.join("file.txt"), "unstaged\n").unwrap();
+    std::fs::write(root.join("untracked.txt"), "untracked\n").unwrap();
+
+    assert_eq!(
+        git.stash(Suppress::ALL, Stash::Stash).unwrap(),
+        Stash::Unstash
+    );
+    assert!(git.dirty_files().unwrap().is_empty());
+    assert!(!root.join("untracked.txt").exists());
+
+    assert_eq!(
+        git.stash(Suppress::ALL, Stash::Unstash).unwrap(),
+        Stash::Stash
+    );
+    assert_eq!(
+        run_git(&root, &["status", "--short"]),
+        " M file.txt\nA  staged.txt\n?? untracked.txt\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(root.join("untracked.txt")).unwrap(),
+        "untracked\n"
+    );
+    assert_eq!(run_git(&root, &["stash", "list"]), "");
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn unstash_without_push_is_noop() {
+    let (root, git) = temp_repo("unstash-noop");
+
+    // Clean tree so nothing is pushed.
+    assert_eq!(git.stash(Suppress::ALL, Stash::Stash).unwrap(), Stash::Dont);
+
+    // A stash not created by cargo-uv must be left alone.
+    std::fs::write(root.join("file.txt"), "user stash\n").unwrap();
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(&root)
+        .args(["stash", "push"])
+        .output()
+        .unwrap()
+        .status;
+    assert!(status.success());
+
+    assert_eq!(
+        git.stash(Suppress::ALL, Stash::Unstash).unwrap(),
+        Stash::Dont
+    );
+    assert_eq!(
+        std::fs::read_to_string(root.join("file.txt")).unwrap(),
+        "main\n"
+    );
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn stash_leaves_user_stash_untouched() {
+    let (root, git) = temp_repo("stash-user");
+    std::fs::write(root.join("file.txt"), "user stash\n").unwrap();
+    run_git(&root, &["stash", "push", "--message", "user work"]);
+    std::fs::write(root.join("file.txt"), "cargo-uv stash\n").unwrap();
+
+    assert_eq!(
+        git.stash(Suppress::ALL, Stash::Stash).unwrap(),
+        Stash::Unstash
+    );
+    assert!(git.dirty_files().unwrap().is_empty());
+    // Another stash on top so cargo-uv's isn't the latest.
+    std::fs::write(root.join("file.txt"), "later stash\n").unwrap();
+    run_git(&root, &["stash", "push", "--message", "later work"]);
+
+    assert_eq!(
+        git.stash(Suppress::ALL, Stash::Unstash).unwrap(),
+        Stash::Stash
+    );
+    assert_eq!(
+        std::fs::read_to_string(root.join("file.txt")).unwrap(),
+        "cargo-uv stash\n"
+    );
+    assert_eq!(
+        run_git(&root, &["stash", "list", "--format=%s"]),
+        "On main: later work\nOn main: user work\n"
+    );
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+/// Adds a bare repo as the `origin` remote of `root`.
+fn add_bare_remote(root: &Path) -> PathBuf {
+    let remote = root.with_extension("remote.git");
+    let _ = std::fs::remove_dir_all(&remote);
+    run_git(
+        root,
+        &[
+            "init",
+            "--bare",
+            remote.to_str().unwrap(),
+            "--initial-branch",
+            "main",
+        ],
+    );
+    run_git(root, &["remote", "add", "origin", remote.to_str().unwrap()]);
+    remote
+}
+
+#[test]
+fn push_branch_sets_upstream_and_pushes_commit() {
+    let (root, git) = temp_repo("push-branch");
+    let remote = add_bare_remote(&root);
+    run_git(&root, &["tag", "0.1.0"]);
+    assert!(!git.has_upstream().unwrap());
+
+    let output = git
+        .push(Some("0.1.0"), Suppress::ALL, false, "origin", true)
+        .unwrap()
+        .wait_with_output()
+        .unwrap();
+    assert!(output.status.success());
+
+    assert!(git.has_upstream().unwrap());
+    let head = run_git(&root, &["rev-parse", "HEAD"]);
+    assert_eq!(run_git(&remote, &["rev-parse", "main"]), head);
+    assert_eq!(run_git(&remote, &["rev-parse", "0.1.0^{commit}"]), head);
+
+    std::fs::remove_dir_all(root).unwrap();
+    std::fs::remove_dir_all(remote).unwrap();
+}
+
+#[test]
+fn push_tag_only_leaves_branch() {
+    let (root, git) = temp_repo("push-tag");
+    let remote = add_bare_remote(&root);
+    run_git(&root, &["tag", "0.1.0"]);
+
+    let output = git
+        .push(Some("0.1.0"), Suppress::ALL, false, "origin", false)
+        .unwrap()
+        .wait_with_output()
+        .unwrap();
+    assert!(output.status.success());
+
+    assert!(!git.has_upstream().unwrap());
+    assert!(run_git(&remote, &["branch", "--list"]).is_empty());
+
+    std::fs::remove_dir_all(root).unwrap();
+    std::fs::remove_dir_all(remote).unwrap();
+}
+
+#[test]
+fn tag_lightweight_by_default() {
+    let (root, git) = temp_repo("tag-lightweight");
+    git.tag("0.1.0", Suppress::ALL, Git::tag_args(false, false, "0.1.0"))
+        .unwrap();
+    assert_eq!(run_git(&root, &["cat-file", "-t", "0.1.0"]), "commit\n");
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn tag_annotated() {
+    let (root, git) = temp_repo("tag-annotated");
+    git.tag(
+        "0.1.0",
+        Suppress::ALL,
+        Git::tag_args(true, false, "Release 0.1.0"),
+    )
+    .unwrap();
+    assert_eq!(run_git(&root, &["cat-file", "-t", "0.1.0"]), "tag\n");
+    assert_eq!(
+        run_git(
+            &root,
+            &["tag", "--list", "--format=%(contents:subject)", "0.1.0"]
+        ),
+        "Release 0.1.0\n"
+    );
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn tag_sign_failure_surfaces_stderr() {
+    let (root, git) = temp_repo("tag-sign");
+    run_git(&root, &["config", "gpg.program", "false"]);
+    let err = git
+        .tag("0.1.0", Suppress::ALL, Git::tag_args(false, true, "0.1.0"))
+        .unwrap_err();
+    assert!(err.to_string().contains("gpg"), "{err}");
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn generate_tag_formats() {
+    let (root, git) = temp_repo("generate-tag");
+    let version = semver::Version::new(1, 2, 3);
+
+    assert_eq!(
+        git.generate_tag("{version}", None, &version).unwrap(),
+        "1.2.3"
+    );
+    assert_eq!(
+        git.generate_tag("v{version}", None, &version).unwrap(),
+        "v1.2.3"
+    );
+    assert_eq!(
+        git.generate_tag("{name}-v{version}", Some("mycrate"), &version)
+            .unwrap(),
+        "mycrate-v1.2.3"
+    );
+    assert!(
+        git.generate_tag("{name}-v{version}", None, &version)
+            .is_err()
+    );
+    assert!(git.generate_tag("release", None, &version).is_err());
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn delete_prefixed_tag() {
+    let (root, git) = temp_repo("delete-prefixed-tag");
+    let tag = git
+        .generate_tag("v{version}", None, semver::Version::new(0, 1, 0))
+        .unwrap();
+
+    git.tag(&tag, Suppress::ALL, None).unwrap();
+    assert_eq!(run_git(&root, &["tag", "--list"]), "v0.1.0\n");
+    git.tag(&tag, Suppress::ALL, Some(vec!["--delete"]))
+        .unwrap();
+    assert!(run_git(&root, &["tag", "--list"]).is_empty());
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn dirty_files_handles_renames_and_spaces() {
+    let (root, git) = temp_repo("dirty-files");
+    std::fs::write(root.join("with space.txt"), "space\n").unwrap();
+    run_git(&root, &["mv", "file.txt", "renamed.txt"]);
+
+    let files = git.dirty_files().unwrap();
+    let renamed = files
+        .iter()
+        .find(|f| f.index_status() == 'R')
+        .expect("rename reported");
+    assert_eq!(renamed.path, PathBuf::from("renamed.txt"));
+    assert_eq!(renamed.orig_path, Some(PathBuf::from("file.txt")));
+    assert!(files.iter().any(|f| f.path == Path::new("with space.txt")));
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn add_files_skips_ignored() {
+    let (root, git) = temp_repo("add-ignored");
+    std::fs::write(root.join(".gitignore"), "Cargo.lock\n").unwrap();
+    std::fs::write(root.join("Cargo.toml"), "").unwrap();
+    std::fs::write(root.join("Cargo.lock"), "").unwrap();
+    let files = vec![PathBuf::from("Cargo.toml"), root.join("Cargo.lock")];
+
+    assert_eq!(
+        git.ignored_files(&files).unwrap(),
+        vec![root.join("Cargo.lock")]
+    );
+    git.add_files(&files).unwrap();
+    assert_eq!(
+        run_git(&root, &["diff", "--cached", "--name-only"]),
+        "Cargo.toml\n"
+    );
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn add_files_lists_missing() {
+    let (root, git) = temp_repo("add-missing");
+    std::fs::write(root.join("Cargo.toml"), "").unwrap();
+    let files = vec![PathBuf::from("Cargo.toml"), PathBuf::from("a/Cargo.toml")];
+
+    let err = git.add_files(&files).unwrap_err();
+    assert!(err.to_string().contains("a/Cargo.toml"), "{err}");
+    assert!(!err.to_string().contains("\nCargo.toml"), "{err}");
+    assert!(run_git(&root, &["diff", "--cached", "--name-only"]).is_empty());
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn tag_exists_and_force() {
+    let (root, git) = temp_repo("tag-exists");
+    assert!(!git.tag_exists("0.1.0").unwrap());
+
+    git.tag("0.1.0", Suppress::ALL, None).unwrap();
+    assert!(git.tag_exists("0.1.0").unwrap());
+    assert!(!git.tag_exists("0.1").unwrap());
+
+    assert!(git.tag("0.1.0", Suppress::ALL, None).is_err());
+    git.tag("0.1.0", Suppress::ALL, Some(vec!["--force"]))
+        .unwrap();
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn bumping_to_a_tagged_version_fails() {
+    let root = temp_fixture_repo("simple", "version-tagged");
+    run_git(&root, &["tag", "v0.1.12"]);
+
+    // Without '--git-tag' as well, the release would clash once tagged.
+    for args in [
+        &["patch", "--tag-prefix", "v"][..],
+        &["patch", "--tag-prefix", "v", "--git-tag"],
+    ] {
+        let err = generate_tasks(&root, args).unwrap_err();
+        let Some(GitError::TagExists { tag, version }) = err.downcast_ref::<GitError>() else {
+            panic!("{err:?}");
+        };
+        assert_eq!(tag, "v0.1.12");
+        assert_eq!(version.to_string(), "0.1.12");
+    }
+
+    generate_tasks(&root, &["patch", "--git-tag"]).unwrap();
+    generate_tasks(&root, &["minor", "--tag-prefix", "v", "--git-tag"]).unwrap();
+    generate_tasks(&root, &["patch", "--tag-prefix", "v", "--force-git"]).unwrap();
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn dry_run_keeps_a_pre_existing_tag() {
+    let root = temp_fixture_repo("simple", "dry-run-keeps-tag");
+    run_git(&root, &["tag", "0.1.12"]);
+    let touches_tag = |tasks: &Tasks| {
+        tasks
+            .tasks()
+            .iter()
+            .any(|t| matches!(t, Task::GitTag(_) | Task::DeleteGitTag(_)))
+    };
+
+    // The existing tag is neither forced nor deleted when tagging.
+    let args = ["patch", "--dry-run", "--force-git", "--git-tag"];
+    assert!(!touches_tag(&generate_tasks(&root, &args).unwrap()));
+
+    // Without '--git-tag' no tag is created, so the cleanup must not delete it.
+    let args = ["patch", "--dry-run", "--force-git", "-Q", "all"];
+    let cli = cli_args(&root, &args);
+    let tasks = generate_tasks(&root, &args).unwrap();
+    assert!(!touches_tag(&tasks));
+    tasks
+        .run_all(&cli)
+        .unwrap()
+        .join_all(&cli)
+        .unwrap()
+        .run_cleanup_tasks(&cli)
+        .unwrap();
+    let git = GitBuilder::new().root_directory(root.clone()).build();
+    assert!(git.tag_exists("0.1.12").unwrap());
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn commit_with_author() {
+    let (root, git) = temp_repo("commit-author");
+    std::fs::write(root.join("file.txt"), "release\n").unwrap();
+    run_git(&root, &["add", "file.txt"]);
+
+    git.commit(
+        "Release",
+        &[],
+        Suppress::ALL,
+        false,
+        false,
+        Some("Release Bot <bot@example.com>"),
+        false,
+    )
+    .unwrap();
+    assert_eq!(
+        run_git(&root, &["log", "-1", "--format=%an <%ae>|%cn"]),
+        "Release Bot <bot@example.com>|cargo-uv\n"
+    );
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn commit_message_is_used_verbatim() {
+    let (root, git) = temp_repo("commit-message-stdin");
+    std::fs::write(root.join("file.txt"), "release\n").unwrap();
+    run_git(&root, &["add", "file.txt"]);
+
+    let message = "chore(release): 1.2.3\n\n--amend \"quoted\" and $HOME stay as written.\n";
+    git.commit(message, &[], Suppress::ALL, false, false, None, false)
+        .unwrap();
+    assert_eq!(
+        run_git(&root, &["log", "-1", "--format=%B"]),
+        format!("{message}\n")
+    );
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn commit_dry_run_does_not_commit() {
+    let (root, git) = temp_repo("commit-dry-run");
+    std::fs::write(root.join("file.txt"), "release\n").unwrap();
+    run_git(&root, &["add", "file.txt"]);
+    let head = run_git(&root, &["rev-parse", "HEAD"]);
+
+    git.commit("Release", &[], Suppress::ALL, true, false, None, false)
+        .unwrap();
+    assert_eq!(run_git(&root, &["rev-parse", "HEAD"]), head);
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn commit_sign_failure_surfaces_stderr() {
+    let (root, git) = temp_repo("commit-sign");
+    run_git(&root, &["config", "gpg.program", "false"]);
+    std::fs::write(root.join("file.txt"), "release\n").unwrap();
+    run_git(&root, &["add", "file.txt"]);
+
+    let err = git
+        .commit("Release", &[], Suppress::ALL, false, true, None, false)
+        .unwrap_err();
+    assert!(err.to_string().contains("gpg"), "{err}");
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn named_remote_must_exist() {
+    let (root, git) = temp_repo("named-remote");
+    let remote = add_bare_remote(&root);
+
+    assert_eq!(git.named_remote("origin").unwrap(), "origin");
+    let err = git.named_remote("upstream").unwrap_err();
+    assert!(err.to_string().contains("upstream"), "{err}");
+    assert_eq!(
+        err.help().map(|h| h.to_string()),
+        Some("Available remotes: origin".to_string())
+    );
+
+    std::fs::remove_dir_all(root).unwrap();
+    std::fs::remove_dir_all(remote).unwrap();
+}
+
+#[test]
+fn remotes_errors_without_tracking_branch() {
+    let (root, git) = temp_repo("remotes-untracked");
+    let remote = add_bare_remote(&root);
+
+    // Another branch being pushed doesn't give `main` a remote.
+    run_git(&root, &["push", "-u", "origin", "release"]);
+    let err = git.remotes(&Branch::Current).unwrap_err();
+    assert!(
+        err.help()
+            .is_some_and(|help| help.to_string().contains("--remote")),
+        "{err:?}"
+    );
+    assert_eq!(
+        git.remotes(&Branch::local("release").unwrap()).unwrap(),
+        vec!["origin".to_string()]
+    );
+
+    run_git(&root, &["push", "-u", "origin", "main"]);
+    assert_eq!(
+        git.remotes(&Branch::Current).unwrap(),
+        vec!["origin".to_string()]
+    );
+
+    std::fs::remove_dir_all(root).unwrap();
+    std::fs::remove_dir_all(remote).unwrap();
+}
+
+fn copy_dir(from: &Path, to: &Path) {
+    std::fs::create_dir_all(to).unwrap();
+    for entry in std::fs::read_dir(from).unwrap() {
+        let entry = entry.unwrap();
+        let target = to.join(entry.file_name());
+        if entry.file_type().unwrap().is_dir() {
+            copy_dir(&entry.path(), &target);
+        } else {
+            std::fs::copy(entry.path(), target).unwrap();
+        }
+    }
+}
+
+/// Creates a repo in the temp dir containing the fixture committed on `main`.
+fn temp_fixture_repo(fixture: &str, name: &str) -> PathBuf {
+    let (root, _) = temp_repo(name);
+    copy_dir(
+        &Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(fixture),
+        &root,
+    );
+    run_git(&root, &["add", "."]);
+    run_git(&root, &["commit", "--message", "fixture"]);
+    root
+}
+
+/// [Cli] for the manifest in `root` with the extra `args`.
+fn cli_args(root: &Path, args: &[&str]) -> Cli {
+    let manifest = root.join("Cargo.toml");
+    let args = ["cargo-uv", "--manifest-path", manifest.to_str().unwrap()]
+        .iter()
+        .chain(args)
+        .map(|s| s.to_string())
+        .collect();
+    Cli::cli_args(args, Some("cargo-uv"), None).unwrap()
+}
+
+fn generate_tasks(root: &Path, args: &[&str]) -> cargo_uv::Result<Tasks> {
+    let mut cli = cli_args(root, args);
+    let packages = Packages::from(cli.get_metadata().unwrap());
+    Tasks::generate_tasks(&cli, packages)
+}
+
+#[test]
+fn git_tag_without_push_is_local_only() {
+    let root = temp_fixture_repo("simple", "tag-local-only");
+
+    let tasks =
+        generate_tasks(&root, &["patch", "--git-tag", "--dry-run", "--allow-dirty"]).unwrap();
+    let tasks = tasks.tasks();
+    assert!(tasks.iter().any(|t| matches!(t, Task::GitTag(_))));
+    assert!(tasks.iter().any(|t| matches!(t, Task::DeleteGitTag(_))));
+    assert!(!tasks.iter().any(|t| t.as_git_push().is_some()));
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn git_push_tag_requires_git_tag() {
+    let root = temp_fixture_repo("simple", "push-requires-tag");
+
+    let err = generate_tasks(&root, &["patch", "--git-push", "--allow-dirty"]).unwrap_err();
+    assert!(err.to_string().contains("requires '--git-tag'"), "{err}");
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn no_tag_push_only_pushes_branch() {
+    let root = temp_fixture_repo("simple", "no-tag-push");
+    let remote = add_bare_remote(&root);
+    run_git(&root, &["push", "-u", "origin", "main"]);
+
+    let tasks = generate_tasks(
+        &root,
+        &[
+            "patch",
+            "--git-push",
+            "--push-branch",
+            "--no-tag-push",
+            "--allow-dirty",
+        ],
+    )
+    .unwrap();
+    let pushes: Vec<_> = tasks
+        .tasks()
+        .into_iter()
+        .filter(|t| t.as_git_push().is_some())
+        .collect();
+    assert_eq!(pushes.len(), 1);
+    assert!(matches!(pushes[0], Task::GitPush { tag: None, .. }));
+
+    std::fs::remove_dir_all(root).unwrap();
+    std::fs::remove_dir_all(remote).unwrap();
+}
+
+#[test]
+fn check_up_to_date_fetches_and_fails_when_behind() {
+    let (root, git) = temp_repo("up-to-date");
+    let remote = add_bare_remote(&root);
+    run_git(&root, &["push", "-u", "origin", "main"]);
+    git.check_up_to_date("origin", Suppress::ALL, false)
+        .unwrap();
+
+    // Another clone pushes a commit the local branch doesn't have.
+    let other = root.with_extension("other");
+    let _ = std::fs::remove_dir_all(&other);
+    run_git(
+        &root,
+        &["clone", remote.to_str().unwrap(), other.to_str().unwrap()],
+    );
+    run_git(
+        &other,
+        &[
+            "-c",
+            "user.name=cargo-uv",
+            "-c",
+            "user.email=cargo-uv@example.com",
+            "commit",
+            "--allow-empty",
+            "--message",
+            "remote",
+        ],
+    );
+    run_git(&other, &["push", "origin", "main"]);
+
+    // A dry run doesn't fetch so still compares against the stale remote branch.
+    git.check_up_to_date("origin", Suppress::ALL, true).unwrap();
+    let err = git
+        .check_up_to_date("origin", Suppress::ALL, false)
+        .unwrap_err();
+    assert!(err.to_string().contains("1 commit/s behind"), "{err}");
+    assert!(
+        err.help()
+            .is_some_and(|help| help.to_string().contains("git pull origin main")),
+        "{err:?}"
+    );
+
+    std::fs::remove_dir_all(root).unwrap();
+    std::fs::remove_dir_all(remote).unwrap();
+    std::fs::remove_dir_all(other).unwrap();
+}
+
+/// Commits a version bump of `Cargo.toml` on top of [`temp_repo`].
+fn commit_version_bump(root: &Path, version: &str) {
+    std::fs::write(
+        root.join("Cargo.toml"),
+        format!("[package]\nversion = \"{version}\"\n"),
+    )
+    .unwrap();
+    run_git(root, &["add", "Cargo.toml"]);
+    run_git(root, &["commit", "--message", version]);
+}
+
+/// Amends the version bump with [`Git::commit`].
+fn commit_version_bump_amend(root: &Path, git: &Git<PathBuf>, version: &str) {
+    std::fs::write(
+        root.join("Cargo.toml"),
+        format!("[package]\nversion = \"{version}\"\n"),
+    )
+    .unwrap();
+    run_git(root, &["add", "Cargo.toml"]);
+    git.commit(version, &[], Suppress::ALL, false, false, None, true)
+        .unwrap();
+}
+
+#[test]
+fn amend_unpushed_version_bump() {
+    let (root, git) = temp_repo("amend-unpushed");
+    let remote = add_bare_remote(&root);
+    run_git(&root, &["push", "-u", "origin", "main"]);
+    commit_version_bump(&root, "0.1.1");
+    let count = run_git(&root, &["rev-list", "--count", "HEAD"]);
+
+    git.check_amend().unwrap();
+    commit_version_bump_amend(&root, &git, "0.1.2");
+    assert_eq!(run_git(&root, &["rev-list", "--count", "HEAD"]), count);
+    assert_eq!(run_git(&root, &["log", "-1", "--format=%s"]), "0.1.2\n");
+
+    std::fs::remove_dir_all(root).unwrap();
+    std::fs::remove_dir_all(remote).unwrap();
+}
+
+#[test]
+fn amend_refuses_pushed_commit() {
+    let (root, git) = temp_repo("amend-pushed");
+    let remote = add_bare_remote(&root);
+    commit_version_bump(&root, "0.1.1");
+    run_git(&root, &["push", "-u", "origin", "main"]);
+
+    let err = git.check_amend().unwrap_err();
+    assert!(err.to_string().contains("already been pushed"), "{err}");
+
+    std::fs::remove_dir_all(root).unwrap();
+    std::fs::remove_dir_all(remote).unwrap();
+}
+
+#[test]
+fn amend_refuses_non_version_bump() {
+    let (root, git) = temp_repo("amend-not-bump");
+
+    let err = git.check_amend().unwrap_err();
+    assert!(err.to_string().contains("isn't a version bump"), "{err}");
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn publish_dry_run_fails_on_dirty_tree() {
+    let root = temp_fixture_repo("simple", "publish-dirty");
+    std::fs::write(root.join("dirty.txt"), "dirty\n").unwrap();
+    let cargo = Cargo::new(Some(root.join("Cargo.toml")));
+    let package = PackageName::from("simple".to_string());
+
+    let output = cargo
+        .publish(&package, Suppress::ALL, true, true, false)
+        .unwrap()
+        .wait_with_output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    let output = cargo
+        .publish(&package, Suppress::ALL, true, true, true)
+        .unwrap()
+        .wait_with_output()
+        .unwrap();
+    assert!(output.status.success());
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn workspace_publish_in_dependency_order() {
+    let root = temp_fixture_repo("dep_order", "publish-order");
+
+    let tasks = generate_tasks(
+        &root,
+        &["patch", "--workspace", "--cargo-publish", "--dry-run"],
+    )
+    .unwrap();
+    let publishes: Vec<_> = tasks
+        .tasks()
+        .into_iter()
+        .filter_map(|t| match t {
+            Task::CargoPublish(name) => Some(name.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(publishes, vec!["a", "b"]);
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn private_members_are_bumped_but_not_published() {
+    let root = temp_fixture_repo("ws_private", "publish-private");
+    let task_names = |args: &[&str]| {
+        let tasks = generate_tasks(&root, args).unwrap();
+        let names = |bumps: bool| -> Vec<String> {
+            tasks
+                .tasks()
+                .into_iter()
+                .filter_map(|t| match t {
+                    Task::Bump { package_name, .. } if bumps => Some(package_name.to_string()),
+                    Task::CargoPublish(name) if !bumps => Some(name.to_string()),
+                    _ => None,
+                })
+                .collect()
+        };
+        (names(true), names(false))
+    };
+
+    let args = ["patch", "--workspace", "--cargo-publish", "--dry-run"];
+    let (bumps, publishes) = task_names(&args);
+    assert_eq!(bumps, ["a", "b"]);
+    assert_eq!(publishes, ["a"]);
+
+    let (bumps, publishes) = task_names(&[&args[..], &["--exclude-private"]].concat());
+    assert_eq!(bumps, ["a", "b"]);
+    assert_eq!(publishes, ["a"]);
+
+    let (bumps, publishes) = task_names(&[&args[..], &["--include-private"]].concat());
+    assert_eq!(bumps, ["a", "b"]);
+    assert_eq!(publishes, ["a", "b"]);
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn dependent_publish_starts_after_its_dependency_exits() {
+    let root = temp_fixture_repo("dep_order", "publish-after-dependency");
+    // Outside the repository, which must stay clean.
+    let shim_dir = root.with_extension("shim");
+    std::fs::create_dir_all(&shim_dir).unwrap();
+    let log = shim_dir.join("publish.log");
+    let real_cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".into());
+    std::fs::write(
+        shim_dir.join("cargo"),
+        format!(
+            r#"#!/bin/sh
+if [ "$1" = "publish" ]; then
+    prev=""
+    for arg in "$@"; do
+        [ "$prev" = "--package" ] && package="$arg"
+        prev="$arg"
+    done
+    echo "start $package" >> '{log}'
+    sleep 1
+    echo "end $package" >> '{log}'
+    exit 0
+fi
+exec '{real_cargo}' "$@"
+"#,
+            log = log.display()
+        ),
+    )
+    .unwrap();
+    Command::new("chmod")
+        .arg("+x")
+        .arg(shim_dir.join("cargo"))
+        .status()
+        .unwrap();
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    let path =
+        std::env::join_paths(std::iter::once(shim_dir.clone()).chain(std::env::split_paths(&path)))
+            .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-uv"))
+        .args(["uv", "--manifest-path"])
+        .arg(root.join("Cargo.toml"))
+        .args([
+            "patch",
+            "--workspace",
+            "--cargo-publish",
+            "--dry-run",
+            "-Q",
+            "all",
+        ])
+        .env("PATH", path)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(
+        std::fs::read_to_string(&log).unwrap(),
+        "start a\nend a\nstart b\nend b\n"
+    );
+
+    std::fs::remove_dir_all(root).unwrap();
+    std::fs::remove_dir_all(shim_dir).unwrap();
+}
+
+#[test]
+fn publish_wait_follows_each_publish() {
+    let root = temp_fixture_repo("dep_order", "publish-wait");
+
+    let tasks = generate_tasks(
+        &root,
+        &[
+            "patch",
+            "--workspace",
+            "--cargo-publish",
+            "--publish-wait",
+            "60",
+        ],
+    )
+    .unwrap();
+    let publishes: Vec<String> = tasks
+        .tasks()
+        .into_iter()
+        .filter_map(|t| match t {
+            Task::CargoPublish(_) | Task::WaitForPublish { .. } => Some(t.to_string()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        publishes,
+        vec![
+            "Cargo Publish: a",
+            "Wait For Publish: a 0.1.1",
+            "Cargo Publish: b",
+            "Wait For Publish: b 0.1.1",
+        ]
+    );
+
+    // Nothing is uploaded on a dry run so there is nothing to wait for.
+    let tasks = generate_tasks(
+        &root,
+        &[
+            "patch",
+            "--workspace",
+            "--cargo-publish",
+            "--publish-wait",
+            "60",
+            "--dry-run",
+        ],
+    )
+    .unwrap();
+    assert!(
+        !tasks
+            .tasks()
+            .iter()
+            .any(|t| matches!(t, Task::WaitForPublish { .. }))
+    );
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn dry_run_publishes_every_selected_member() {
+    let root = temp_fixture_repo("pure_ws", "publish-members");
+
+    let tasks = generate_tasks(
+        &root,
+        &["patch", "--workspace", "--cargo-publish", "--dry-run"],
+    )
+    .unwrap();
+    let publishes: Vec<_> = tasks
+        .tasks()
+        .into_iter()
+        .filter_map(|t| match t {
+            Task::CargoPublish(name) => Some(name.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(publishes, vec!["a", "b", "c"]);
+
+    let tasks = generate_tasks(
+        &root,
+        &[
+            "patch",
+            "--workspace",
+            "--exclude",
+            "b",
+            "--cargo-publish",
+            "--dry-run",
+        ],
+    )
+    .unwrap();
+    let publishes = tasks
+        .tasks()
+        .into_iter()
+        .filter(|t| matches!(t, Task::CargoPublish(_)))
+        .count();
+    assert_eq!(publishes, 2);
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn generate_lockfile_at_workspace_root_from_nested_member() {
+    let root = temp_fixture_repo("ws_version_nested", "lockfile-nested");
+    let _ = std::fs::remove_file(root.join("Cargo.lock"));
+    run_git(&root, &["add", "--all"]);
+    run_git(
+        &root,
+        &["commit", "--allow-empty", "--message", "no lockfile"],
+    );
+    let manifest = root.join("a/c/Cargo.toml");
+    let args = [
+        "cargo-uv",
+        "--manifest-path",
+        manifest.to_str().unwrap(),
+        "patch",
+        "--git-tag",
+        "--include-lockfile",
+        "--suppress",
+        "all",
+    ]
+    .map(String::from)
+    .to_vec();
+    let mut cli = Cli::cli_args(args, Some("cargo-uv"), None).unwrap();
+    let packages = Packages::from(cli.get_metadata().unwrap());
+    Tasks::generate_tasks(&cli, packages)
+        .unwrap()
+        .run_all(&cli)
+        .unwrap()
+        .join_all(&cli)
+        .unwrap();
+
+    assert!(root.join("Cargo.lock").exists());
+    assert!(!root.join("a/c/Cargo.lock").exists());
+    assert!(run_git(&root, &["status", "--porcelain"]).is_empty());
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn generate_lockfile_errors_when_missing() {
+    let root = temp_fixture_repo("ws_version_nested", "lockfile-missing");
+    // Cargo writes the lockfile at the workspace root, not next to a member.
+    let cargo = Cargo::new(Some(root.join("a/c/Cargo.toml")));
+
+    let err = cargo.generate_lockfile().unwrap_err();
+    assert!(
+        err.to_string()
+            .contains(&root.join("a/c/Cargo.lock").display().to_string()),
+        "{err}"
+    );
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn locked_release_leaves_the_lockfile() {
+    let root = temp_fixture_repo("simple", "locked-release");
+    Cargo::new(Some(root.join("Cargo.toml")))
+        .generate_lockfile()
+        .unwrap();
+    run_git(&root, &["add", "Cargo.lock"]);
+    run_git(&root, &["commit", "--allow-empty", "--message", "lockfile"]);
+    let manifest = root.join("Cargo.toml");
+    let args = [
+        "cargo-uv",
+        "--manifest-path",
+        manifest.to_str().unwrap(),
+        "patch",
+        "--git-tag",
+        "--locked",
+        "--offline",
+        "--suppress",
+        "all",
+    ]
+    .map(String::from)
+    .to_vec();
+    let mut cli = Cli::cli_args(args, Some("cargo-uv"), None).unwrap();
+    let packages = Packages::from(cli.get_metadata().unwrap());
+    let tasks = Tasks::generate_tasks(&cli, packages).unwrap();
+    assert_eq!(tasks.tasks()[0], &Task::CargoVerifyLock);
+    assert!(!tasks.tasks().contains(&&Task::CargoGenerateLock));
+    let before = std::fs::read_to_string(root.join("Cargo.lock")).unwrap();
+    tasks.run_all(&cli).unwrap().join_all(&cli).unwrap();
+
+    assert_eq!(
+        std::fs::read_to_string(root.join("Cargo.lock")).unwrap(),
+        before
+    );
+    assert!(run_git(&root, &["status", "--porcelain"]).is_empty());
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn verify_lockfile_detects_stale_lockfile() {
+    let root = temp_fixture_repo("lock_mismatch", "locked-stale");
+
+    let err = Cargo::new(Some(root.join("Cargo.toml")))
+        .with_locked(true)
+        .with_offline(true)
+        .verify_lockfile()
+        .unwrap_err();
+    assert!(err.to_string().contains("out of date"), "{err}");
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn update_dependents_sets_inline_table_and_string_requirements() {
+    let root = temp_fixture_repo("dependents", "update-dependents");
+    let manifest = root.join("Cargo.toml");
+    let args = [
+        "cargo-uv",
+        "--manifest-path",
+        manifest.to_str().unwrap(),
+        "set",
+        "0.2.0",
+        "--package",
+        "a",
+        "--update-dependents",
+        "--suppress",
+        "all",
+    ]
+    .map(String::from)
+    .to_vec();
+    let mut cli = Cli::cli_args(args, Some("cargo-uv"), None).unwrap();
+    let packages = Packages::from(cli.get_metadata().unwrap());
+    let tasks = Tasks::generate_tasks(&cli, packages).unwrap();
+    tasks.run_all(&cli).unwrap().join_all(&cli).unwrap();
+
+    let b = std::fs::read_to_string(root.join("b/Cargo.toml")).unwrap();
+    assert!(
+        b.contains(r#"a = { path = "../a", version = "0.2.0" } # Keep this comment"#),
+        "{b}"
+    );
+    let c = std::fs::read_to_string(root.join("c/Cargo.toml")).unwrap();
+    assert!(c.contains(r#"a = "=0.2.0""#), "{c}");
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn update_dependents_is_opt_in() {
+    let root = temp_fixture_repo("dependents", "update-dependents-opt-in");
+
+    let tasks = generate_tasks(&root, &["set", "0.2.0", "--package", "a", "--dry-run"]).unwrap();
+    assert!(
+        !tasks
+            .tasks()
+            .iter()
+            .any(|task| matches!(task, Task::UpdateDependents { .. }))
+    );
+
+    let tasks = generate_tasks(
+        &root,
+        &[
+            "set",
+            "0.2.0",
+            "--package",
+            "a",
+            "--update-dependents",
+            "--git-tag",
+            "--dry-run",
+        ],
+    )
+    .unwrap();
+    let added = tasks
+        .tasks()
+        .into_iter()
+        .find_map(|task| match task {
+            Task::GitAdd(paths) => Some(paths.clone()),
+            _ => None,
+        })
+        .unwrap();
+    assert!(added.iter().any(|path| path.ends_with("b/Cargo.toml")));
+    assert!(added.iter().any(|path| path.ends_with("c/Cargo.toml")));
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn bumping_a_patched_crate_still_generates_tasks() {
+    let root = temp_fixture_repo("patched", "patched-bump");
+
+    let tasks = generate_tasks(&root, &["patch", "--package", "a", "--dry-run"]).unwrap();
+    assert!(tasks.tasks().iter().any(
+        |task| matches!(task, Task::Bump { package_name, .. } if package_name.as_str() == "a")
+    ));
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn failed_publish_rolls_back_manifest() {
+    let root = temp_fixture_repo("simple", "rollback");
+    let manifest = std::fs::read_to_string(root.join("Cargo.toml")).unwrap();
+    // Fails as the bumped manifest is uncommitted, offline so nothing is uploaded.
+    let args = ["patch", "--cargo-publish", "--offline", "--suppress", "all"];
+
+    let cli = cli_args(&root, &args);
+    let tasks = generate_tasks(&root, &args).unwrap();
+    let result = tasks.run_all(&cli).unwrap().join_all(&cli);
+    assert!(result.is_err());
+    assert_eq!(
+        std::fs::read_to_string(root.join("Cargo.toml")).unwrap(),
+        manifest
+    );
+
+    let args = [&args[..], &["--no-rollback"]].concat();
+    let cli = cli_args(&root, &args);
+    let tasks = generate_tasks(&root, &args).unwrap();
+    assert!(tasks.run_all(&cli).unwrap().join_all(&cli).is_err());
+    let bumped = std::fs::read_to_string(root.join("Cargo.toml")).unwrap();
+    assert!(bumped.contains("version = \"0.1.12\""), "{bumped}");
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn virtual_workspace_defaults_to_default_members() {
+    let root = temp_fixture_repo("pure_ws_default", "default-members");
+
+    let tasks = generate_tasks(&root, &["patch", "--dry-run"]).unwrap();
+    let bumped: Vec<&str> = tasks
+        .tasks()
+        .into_iter()
+        .filter_map(|task| match task {
+            Task::Bump { package_name, .. } => Some(package_name.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(bumped, vec!["a"]);
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn lockstep_sets_every_member_to_one_version() {
+    let root = temp_fixture_repo("lockstep", "lockstep");
+    let args = [
+        "set",
+        "2.0.0",
+        "--workspace",
+        "--lockstep",
+        "--suppress",
+        "all",
+    ];
+
+    let cli = cli_args(&root, &args);
+    let tasks = generate_tasks(&root, &args).unwrap();
+    assert_eq!(tasks.root_version().unwrap(), semver::Version::new(2, 0, 0));
+    tasks.run_all(&cli).unwrap().join_all(&cli).unwrap();
+
+    for manifest in ["Cargo.toml", "a/Cargo.toml", "b/Cargo.toml"] {
+        let contents = std::fs::read_to_string(root.join(manifest)).unwrap();
+        assert!(
+            contents.contains("version = \"2.0.0\""),
+            "{manifest}: {contents}"
+        );
+    }
+    let c = std::fs::read_to_string(root.join("c/Cargo.toml")).unwrap();
+    assert!(c.contains("version.workspace = true"), "{c}");
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn lockstep_bumps_from_the_highest_version() {
+    let root = temp_fixture_repo("lockstep", "lockstep-bump");
+
+    let tasks =
+        generate_tasks(&root, &["minor", "--workspace", "--lockstep", "--dry-run"]).unwrap();
+    let new_versions: Vec<semver::Version> = tasks
+        .tasks()
+        .into_iter()
+        .filter_map(|task| match task {
+            Task::Bump { new_version, .. } | Task::BumpWorkspace { new_version, .. } => {
+                Some(new_version.clone())
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(new_versions.len(), 3);
+    assert!(
+        new_versions
+            .iter()
+            .all(|v| *v == semver::Version::new(1, 3, 0))
+    );
+    assert_eq!(tasks.root_version().unwrap(), semver::Version::new(1, 3, 0));
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn task_generation_is_reproducible() {
+    let root = temp_fixture_repo("pure_ws", "reproducible");
+    let args = ["patch", "--workspace", "--git-tag", "--dry-run"];
+
+    let first = generate_tasks(&root, &args).unwrap().tasks_owned();
+    let second = generate_tasks(&root, &args).unwrap().tasks_owned();
+    assert_eq!(first, second);
+    let bumped: Vec<&str> = first
+        .iter()
+        .filter_map(|task| match task {
+            Task::Bump { package_name, .. } => Some(package_name.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(bumped, vec!["a", "b", "c"]);
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn dry_run_plan_serializes_to_json() {
+    let root = temp_fixture_repo("simple", "plan-json");
+    let remote = add_bare_remote(&root);
+
+    let tasks = generate_tasks(
+        &root,
+        &[
+            "patch",
+            "--git-tag",
+            "--git-push",
+            "--remote",
+            "origin",
+            "--dry-run",
+        ],
+    )
+    .unwrap();
+    let plan = tasks.dry_run_plan();
+    assert!(!plan.iter().any(Task::is_run_after_completed));
+    let json = serde_json::to_string(&plan).unwrap();
+    for expected in [
+        r#"{"Bump":{"package_name":"simple","bump":"patch","new_version":"0.1.12"}}"#,
+        r#"{"GitTag":"0.1.12"}"#,
+        r#"{"GitPush":{"remote":"origin","#,
+        r#""tag":"0.1.12"}}"#,
+    ] {
+        assert!(json.contains(expected), "{expected} not in {json}");
+    }
+
+    std::fs::remove_dir_all(root).unwrap();
+    std::fs::remove_dir_all(remote).unwrap();
+}
+
+#[test]
+fn pushes_to_every_remote_are_spawned_before_joining() {
+    let root = temp_fixture_repo("simple", "push-stages");
+    let origin = add_bare_remote(&root);
+    let mirror = root.with_extension("mirror.git");
+    let _ = std::fs::remove_dir_all(&mirror);
+    run_git(&root, &["init", "--bare", mirror.to_str().unwrap()]);
+    run_git(
+        &root,
+        &["remote", "add", "mirror", mirror.to_str().unwrap()],
+    );
+    run_git(&root, &["push", "origin", "main"]);
+    run_git(&root, &["push", "mirror", "main"]);
+    let args = ["patch", "--git-tag", "--git-push", "--suppress", "all"];
+
+    let cli = cli_args(&root, &args);
+    let tasks = generate_tasks(&root, &args).unwrap().run_all(&cli).unwrap();
+    let mut spawned: Vec<&str> = tasks
+        .spawned_tasks()
+        .into_iter()
+        .filter_map(|task| match task {
+            Task::GitPush { remote, .. } => Some(remote.as_str()),
+            _ => None,
+        })
+        .collect();
+    spawned.sort();
+    assert_eq!(spawned, vec!["mirror", "origin"]);
+
+    tasks.join_all(&cli).unwrap();
+    for remote in [&origin, &mirror] {
+        assert_eq!(run_git(remote, &["tag", "--list"]), "0.1.12\n");
+    }
+
+    std::fs::remove_dir_all(root).unwrap();
+    std::fs::remove_dir_all(origin).unwrap();
+    std::fs::remove_dir_all(mirror).unwrap();
+}
+
+#[test]
+fn hooks_run_before_and_after_the_release() {
+    let root = temp_fixture_repo("simple", "hooks");
+    let args = [
+        "patch",
+        "--pre-hook",
+        "grep '^version' Cargo.toml > hooks.log",
+        "--post-hook",
+        "grep '^version' Cargo.toml >> hooks.log",
+        "--suppress",
+        "all",
+    ];
+
+    let cli = cli_args(&root, &args);
+    let tasks = generate_tasks(&root, &args).unwrap();
+    tasks.run_all(&cli).unwrap().join_all(&cli).unwrap();
+    assert_eq!(
+        std::fs::read_to_string(root.join("hooks.log")).unwrap(),
+        "version = \"0.1.11\"\nversion = \"0.1.12\"\n"
+    );
+
+    let args = [
+        "patch",
+        "--dry-run",
+        "--allow-dirty",
+        "--pre-hook",
+        "touch dry-run.log",
+    ];
+    let cli = cli_args(&root, &args);
+    let tasks = generate_tasks(&root, &args).unwrap();
+    tasks.run_all(&cli).unwrap();
+    assert!(!root.join("dry-run.log").exists());
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn message_template_names_commit_and_tag() {
+    let root = temp_fixture_repo("simple", "message-template");
+    let args = [
+        "patch",
+        "--git-tag",
+        "--annotate",
+        "--message-template",
+        "chore(release): {name} {version} (was {previous}) {unknown}",
+        "--suppress",
+        "all",
+    ];
+
+    let cli = cli_args(&root, &args);
+    let tasks = generate_tasks(&root, &args).unwrap();
+    tasks.run_all(&cli).unwrap().join_all(&cli).unwrap();
+    let message = "chore(release): simple 0.1.12 (was 0.1.11) {unknown}\n";
+    assert_eq!(run_git(&root, &["log", "-1", "--format=%s"]), message);
+    assert_eq!(
+        run_git(&root, &["tag", "--list", "--format=%(subject)", "0.1.12"]),
+        message
+    );
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn run_report_records_bump_and_tag() {
+    let root = temp_fixture_repo("simple", "run-report");
+    let args = ["patch", "--git-tag", "--suppress", "all"];
+
+    let cli = cli_args(&root, &args);
+    let tasks = generate_tasks(&root, &args).unwrap();
+    let tasks = tasks.run_all(&cli).unwrap().join_all(&cli).unwrap();
+    // Compared whole so internal state can't leak into the public report.
+    let expected = RunReport {
+        version: Some(semver::Version::new(0, 1, 12)),
+        commit: Some(run_git(&root, &["rev-parse", "HEAD"]).trim().to_string()),
+        tag: Some("0.1.12".to_string()),
+        pushed_remotes: Vec::new(),
+        published: false,
+    };
+    assert_eq!(tasks.report(), &expected);
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn changelog_lists_commits_since_the_last_tag() {
+    let root = temp_fixture_repo("simple", "changelog");
+    std::fs::write(
+        root.join("CHANGELOG.md"),
+        "# Changelog\n\n## [0.1.11] - 2024-01-01\n\n- fixture\n",
+    )
+    .unwrap();
+    run_git(&root, &["add", "CHANGELOG.md"]);
+    run_git(&root, &["commit", "--message", "docs: changelog"]);
+    run_git(&root, &["tag", "0.1.11"]);
+    for subject in ["feat: one", "fix: two"] {
+        run_git(&root, &["commit", "--allow-empty", "--message", subject]);
+    }
+    let args = [
+        "patch",
+        "--git-tag",
+        "--changelog",
+        "CHANGELOG.md",
+        "--suppress",
+        "all",
+    ];
+
+    let cli = cli_args(&root, &args);
+    let tasks = generate_tasks(&root, &args).unwrap();
+    tasks.run_all(&cli).unwrap().join_all(&cli).unwrap();
+    let changelog = std::fs::read_to_string(root.join("CHANGELOG.md")).unwrap();
+    let (heading, rest) = changelog
+        .strip_prefix("# Changelog\n\n## [0.1.12] - ")
+        .and_then(|rest| rest.split_once('\n'))
+        .unwrap_or_else(|| panic!("{changelog}"));
+    assert_eq!(heading.len(), "YYYY-MM-DD".len(), "{changelog}");
+    assert_eq!(
+        rest,
+        "\n- fix: two\n- feat: one\n\n## [0.1.11] - 2024-01-01\n\n- fixture\n"
+    );
+    assert_eq!(run_git(&root, &["status", "--porcelain"]), "");
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn first_release_of_an_untagged_repo() {
+    let root = temp_fixture_repo("simple", "first-release");
+    // Without a remote the branch can't be behind, so the check is skipped.
+    let args = [
+        "set",
+        "0.1.11",
+        "--first-release",
+        "--git-tag",
+        "--require-up-to-date",
+        "--suppress",
+        "all",
+    ];
+
+    let cli = cli_args(&root, &args);
+    let tasks = generate_tasks(&root, &args).unwrap();
+    assert!(
+        !tasks
+            .tasks()
+            .iter()
+            .any(|t| matches!(t, Task::GitCommit(_) | Task::GitUpToDate(_))),
+        "{tasks:?}"
+    );
+    tasks.run_all(&cli).unwrap().join_all(&cli).unwrap();
+    assert_eq!(
+        run_git(&root, &["tag", "--points-at", "HEAD"]).trim(),
+        "0.1.11"
+    );
+
+    let err = generate_tasks(&root, &args).unwrap_err();
+    assert!(err.to_string().contains("'--first-release'"), "{err}");
+    std::fs::remove_dir_all(root).unwrap();
+
+    // The changelog lists every commit as there is no previous tag.
+    let root = temp_fixture_repo("simple", "first-release-changelog");
+    run_git(
+        &root,
+        &["commit", "--allow-empty", "--message", "feat: one"],
+    );
+    let args = [
+        "set",
+        "0.2.0",
+        "--first-release",
+        "--git-tag",
+        "--changelog",
+        "CHANGELOG.md",
+        "--suppress",
+        "all",
+    ];
+    let cli = cli_args(&root, &args);
+    let tasks = generate_tasks(&root, &args).unwrap();
+    tasks.run_all(&cli).unwrap().join_all(&cli).unwrap();
+    let changelog = std::fs::read_to_string(root.join("CHANGELOG.md")).unwrap();
+    assert!(
+        changelog.ends_with("\n- feat: one\n- fixture\n- initial\n\n"),
+        "{changelog}"
+    );
+    assert_eq!(run_git(&root, &["status", "--porcelain"]), "");
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn failing_hook_stops_the_release() {
+    let root = temp_fixture_repo("simple", "hook-fails");
+    let manifest = std::fs::read_to_string(root.join("Cargo.toml")).unwrap();
+    let args = ["patch", "--pre-hook", "exit 3", "--suppress", "all"];
+
+    let cli = cli_args(&root, &args);
+    let tasks = generate_tasks(&root, &args).unwrap();
+    let err = tasks.run_all(&cli).unwrap_err();
+    assert!(
+        format!("{err:?}").contains("Hook `exit 3` exited with code 3"),
+        "{err:?}"
+    );
+    assert_eq!(
+        std::fs::read_to_string(root.join("Cargo.toml")).unwrap(),
+        manifest
+    );
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn setting_the_current_version_skips_the_release() {
+    let root = temp_fixture_repo("simple", "unchanged");
+    let args = ["set", "0.1.11", "--git-tag", "--cargo-publish"];
+
+    let tasks = generate_tasks(&root, &args).unwrap();
+    assert_eq!(
+        tasks.tasks_owned(),
+        vec![Task::DisplayVersion("simple".into())]
+    );
+
+    let args = [&args[..], &["--force-version"]].concat();
+    let tasks = generate_tasks(&root, &args).unwrap().tasks_owned();
+    assert!(tasks.iter().any(|t| t.is_git_commit()), "{tasks:?}");
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn dry_run_verifies_the_repository_is_unchanged() {
+    let root = temp_fixture_repo("simple", "verify-clean");
+    std::fs::write(root.join("notes.txt"), "untracked").unwrap();
+    let args = ["patch", "--dry-run", "--allow-dirty", "--git-tag"];
+
+    let tasks = generate_tasks(&root, &args).unwrap();
+    let verify = Task::VerifyClean {
+        dirty_files: vec!["notes.txt".into()],
+        tag: Some("0.1.12".into()),
+    };
+    assert_eq!(tasks.tasks_owned().last(), Some(&verify));
+
+    let mut cli = cli_args(&root, &args);
+    let mut tasks = Tasks::new(Packages::from(cli.get_metadata().unwrap()));
+    tasks.insert(verify, None);
+    let tasks = tasks.run_cleanup_tasks(&cli).unwrap();
+
+    // The dry run left its tag behind.
+    run_git(&root, &["tag", "0.1.12"]);
+    let err = tasks.run_cleanup_tasks(&cli).unwrap_err();
+    assert!(err.to_string().contains("tag 0.1.12 still exists"), "{err}");
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn generated_plan_stages_are_monotonic() {
+    let root = temp_fixture_repo("dep_order", "stages");
+    let remote = add_bare_remote(&root);
+    let args = [
+        "patch",
+        "--workspace",
+        "--git-tag",
+        "--git-push",
+        "--remote",
+        "origin",
+        "--cargo-publish",
+        "--publish-wait",
+        "10",
+        "--pre-hook",
+        "true",
+        "--post-hook",
+        "true",
+        "--dry-run",
+    ];
+
+    let tasks = generate_tasks(&root, &args).unwrap().tasks_owned();
+    assert!(
+        tasks
+            .windows(2)
+            .all(|pair| pair[0].stage() <= pair[1].stage()),
+        "{tasks:#?}"
+    );
+    assert_eq!(tasks.first().map(Task::stage), Some(Stage::Prepare));
+    assert_eq!(tasks.last().map(Task::stage), Some(Stage::Cleanup));
+
+    std::fs::remove_dir_all(root).unwrap();
+    std::fs::remove_dir_all(remote).unwrap();
+}
+
+/// [`temp_fixture_repo`] with the `release` branch moved to the fixture commit.
+fn temp_release_branch_repo(name: &str) -> PathBuf {
+    let root = temp_fixture_repo("simple", name);
+    run_git(&root, &["branch", "--force", "release"]);
+    root
+}
+
+/// Runs the tasks the same as the binary.
+fn run_release(root: &Path, args: &[&str]) -> Tasks {
+    let cli = cli_args(root, args);
+    let tasks = generate_tasks(root, args).unwrap();
+    tasks
+        .run_all(&cli)
+        .unwrap()
+        .join_all(&cli)
+        .unwrap()
+        .run_cleanup_tasks(&cli)
+        .unwrap()
+}
+
+#[test]
+fn branch_release_on_clean_tree() {
+    let root = temp_release_branch_repo("branch-clean");
+
+    let tasks = run_release(
+        &root,
+        &["patch", "--git-tag", "--branch", "release", "-Q", "all"],
+    );
+    assert!(
+        !tasks
+            .tasks()
+            .iter()
+            .any(|t| matches!(t, Task::GitStash { .. }))
+    );
+    assert_eq!(run_git(&root, &["branch", "--show-current"]), "main\n");
+    assert!(
+        run_git(&root, &["show", "release:Cargo.toml"]).contains("version = \"0.1.12\""),
+        "release not bumped"
+    );
+    assert!(
+        std::fs::read_to_string(root.join("Cargo.toml"))
+            .unwrap()
+            .contains("version = \"0.1.11\"")
+    );
+    assert_eq!(
+        run_git(&root, &["rev-parse", "0.1.12^{commit}"]),
+        run_git(&root, &["rev-parse", "release"])
+    );
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn branch_release_stashes_dirty_tree() {
+    let root = temp_release_branch_repo("branch-dirty");
+    std::fs::write(root.join("file.txt"), "work in progress\n").unwrap();
+
+    let tasks = run_release(
+        &root,
+        &[
+            "patch",
+            "--git-tag",
+            "--branch",
+            "release",
+            "--allow-dirty",
+            "-Q",
+            "all",
+        ],
+    );
+    assert!(
+        tasks
+            .tasks()
+            .iter()
+            .any(|t| matches!(t, Task::GitStash { .. }))
+    );
+    assert_eq!(run_git(&root, &["branch", "--show-current"]), "main\n");
+    assert_eq!(
+        std::fs::read_to_string(root.join("file.txt")).unwrap(),
+        "work in progress\n"
+    );
+    assert_eq!(run_git(&root, &["stash", "list"]), "");
+    assert_eq!(run_git(&root, &["show", "release:file.txt"]), "main\n");
+    assert!(run_git(&root, &["show", "release:Cargo.toml"]).contains("version = \"0.1.12\""));
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn branch_release_to_missing_branch_errors() {
+    let root = temp_release_branch_repo("branch-missing");
+    let head = run_git(&root, &["rev-parse", "HEAD"]);
+
+    let err = generate_tasks(&root, &["patch", "--git-tag", "--branch", "missing"]).unwrap_err();
+    assert!(
+        matches!(
+            err.downcast_ref::<GitError>(),
+            Some(GitError::BranchNotFound(branch)) if branch == "missing"
+        ),
+        "{err}"
+    );
+    assert_eq!(run_git(&root, &["branch", "--show-current"]), "main\n");
+    assert_eq!(run_git(&root, &["rev-parse", "HEAD"]), head);
+    assert_eq!(run_git(&root, &["status", "--porcelain"]), "");
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn git_root_for_a_nested_manifest() {
+    let (root, _) = temp_repo("git-root");
+    let nested = root.join("crates/simple");
+    copy_dir(
+        &Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/simple"),
+        &nested,
+    );
+    run_git(&root, &["add", "."]);
+    run_git(&root, &["commit", "--message", "fixture"]);
+
+    let git_root = root.to_str().unwrap();
+    let args = ["patch", "--git-tag", "--git-root", git_root, "-Q", "all"];
+    let cli = cli_args(&nested, &args);
+    assert_eq!(cli.git().unwrap().root_directory(), root);
+    let tasks = generate_tasks(&nested, &args).unwrap();
+    tasks.run_all(&cli).unwrap().join_all(&cli).unwrap();
+
+    assert_eq!(
+        run_git(&root, &["show", "--name-only", "--format=", "HEAD"]),
+        "crates/simple/Cargo.lock\ncrates/simple/Cargo.toml\n"
+    );
+    assert_eq!(run_git(&root, &["tag", "--list"]), "0.1.12\n");
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn commit_paths_leaves_other_staged_files() {
+    let root = temp_fixture_repo("simple", "commit-paths");
+    std::fs::write(root.join("notes.txt"), "unrelated\n").unwrap();
+    run_git(&root, &["add", "notes.txt"]);
+
+    let args = [
+        "patch",
+        "--git-tag",
+        "--commit-paths",
+        "--allow-dirty",
+        "-Q",
+        "all",
+    ];
+    let cli = cli_args(&root, &args);
+    let tasks = generate_tasks(&root, &args).unwrap();
+    tasks.run_all(&cli).unwrap().join_all(&cli).unwrap();
+
+    let committed = run_git(&root, &["show", "--name-only", "--format=", "HEAD"]);
+    assert!(committed.contains("Cargo.toml"), "{committed}");
+    assert!(!committed.contains("notes.txt"), "{committed}");
+    assert_eq!(
+        run_git(&root, &["diff", "--cached", "--name-only"]),
+        "notes.txt\n"
+    );
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn allow_dirty_reports_staged_and_unstaged_changes() {
+    let root = temp_fixture_repo("simple", "staged-changes");
+    let cli = cli_args(&root, &["patch"]);
+    let manifest = std::fs::read_to_string(root.join("Cargo.toml")).unwrap();
+
+    std::fs::write(root.join("notes.txt"), "unrelated\n").unwrap();
+    run_git(&root, &["add", "notes.txt"]);
+    let err = cli.try_allow_dirty().unwrap_err().to_string();
+    assert!(err.starts_with("1 file/s have staged changes"), "{err}");
+    assert!(!err.contains("working directory"), "{err}");
+
+    std::fs::write(root.join("Cargo.toml"), format!("{manifest}\n")).unwrap();
+    let err = cli.try_allow_dirty().unwrap_err().to_string();
+    assert!(err.contains("1 file/s have staged changes"), "{err}");
+    assert!(err.contains("1 file/s in the working directory"), "{err}");
+
+    run_git(&root, &["restore", "--staged", "notes.txt"]);
+    std::fs::remove_file(root.join("notes.txt")).unwrap();
+    let err = cli.try_allow_dirty().unwrap_err().to_string();
+    assert!(
+        err.starts_with("1 file/s in the working directory"),
+        "{err}"
+    );
+    assert!(!err.contains("staged"), "{err}");
+
+    assert!(
+        cli_args(&root, &["patch", "--allow-dirty"])
+            .try_allow_dirty()
+            .is_ok()
+    );
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn run_releases_a_temp_project() {
+    let root = temp_fixture_repo("simple", "lib-run");
+    let cli = cli_args(&root, &["minor", "--git-tag", "-Q", "all"]);
+
+    let report = cargo_uv::run(cli).unwrap();
+    assert_eq!(report.version, Some(semver::Version::new(0, 2, 0)));
+    assert_eq!(report.tag.as_deref(), Some("0.2.0"));
+    assert_eq!(
+        report.commit,
+        Some(run_git(&root, &["rev-parse", "HEAD"]).trim().to_string())
+    );
+    assert!(!report.published);
+    assert!(report.pushed_remotes.is_empty());
+
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn lockfile_is_committed_for_binaries_only() {
+    let root = temp_fixture_repo("simple", "lockfile-bin");
+    let args = ["patch", "--git-tag", "-Q", "all"];
+    let tasks = generate_tasks(&root, &args).unwrap();
+    assert!(tasks.tasks_owned().contains(&Task::CargoGenerateLock));
+    tasks
+        .run_all(&cli_args(&root, &args))
+        .unwrap()
+        .join_all(&cli_args(&root, &args))
+        .unwrap();
+    let committed = run_git(&root, &["show", "--name-only", "--format=", "HEAD"]);
+    assert!(committed.contains("Cargo.lock"), "{committed}");
+    std::fs::remove_dir_all(root).unwrap();
+
+    let root = temp_fixture_repo("simple", "lockfile-lib");
+    run_git(&root, &["mv", "src/main.rs", "src/lib.rs"]);
+    run_git(&root, &["commit", "--message", "library"]);
+    let tasks = generate_tasks(&root, &args).unwrap();
+    assert!(!tasks.tasks_owned().contains(&Task::CargoGenerateLock));
+    tasks
+        .run_all(&cli_args(&root, &args))
+        .unwrap()
+        .join_all(&cli_args(&root, &args))
+        .unwrap();
+    let committed = run_git(&root, &["show", "--name-only", "--format=", "HEAD"]);
+    assert_eq!(committed, "Cargo.toml\n");
+
+    let args = [&args[..], &["--include-lockfile"]].concat();
+    let tasks = generate_tasks(&root, &args).unwrap();
+    assert!(tasks.tasks_owned().contains(&Task::CargoGenerateLock));
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn cargo_metadata_runs_once_per_release() {
+    let root = temp_fixture_repo("simple", "metadata-once");
+    // Outside the repository, which must stay clean.
+    let shim_dir = root.with_extension("shim");
+    std::fs::create_dir_all(&shim_dir).unwrap();
+    let log = shim_dir.join("cargo.log");
+    let shim = shim_dir.join("cargo");
+    let real_cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".into());
+    std::fs::write(
+        &shim,
+        format!(
+            "#!/bin/sh\necho \"$1\" >> '{}'\nexec '{real_cargo}' \"$@\"\n",
+            log.display()
+        ),
+    )
+    .unwrap();
+    Command::new("chmod").arg("+x").arg(&shim).status().unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cargo-uv"))
+        .args(["uv", "--manifest-path"])
+        .arg(root.join("Cargo.toml"))
+        .args(["patch", "--git-tag", "-Q", "all"])
+        .env("CARGO", &shim)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    let calls = std::fs::read_to_string(&log).unwrap();
+    assert_eq!(
+        calls.lines().filter(|&c| c == "metadata").count(),
+        1,
+        "{calls}"
+    );
+
+    std::fs::remove_dir_all(root).unwrap();
+    std::fs::remove_dir_all(shim_dir).unwrap();
+}
+
+#[test]
+fn dirty_allow_ignores_matching_files_only() {
+    let root = temp_fixture_repo("simple", "dirty-allow");
+    std::fs::create_dir_all(root.join("docs")).unwrap();
+    std::fs::write(root.join("docs/generated.md"), "generated\n").unwrap();
+
+    let allow = ["patch", "--dirty-allow", "docs/*.md"];
+    assert!(cli_args(&root, &allow).try_allow_dirty().is_ok());
+
+    std::fs::write(root.join("notes.txt"), "unrelated\n").unwrap();
+    let err = cli_args(&root, &allow)
+        .try_allow_dirty()
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.starts_with("1 file/s in the working directory"),
+        "{err}"
+    );
+    assert!(err.contains("notes.txt"), "{err}");
+    assert!(!err.contains("generated.md"), "{err}");
+
+    std::fs::remove_dir_all(root).unwrap();
+}
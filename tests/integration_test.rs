@@ -1,5 +1,8 @@
 use cargo_metadata::MetadataCommand;
-use cargo_uv::Packages;
+use cargo_uv::{
+    CargoFile, Cli, ItemType, PackageError, Packages, Suppress, VersionLocation,
+    VersionLocationErrorKind, VersionType,
+};
 
 fn fixture(relative: &str) -> std::path::PathBuf {
     std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
@@ -7,6 +10,15 @@ fn fixture(relative: &str) -> std::path::PathBuf {
         .join(relative)
 }
 
+/// Writes `contents` to a `Cargo.toml` in a new temp dir named `name`.
+fn temp_manifest(name: &str, contents: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("cargo-uv-{name}-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let manifest = dir.join("Cargo.toml");
+    std::fs::write(&manifest, contents).unwrap();
+    manifest
+}
+
 fn packages_from(manifest: &str) -> Packages {
     let metadata = MetadataCommand::new()
         .manifest_path(fixture(manifest))
@@ -85,3 +97,637 @@ fn display_tree_does_not_panic() {
         assert!(!tree.is_empty(), "display_tree empty for {manifest}");
     }
 }
+
+#[test]
+fn verify_version_matches_manifests_and_lockfile() {
+    let packages = packages_from("ws_version/Cargo.toml");
+    for name in ["a", "b", "c", "workspace.package"] {
+        let version = packages.verify_version(&name.into()).unwrap();
+        assert_eq!(version.to_string(), "0.2.5-rc.4");
+    }
+}
+
+#[test]
+fn verify_version_detects_stale_lockfile() {
+    // no_deps so cargo does not regenerate the stale lockfile.
+    let metadata = MetadataCommand::new()
+        .manifest_path(fixture("lock_mismatch/Cargo.toml"))
+        .no_deps()
+        .exec()
+        .unwrap();
+    let packages = Packages::from(&metadata);
+    assert!(packages.verify_version(&"b".into()).is_ok());
+    let err = packages.verify_version(&"a".into()).unwrap_err();
+    assert!(
+        err.to_string().contains("expected 0.3.0, found 0.2.0"),
+        "{err}"
+    );
+}
+
+#[test]
+fn publish_order_puts_dependencies_first() {
+    let packages = packages_from("dep_order/Cargo.toml");
+    let order = packages.publish_order(&["b".into(), "a".into()]).unwrap();
+    let order: Vec<&str> = order.iter().map(|name| name.as_str()).collect();
+    assert_eq!(order, vec!["a", "b"]);
+}
+
+#[test]
+fn dependency_graph_orders_a_linear_chain() {
+    let packages = packages_from("dep_chain/Cargo.toml");
+    let order = packages.dependency_graph().unwrap();
+    let order: Vec<&str> = order.iter().map(|name| name.as_str()).collect();
+    assert_eq!(order, vec!["c", "b", "a"]);
+}
+
+#[test]
+fn dependency_graph_errors_on_a_cycle() {
+    // Cargo refuses to resolve a cycle, the CLI reads metadata without resolving.
+    let metadata = MetadataCommand::new()
+        .manifest_path(fixture("dep_cycle/Cargo.toml"))
+        .no_deps()
+        .exec()
+        .unwrap();
+    let packages = Packages::from(&metadata);
+    let err = packages.dependency_graph().unwrap_err();
+    let PackageError::DependencyCycle(names) = err else {
+        panic!("expected a dependency cycle, got {err:?}");
+    };
+    let names: Vec<&str> = names.iter().map(|name| name.as_str()).collect();
+    assert_eq!(names, vec!["a", "b"]);
+}
+
+#[test]
+fn display_tree_labels_the_version_source() {
+    let tree = packages_from("ws_inherit/Cargo.toml").display_tree();
+    assert!(tree.contains("├─ a 0.3.0 (inherited): ./a"), "{tree}");
+    assert!(tree.contains("└─ b 0.1.0 (package): ./b"), "{tree}");
+}
+
+#[test]
+fn ws_inherit_classifies_inherited_package_versions() {
+    let packages = packages_from("ws_inherit/Cargo.toml");
+    let a = packages.get_package(&"a".into()).unwrap();
+    assert_eq!(a.version_type(), VersionType::SetByWorkspace);
+    assert_eq!(a.version().to_string(), "0.3.0");
+    // Inheriting a dependency doesn't inherit the package version.
+    let b = packages.get_package(&"b".into()).unwrap();
+    assert_eq!(b.version_type(), VersionType::Package);
+}
+
+#[test]
+fn ws_inherit_refuses_literal_version_for_inherited() {
+    let mut packages = packages_from("ws_inherit/Cargo.toml");
+    let manifest = fixture("ws_inherit/a/Cargo.toml");
+    let contents = std::fs::read_to_string(&manifest).unwrap();
+
+    assert!(
+        packages
+            .set_package_version(&"a".into(), "0.4.0".parse().unwrap())
+            .is_err()
+    );
+    assert!(packages.write_cargo_file(&"a".into()).is_err());
+    assert_eq!(std::fs::read_to_string(&manifest).unwrap(), contents);
+}
+
+#[test]
+fn set_version_keeps_version_line_comment() {
+    let contents = "[package]\nname = \"pinned\"\nversion   =   \"1.2.3\"   # pinned\nedition = \"2024\"\n\n[workspace.package]\nversion = \"1.2.3\" # shared\n";
+    let manifest = temp_manifest("version-comment", contents);
+
+    let mut cargo_file = CargoFile::new(&manifest).unwrap();
+    cargo_file
+        .set_package_version(&"1.2.4".parse().unwrap())
+        .unwrap();
+    cargo_file
+        .set_workspace_version(&"1.3.0".parse().unwrap())
+        .unwrap();
+    cargo_file.write_cargo_file().unwrap();
+
+    let expected = "[package]\nname = \"pinned\"\nversion   =   \"1.2.4\"   # pinned\nedition = \"2024\"\n\n[workspace.package]\nversion = \"1.3.0\" # shared\n";
+    assert_eq!(std::fs::read_to_string(&manifest).unwrap(), expected);
+
+    std::fs::remove_dir_all(manifest.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn get_all_versions_reports_mixed_dependency_styles() {
+    let contents = r#"[package]
+name = "mixed"
+version = "1.2.3"
+
+[workspace.package]
+version = "2.0.0"
+
+[dependencies]
+string = "0.4"
+inline = { version = "=1.0.1", features = ["std"] }
+path_only = { path = "../path_only" }
+inherited = { workspace = true }
+
+[dependencies.table]
+version = "~3.1.0-rc.1"
+
+[dev-dependencies]
+dev = "^0.2.1"
+"#;
+    let manifest = temp_manifest("all-versions", contents);
+
+    let versions: Vec<(String, String)> = CargoFile::new(&manifest)
+        .unwrap()
+        .get_all_versions()
+        .into_iter()
+        .map(|(key, version)| (key, version.to_string()))
+        .collect();
+    let expected = [
+        ("package.version", "1.2.3"),
+        ("workspace.package.version", "2.0.0"),
+        ("dependencies.string", "0.4.0"),
+        ("dependencies.inline.version", "1.0.1"),
+        ("dependencies.table.version", "3.1.0-rc.1"),
+        ("dev-dependencies.dev", "0.2.1"),
+    ]
+    .map(|(key, version)| (key.to_string(), version.to_string()));
+    assert_eq!(versions, expected);
+
+    std::fs::remove_dir_all(manifest.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn get_patched_packages_reads_patch_sections() {
+    let patched = CargoFile::new(fixture("patched/Cargo.toml"))
+        .unwrap()
+        .get_patched_packages();
+    let patched: Vec<(&str, &str)> = patched
+        .iter()
+        .map(|(section, name)| (section.as_str(), name.as_str()))
+        .collect();
+    assert_eq!(
+        patched,
+        vec![
+            ("patch.crates-io", "a"),
+            ("patch.https://github.com/example/b", "b"),
+        ]
+    );
+}
+
+#[test]
+fn get_patched_packages_reads_replace_specs() {
+    let contents = r#"[replace]
+"a:0.1.0" = { path = "a" }
+"https://github.com/rust-lang/crates.io-index#b:0.2.0" = { path = "b" }
+"#;
+    let manifest = temp_manifest("replace", contents);
+
+    let patched = CargoFile::new(&manifest).unwrap().get_patched_packages();
+    assert_eq!(
+        patched,
+        vec![
+            ("replace".to_string(), "a".to_string()),
+            ("replace".to_string(), "b".to_string()),
+        ]
+    );
+
+    std::fs::remove_dir_all(manifest.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn get_version_errors_on_non_string_versions() {
+    for (name, version) in [("integer", "1"), ("bool", "true")] {
+        let manifest = temp_manifest(
+            &format!("invalid-{name}"),
+            &format!(
+                "[package]\nname = \"invalid\"\nversion = {version}\n\n[workspace.package]\nversion = {version}\n"
+            ),
+        );
+        let cargo_file = CargoFile::new(&manifest).unwrap();
+        for location in [VersionLocation::Package, VersionLocation::WorkspacePackage] {
+            let err = location.get_version(&cargo_file).unwrap_err();
+            assert!(
+                matches!(
+                    err.kind(),
+                    VersionLocationErrorKind::ItemInvalid(ItemType::Value)
+                ),
+                "{location}: {err:?}"
+            );
+        }
+        assert!(cargo_file.get_package_version().is_none());
+
+        std::fs::remove_dir_all(manifest.parent().unwrap()).unwrap();
+    }
+}
+
+#[test]
+fn get_version_errors_on_invalid_semver() {
+    let manifest = temp_manifest(
+        "invalid-semver",
+        "[package]\nname = \"invalid\"\nversion = \"not.semver\"\n",
+    );
+    let cargo_file = CargoFile::new(&manifest).unwrap();
+    let err = VersionLocation::Package
+        .get_version(&cargo_file)
+        .unwrap_err();
+    assert!(
+        matches!(err.kind(), VersionLocationErrorKind::SemverError(_)),
+        "{err:?}"
+    );
+
+    std::fs::remove_dir_all(manifest.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn write_cargo_file_is_never_observed_partially_written() {
+    let old = "[package]\nname = \"atomic\"\nversion = \"1.0.0\"\n";
+    let manifest = temp_manifest("atomic-write", old);
+    let new = old.replace("1.0.0", "2.0.0");
+
+    let done = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let reader = {
+        let manifest = manifest.clone();
+        let done = done.clone();
+        let (old, new) = (old.to_string(), new.clone());
+        std::thread::spawn(move || {
+            while !done.load(std::sync::atomic::Ordering::Relaxed) {
+                let contents = std::fs::read_to_string(&manifest).unwrap();
+                assert!(contents == old || contents == new, "{contents:?}");
+            }
+        })
+    };
+    let mut cargo_file = CargoFile::new(&manifest).unwrap();
+    for version in ["2.0.0", "1.0.0"].iter().cycle().take(200) {
+        cargo_file
+            .set_package_version(&version.parse().unwrap())
+            .unwrap();
+        cargo_file.write_cargo_file().unwrap();
+    }
+    done.store(true, std::sync::atomic::Ordering::Relaxed);
+    reader.join().unwrap();
+
+    // Only the manifest is left behind.
+    let dir = manifest.parent().unwrap();
+    assert_eq!(std::fs::read_dir(dir).unwrap().count(), 1);
+    assert_eq!(std::fs::read_to_string(&manifest).unwrap(), old);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn write_cargo_file_keeps_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let manifest = temp_manifest(
+        "atomic-permissions",
+        "[package]\nname = \"atomic\"\nversion = \"1.0.0\"\n",
+    );
+    std::fs::set_permissions(&manifest, std::fs::Permissions::from_mode(0o640)).unwrap();
+
+    let mut cargo_file = CargoFile::new(&manifest).unwrap();
+    cargo_file
+        .set_package_version(&"1.0.1".parse().unwrap())
+        .unwrap();
+    cargo_file.write_cargo_file().unwrap();
+
+    let mode = std::fs::metadata(&manifest).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o640);
+
+    std::fs::remove_dir_all(manifest.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn set_by_workspace_errors_name_the_workspace_manifest() {
+    let root_manifest = fixture("ws_inherit/Cargo.toml");
+    let cargo_file = CargoFile::new(fixture("ws_inherit/a/Cargo.toml")).unwrap();
+    let err = VersionLocation::Package
+        .get_version(&cargo_file)
+        .unwrap_err();
+    match err.kind() {
+        VersionLocationErrorKind::SetByWorkspace { workspace_manifest } => {
+            assert_eq!(workspace_manifest.as_deref(), Some(root_manifest.as_path()))
+        }
+        kind => panic!("{kind:?}"),
+    }
+
+    let mut packages = packages_from("ws_inherit/Cargo.toml");
+    let err = packages.write_cargo_file(&"a".into()).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains(&root_manifest.display().to_string()),
+        "{err}"
+    );
+}
+
+#[test]
+fn root_version_falls_back_to_unanimous_member_version() {
+    let packages = packages_from("pure_ws_unanimous/Cargo.toml");
+    assert!(packages.root_package_name_unchecked().is_none());
+    assert!(packages.workspace_package().is_none());
+    assert_eq!(packages.root_version().unwrap().to_string(), "0.4.0");
+}
+
+#[test]
+fn root_version_prefers_root_package_then_workspace_package() {
+    // The root package differs from the members.
+    let packages = packages_from("mixed_ws/Cargo.toml");
+    assert_eq!(packages.root_version().unwrap().to_string(), "0.1.2");
+    // No root package, members inherit workspace.package.version.
+    let packages = packages_from("ws_inherit/Cargo.toml");
+    assert!(packages.root_package_name_unchecked().is_none());
+    assert_eq!(packages.root_version().unwrap().to_string(), "0.3.0");
+}
+
+/// [Cli] for the `manifest` with the extra `args`.
+fn cli_for(manifest: &std::path::Path, args: &[&str]) -> Cli {
+    let args = ["cargo-uv", "--manifest-path", manifest.to_str().unwrap()]
+        .iter()
+        .chain(args)
+        .map(|s| s.to_string())
+        .collect();
+    Cli::cli_args(args, Some("cargo-uv"), None).unwrap()
+}
+
+#[test]
+fn package_metadata_config_is_overridden_by_flags() {
+    let manifest = temp_manifest(
+        "metadata-config",
+        "[package]\nname = \"a\"\nversion = \"0.1.0\"\n\n[package.metadata.uv]\ntag_prefix = \"v\"\nsuppress = \"git\"\n",
+    );
+
+    let cli = cli_for(&manifest, &[]);
+    assert_eq!(cli.tag_format(), "v{version}");
+    assert_eq!(cli.suppress(), Suppress::GIT);
+
+    let cli = cli_for(
+        &manifest,
+        &["--tag-prefix", "release-", "--suppress", "none"],
+    );
+    assert_eq!(cli.tag_format(), "release-{version}");
+    assert_eq!(cli.suppress(), Suppress::NONE);
+    // The prefix conflicts with '--tag-format', so isn't applied with it.
+    let cli = cli_for(&manifest, &["--tag-format", "{name}@{version}"]);
+    assert_eq!(cli.tag_format(), "{name}@{version}");
+
+    // uv.toml takes precedence over the manifest.
+    let uv_toml = manifest.with_file_name("uv.toml");
+    std::fs::write(&uv_toml, "tag_prefix = \"uv-\"\n").unwrap();
+    assert_eq!(cli_for(&manifest, &[]).tag_format(), "uv-{version}");
+
+    std::fs::remove_dir_all(manifest.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn json_error_format_reports_missing_manifest() {
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_cargo-uv"))
+        .args([
+            "uv",
+            "--manifest-path",
+            "/does/not/exist/Cargo.toml",
+            "--error-format",
+            "json",
+        ])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let report: serde_json::Value = serde_json::from_str(&stderr).unwrap_or_else(|e| {
+        panic!("{e}: {stderr}");
+    });
+    assert_eq!(report["code"], "ManifestNotFound");
+    assert_eq!(report["message"], "The manifest path does not exist.");
+    assert!(report["help"].as_str().unwrap().contains("--manifest-path"));
+}
+
+#[test]
+fn json_error_format_reports_invalid_arguments() {
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_cargo-uv"))
+        .args(["uv", "--error-format", "json", "--not-a-flag"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let report: serde_json::Value = serde_json::from_str(&stderr).unwrap_or_else(|e| {
+        panic!("{e}: {stderr}");
+    });
+    assert_eq!(report["code"], "Clap");
+    assert_eq!(
+        report["message"],
+        "unexpected argument '--not-a-flag' found"
+    );
+    assert!(report["help"].as_str().unwrap().contains("Usage:"));
+}
+
+/// Runs the binary for the fixture, returning the stdout.
+fn run_uv(manifest: &str, args: &[&str]) -> String {
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_cargo-uv"))
+        .args(["uv", "--manifest-path", fixture(manifest).to_str().unwrap()])
+        // The fixtures are inside this repository, which may be dirty.
+        .arg("--allow-dirty")
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn bare_version_plans_the_same_as_set() {
+    let implied = run_uv("simple/Cargo.toml", &["1.2.3", "--plan", "--git-tag"]);
+    let set = run_uv(
+        "simple/Cargo.toml",
+        &["set", "1.2.3", "--plan", "--git-tag"],
+    );
+    assert_eq!(implied, set);
+    assert!(implied.contains("1.2.3"), "{implied}");
+}
+
+#[test]
+fn quiet_keeps_the_printed_version_only() {
+    assert_eq!(
+        run_uv("simple/Cargo.toml", &["print", "-q"]),
+        "simple 0.1.11\n"
+    );
+    assert!(!run_uv("simple/Cargo.toml", &["tree"]).is_empty());
+    assert_eq!(run_uv("simple/Cargo.toml", &["tree", "-q"]), "");
+    assert_eq!(
+        run_uv("simple/Cargo.toml", &["print", "-q", "--display-tasks"]),
+        "simple 0.1.11\n"
+    );
+}
+
+#[test]
+fn plan_runs_outside_a_git_repository() {
+    let manifest = temp_manifest(
+        "plan",
+        "[package]\nname = \"planned\"\nversion = \"0.1.0\"\nedition = \"2024\"\n",
+    );
+    let dir = manifest.parent().unwrap();
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    std::fs::write(dir.join("src/lib.rs"), "").unwrap();
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_cargo-uv"))
+        .args(["uv", "--manifest-path", manifest.to_str().unwrap()])
+        .args([
+            "patch",
+            "--plan",
+            "--git-tag",
+            "--git-push",
+            "--cargo-publish",
+        ])
+        .env("GIT_CEILING_DIRECTORIES", dir.parent().unwrap())
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    for task in [
+        "Bump patch: planned -> 0.1.1",
+        "Git Tag: 0.1.1",
+        "Git Push: 0.1.1 to <remote>",
+        "Cargo Publish: planned",
+    ] {
+        assert!(stdout.contains(task), "{task} missing from {stdout}");
+    }
+    // Nothing was written.
+    assert!(
+        std::fs::read_to_string(&manifest)
+            .unwrap()
+            .contains("version = \"0.1.0\"")
+    );
+    assert!(!dir.join(".git").exists());
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn json_log_format_prints_an_object_per_line() {
+    let stdout = run_uv(
+        "simple/Cargo.toml",
+        &["print", "-vv", "--log-format", "json"],
+    );
+    let line = stdout
+        .lines()
+        .find(|line| line.contains("Tracing level"))
+        .unwrap_or_else(|| panic!("no tracing level log in {stdout}"));
+    let log: serde_json::Value = serde_json::from_str(line).unwrap();
+    assert_eq!(log["fields"]["message"], "Tracing level: INFO");
+    assert_eq!(log["target"], "cargo_uv");
+    assert_eq!(log["level"], "INFO");
+}
+
+#[test]
+fn set_version_from_env_and_stdin() {
+    let manifest = temp_manifest(
+        "set-version-input",
+        "[package]\nname = \"a\"\nversion = \"0.1.0\"\n\n[lib]\npath = \"lib.rs\"\n",
+    );
+    std::fs::write(manifest.with_file_name("lib.rs"), "").unwrap();
+    let run = |args: &[&str], stdin: &str| {
+        let mut child = std::process::Command::new(env!("CARGO_BIN_EXE_cargo-uv"))
+            .args(["uv", "--manifest-path", manifest.to_str().unwrap()])
+            .args(["--allow-dirty", "-q", "set"])
+            .args(args)
+            .env("RELEASE_VERSION", "0.2.0")
+            .env("BAD_VERSION", "v0.3")
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        std::io::Write::write_all(&mut child.stdin.take().unwrap(), stdin.as_bytes()).unwrap();
+        child.wait_with_output().unwrap()
+    };
+    let version = || {
+        CargoFile::new(&manifest)
+            .unwrap()
+            .get_package_version()
+            .unwrap()
+    };
+
+    assert!(
+        run(&["--set-version-from-env", "RELEASE_VERSION"], "")
+            .status
+            .success()
+    );
+    assert_eq!(version(), "0.2.0".parse().unwrap());
+
+    assert!(run(&["--set-version", "-"], "0.3.0\n").status.success());
+    assert_eq!(version(), "0.3.0".parse().unwrap());
+
+    let output = run(&["--set-version-from-env", "BAD_VERSION"], "");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("$BAD_VERSION"), "{stderr}");
+    assert_eq!(version(), "0.3.0".parse().unwrap());
+
+    std::fs::remove_dir_all(manifest.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn print_formats() {
+    assert_eq!(
+        run_uv(
+            "simple/Cargo.toml",
+            &["print", "--format", "{name}@{version}"]
+        ),
+        "simple@0.1.11\n"
+    );
+    assert_eq!(run_uv("simple/Cargo.toml", &["print", "--bare"]), "0.1.11");
+    assert_eq!(
+        run_uv("simple/Cargo.toml", &["print", "--format", "json"]),
+        "{\"name\":\"simple\",\"version\":\"0.1.11\"}\n"
+    );
+    let stdout = run_uv(
+        "pure_ws/Cargo.toml",
+        &["print", "--workspace", "--format", "json"],
+    );
+    let versions: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    let names: Vec<_> = versions
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|package| package["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["a", "b", "c"]);
+}
+
+#[test]
+fn mixed_pure_workspace_lists_member_versions() {
+    let packages = packages_from("pure_ws_mixed/Cargo.toml");
+    let err = packages.root_version().unwrap_err();
+    let help = miette::Diagnostic::help(&err).unwrap().to_string();
+    assert_eq!(
+        help,
+        "The members have differing versions:\n  a 0.1.0\n  b 0.2.0\nSelect one with `-p <name>`, or all of them with `--workspace`."
+    );
+}
+
+#[test]
+fn display_tree_member_outside_the_workspace_root() {
+    let root = std::env::temp_dir().join(format!("cargo-uv-outside-root-{}", std::process::id()));
+    let member = root.join("outside/member");
+    std::fs::create_dir_all(member.join("src")).unwrap();
+    std::fs::create_dir_all(root.join("ws")).unwrap();
+    std::fs::write(
+        root.join("ws/Cargo.toml"),
+        "[workspace]\nmembers = [\"../outside/member\"]\n",
+    )
+    .unwrap();
+    std::fs::write(
+        member.join("Cargo.toml"),
+        "[package]\nname = \"member\"\nversion = \"0.1.0\"\nworkspace = \"../../ws\"\n",
+    )
+    .unwrap();
+    std::fs::write(member.join("src/lib.rs"), "").unwrap();
+
+    let metadata = MetadataCommand::new()
+        .manifest_path(root.join("ws/Cargo.toml"))
+        .no_deps()
+        .exec()
+        .unwrap();
+    let tree = Packages::from(&metadata).display_tree();
+    assert!(
+        tree.contains(&format!("member 0.1.0 (package): {}", member.display())),
+        "{tree}"
+    );
+
+    std::fs::remove_dir_all(root).unwrap();
+}
@@ -0,0 +1,128 @@
+use std::{
+    path::PathBuf,
+    sync::{Mutex, MutexGuard, Once, PoisonError},
+};
+
+use cargo_uv::{Cli, Packages, Task, Tasks};
+
+static FAKE_GIT: Once = Once::new();
+/// Held for the whole of each test, so no other thread spawns a process while `PATH` is set.
+static SERIAL: Mutex<()> = Mutex::new(());
+
+/// Puts a fake `git` first on the `PATH`, which fakes `git push` based on the remote:
+///
+/// - `flaky`: fails twice with a transient error then succeeds.
+/// - `auth`: always fails with an authentication error.
+///
+/// Each push is appended to `<shim dir>/<remote>.count`. The returned guard serialises the
+/// tests, keep it until the test ends.
+fn fake_git() -> (MutexGuard<'static, ()>, PathBuf) {
+    let serial = SERIAL.lock().unwrap_or_else(PoisonError::into_inner);
+    let dir = std::env::temp_dir().join(format!("cargo-uv-fake-git-{}", std::process::id()));
+    FAKE_GIT.call_once(|| {
+        let real_git = std::process::Command::new("sh")
+            .args(["-c", "command -v git"])
+            .output()
+            .unwrap();
+        let real_git = String::from_utf8(real_git.stdout).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let script = format!(
+            r#"#!/bin/sh
+if [ "$3" = "push" ]; then
+    remote="$4"
+    echo push >> "{dir}/$remote.count"
+    count=$(wc -l < "{dir}/$remote.count")
+    if [ "$remote" = "auth" ]; then
+        echo "fatal: Authentication failed for 'https://example.com/repo.git/'" >&2
+        exit 128
+    fi
+    if [ "$count" -le 2 ]; then
+        echo "fatal: unable to access 'https://example.com/repo.git/': Could not resolve host: example.com" >&2
+        exit 128
+    fi
+    echo Done
+    exit 0
+fi
+exec {real_git} "$@"
+"#,
+            dir = dir.display(),
+            real_git = real_git.trim()
+        );
+        let shim = dir.join("git");
+        std::fs::write(&shim, script).unwrap();
+        std::process::Command::new("chmod")
+            .arg("+x")
+            .arg(&shim)
+            .status()
+            .unwrap();
+        let path = std::env::var_os("PATH").unwrap_or_default();
+        let mut paths = vec![dir.clone()];
+        paths.extend(std::env::split_paths(&path));
+        // SAFETY: Only set once while holding `SERIAL`, which every test holds before spawning
+        // any process, so no other thread reads the environment concurrently.
+        unsafe { std::env::set_var("PATH", std::env::join_paths(paths).unwrap()) };
+    });
+    (serial, dir)
+}
+
+fn push_tasks(remote: &str) -> (Cli, Tasks) {
+    let manifest =
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/simple/Cargo.toml");
+    let args = [
+        "cargo-uv",
+        "--manifest-path",
+        manifest.to_str().unwrap(),
+        "patch",
+        "--git-push",
+        "--push-retries",
+        "3",
+        "--push-retry-delay",
+        "1",
+    ]
+    .map(String::from)
+    .to_vec();
+    let mut cli = Cli::cli_args(args, Some("cargo-uv"), None).unwrap();
+    let packages = Packages::from(cli.get_metadata().unwrap());
+    let mut tasks = Tasks::new(packages);
+    tasks.insert(
+        Task::GitPush {
+            remote: remote.to_string(),
+            branch: cargo_uv::Branch::Current,
+            tag: Some("0.1.11".to_string()),
+        },
+        None,
+    );
+    (cli, tasks)
+}
+
+fn push_count(dir: &std::path::Path, remote: &str) -> usize {
+    std::fs::read_to_string(dir.join(format!("{remote}.count")))
+        .unwrap()
+        .lines()
+        .count()
+}
+
+#[test]
+fn push_retries_transient_failures() {
+    let (_serial, dir) = fake_git();
+    let (cli, tasks) = push_tasks("flaky");
+
+    let tasks = tasks.run_all(&cli).unwrap().join_all(&cli).unwrap();
+    assert_eq!(tasks.completed_tasks().len(), 1);
+    assert_eq!(push_count(&dir, "flaky"), 3);
+}
+
+#[test]
+fn push_does_not_retry_auth_failures() {
+    let (_serial, dir) = fake_git();
+    let (cli, tasks) = push_tasks("auth");
+
+    let err = tasks.run_all(&cli).unwrap().join_all(&cli).unwrap_err();
+    assert!(
+        err.output.contains("Authentication failed"),
+        "{}",
+        err.output
+    );
+    assert_eq!(push_count(&dir, "auth"), 1);
+}